@@ -1,9 +1,14 @@
 #include "llvm/Passes/PassPlugin.h"
 #include "llvm/Passes/PassBuilder.h"
+#include "llvm/IR/DebugInfoMetadata.h"
 #include "llvm/IR/Module.h"
 #include "llvm/IR/PassManager.h"
+#include "llvm/Support/FileSystem.h"
+#include "llvm/Support/JSON.h"
+#include "llvm/Support/Path.h"
 #include "llvm/Support/raw_ostream.h"
 #include <chrono>
+#include <cstdlib>
 
 // SVF Headers
 #include "SVF-LLVM/LLVMModule.h"
@@ -44,6 +49,126 @@ static Value* stripCasts(Value* V) {
     return V;
 }
 
+// Argument layout of the alias check hooks; false for any other callee.
+// Signature: void __svf_check_alias(i8* p, i8* q, i32 id)
+// Sized:     void __svf_check_alias_sized(i8* p, i64 p_size, i8* q, i64 q_size, i32 id)
+// V2:        void __svf_check_alias_v2(i8* p, i8* q, i64 id, i8 prediction)
+static bool aliasCheckArgs(StringRef Name, unsigned &qArg, unsigned &idArg, bool &isV2) {
+    qArg = 1;
+    idArg = 2;
+    isV2 = Name == "__svf_check_alias_v2";
+    if (Name == "__svf_check_alias_sized") {
+        qArg = 2;
+        idArg = 4;
+    }
+    return isV2 || Name == "__svf_check_alias" || Name == "__svf_check_alias_sized";
+}
+
+// Prediction byte of __svf_check_alias_v2, mirroring svf_runtime::alias::Prediction.
+// Spelled out rather than cast so a reordered AliasResult cannot shift the encoding;
+// the runtime counts any other byte as no info.
+static uint8_t predictionByte(AliasResult res) {
+    switch (res) {
+    case NoAlias: return 0;
+    case MayAlias: return 1;
+    case MustAlias: return 2;
+    case PartialAlias: return 3;
+    }
+    return 0xff;
+}
+
+// The load or store a heap access hook guards: the next one in the block whose
+// pointer operand (casts stripped) is the hook's pointer.
+static Instruction* guardedAccess(CallInst *CI, Value *Ptr) {
+    for (Instruction *I = CI->getNextNode(); I; I = I->getNextNode()) {
+        Value *Op = nullptr;
+        if (auto *LI = llvm::dyn_cast<LoadInst>(I)) Op = LI->getPointerOperand();
+        else if (auto *SI = llvm::dyn_cast<StoreInst>(I)) Op = SI->getPointerOperand();
+        if (Op && stripCasts(Op) == Ptr) return I;
+    }
+    return nullptr;
+}
+
+// Write svf_access_ids_<module>.json (into $SVF_ACCESS_MAP_DIR, default the
+// working directory): for every __svf_check_heap_access[_sized] call, its
+// access id and the load or store it guards. The pts dumps' unsafe_ptrs do not
+// record access ids; svf_pts::access_map joins them through this map.
+// Signature: void __svf_check_heap_access(i8* ptr, i1 is_load, i64 access_id)
+// Sized:     void __svf_check_heap_access_sized(i8* ptr, i64 len, i1 is_load, i64 access_id)
+static void writeAccessMap(Module &M) {
+    std::string Text;
+    raw_string_ostream OS(Text);
+    json::OStream J(OS);
+    unsigned mapped = 0, unmapped = 0;
+    J.objectBegin();
+    J.attribute("module", M.getModuleIdentifier());
+    J.attributeBegin("accesses");
+    J.arrayBegin();
+    for (Function &F : M) {
+        for (BasicBlock &BB : F) {
+            for (Instruction &I : BB) {
+                CallInst *CI = llvm::dyn_cast<CallInst>(&I);
+                Function *CalledFn = CI ? CI->getCalledFunction() : nullptr;
+                if (!CalledFn) continue;
+                unsigned first;
+                if (CalledFn->getName() == "__svf_check_heap_access") first = 1;
+                else if (CalledFn->getName() == "__svf_check_heap_access_sized") first = 2;
+                else continue;
+                if (CI->arg_size() <= first + 1) continue;
+
+                ConstantInt *IsLoad = llvm::dyn_cast<ConstantInt>(CI->getArgOperand(first));
+                ConstantInt *ID = llvm::dyn_cast<ConstantInt>(CI->getArgOperand(first + 1));
+                Instruction *Access = guardedAccess(CI, stripCasts(CI->getArgOperand(0)));
+                if (!IsLoad || !ID || !Access) {
+                    unmapped++;
+                    continue;
+                }
+
+                std::string Inst;
+                raw_string_ostream InstOS(Inst);
+                Access->print(InstOS);
+                J.objectBegin();
+                J.attribute("access_id", ID->getZExtValue());
+                J.attribute("is_load", !IsLoad->isZero());
+                J.attribute("function", F.getName());
+                J.attribute("instruction", InstOS.str());
+                if (const DILocation *Loc = Access->getDebugLoc().get()) {
+                    SmallString<256> File(Loc->getFilename());
+                    if (!sys::path::is_absolute(File)) {
+                        File = Loc->getDirectory();
+                        sys::path::append(File, Loc->getFilename());
+                    }
+                    J.attributeObject("source_loc", [&] {
+                        J.attribute("file", File.str());
+                        J.attribute("line", (int64_t)Loc->getLine());
+                        J.attribute("col", (int64_t)Loc->getColumn());
+                    });
+                }
+                J.objectEnd();
+                mapped++;
+            }
+        }
+    }
+    J.arrayEnd();
+    J.attributeEnd();
+    J.objectEnd();
+
+    SmallString<256> Path;
+    if (const char *Dir = std::getenv("SVF_ACCESS_MAP_DIR")) Path = Dir;
+    std::string Name = sys::path::filename(M.getModuleIdentifier()).str();
+    sys::path::append(Path, "svf_access_ids_" + Name + ".json");
+    std::error_code EC;
+    raw_fd_ostream Out(Path, EC, sys::fs::OF_Text);
+    if (EC) {
+        errs() << "[SVF-LTO] Could not write " << Path << ": " << EC.message() << "\n";
+        return;
+    }
+    Out << OS.str() << "\n";
+    errs() << "[SVF-LTO] Wrote " << mapped << " heap accesses to " << Path;
+    if (unmapped) errs() << " (" << unmapped << " hooks without a guarded load/store skipped)";
+    errs() << "\n";
+}
+
 // -----------------------------------------------------------------------------
 // SVF LTO Pass
 // -----------------------------------------------------------------------------
@@ -89,16 +214,18 @@ struct SvfLtoPass : public PassInfoMixin<SvfLtoPass> {
                 for (Instruction &I : BB) {
                     if (CallInst *CI = llvm::dyn_cast<CallInst>(&I)) {
                         Function *CalledFn = CI->getCalledFunction();
-                        if (CalledFn && CalledFn->getName() == "__svf_check_alias") {
-                            // Signature: void __svf_check_alias(i8* p, i8* q, i32 id)
-                            if (CI->arg_size() < 3) continue;
+                        unsigned qArg, idArg;
+                        bool isV2;
+                        if (CalledFn && aliasCheckArgs(CalledFn->getName(), qArg, idArg, isV2)) {
+                            // Signature: see aliasCheckArgs
+                            if (CI->arg_size() <= (isV2 ? 3u : idArg)) continue;
 
                             Value *P = stripCasts(CI->getArgOperand(0));
-                            Value *Q = stripCasts(CI->getArgOperand(1));
+                            Value *Q = stripCasts(CI->getArgOperand(qArg));
                             
-                            // Get ID (Argument 2)
+                            // Get ID (Argument idArg)
                             uint64_t ID = 0;
-                            if (ConstantInt *C = llvm::dyn_cast<ConstantInt>(CI->getArgOperand(2))) {
+                            if (ConstantInt *C = llvm::dyn_cast<ConstantInt>(CI->getArgOperand(idArg))) {
                                 ID = C->getZExtValue();
                             }
 
@@ -144,16 +271,20 @@ struct SvfLtoPass : public PassInfoMixin<SvfLtoPass> {
                             // Format: ID:<id> RES:<1|0>
                             // outs() << "ID:" << ID << " RES:" << (isAlias ? "1" : "0") << "\n";
 
-                            // Inject Analysis Result into ID (Argument 2)
-                            // Top bit (31) = 1 if Alias (Predicted), 0 if NoAlias
-                            if (isAlias) {
+                            if (isV2) {
+                                // V2: pass the full AliasResult (NoAlias/MayAlias/MustAlias/PartialAlias)
+                                // in the prediction argument, the 64-bit ID is left untouched.
+                                CI->setArgOperand(3, ConstantInt::get(Type::getInt8Ty(M.getContext()), predictionByte(res)));
+                            } else if (isAlias) {
+                                // Inject Analysis Result into ID (Argument idArg)
+                                // Top bit (31) = 1 if Alias (Predicted), 0 if NoAlias
                                 uint32_t newID = (uint32_t)ID | (1 << 31);
-                                CI->setArgOperand(2, ConstantInt::get(Type::getInt32Ty(M.getContext()), newID));
+                                CI->setArgOperand(idArg, ConstantInt::get(Type::getInt32Ty(M.getContext()), newID));
                             } else {
                                 // Ensure top bit is 0 just in case (though likely already is)
                                 // If the ID uses top bit, we are in trouble, but assuming standard u32 IDs.
                                 uint32_t newID = (uint32_t)ID & ~(1 << 31);
-                                CI->setArgOperand(2, ConstantInt::get(Type::getInt32Ty(M.getContext()), newID));
+                                CI->setArgOperand(idArg, ConstantInt::get(Type::getInt32Ty(M.getContext()), newID));
                             }
 
                             // Debugging Info
@@ -180,6 +311,9 @@ struct SvfLtoPass : public PassInfoMixin<SvfLtoPass> {
 
         errs() << "[SVF-LTO] Processed " << checkCount << " alias checks.\n";
 
+        // 5. Map heap access ids to the accesses they guard
+        writeAccessMap(M);
+
         // Cleanup
         // In a real plugin we might want to keep generic graphs, but here we are done.
         // LLVMModuleSet::releaseLLVMModuleSet(); 
@@ -12,6 +12,21 @@ using namespace llvm;
 using namespace std;
 using namespace SVF;
 
+// Argument layout of the alias check hooks, as in the LTO plugin; false for any
+// other callee.
+// Signature: void __svf_check_alias(i8* p, i8* q, i32 id)
+// Sized:     void __svf_check_alias_sized(i8* p, i64 p_size, i8* q, i64 q_size, i32 id)
+// V2:        void __svf_check_alias_v2(i8* p, i8* q, i64 id, i8 prediction)
+static bool aliasCheckArgs(StringRef Name, unsigned &qArg, unsigned &idArg) {
+    qArg = 1;
+    idArg = 2;
+    if (Name == "__svf_check_alias_sized") {
+        qArg = 2;
+        idArg = 4;
+    }
+    return Name == "__svf_check_alias" || Name == "__svf_check_alias_sized" || Name == "__svf_check_alias_v2";
+}
+
 int main(int argc, char **argv) {
     std::vector<std::string> moduleNameVec;
     moduleNameVec = OptionBase::parseOptions(argc, argv, "SVF Static Checker", "[options] <input-bitcode...>");
@@ -35,13 +50,13 @@ int main(int argc, char **argv) {
 
     outs() << "SVF Analysis Done. Checking instrumentation points...\n";
 
-    // 4. Iterate over the module to find __svf_check_alias calls
+    // 4. Iterate over the module to find __svf_check_alias[_sized|_v2] calls
     // We assume there's one module loaded
     Module* mod = moduleSet->getModule(0); 
 
-    // Find the function declaration for __svf_check_alias to easily identify calls
-    Function* checkFn = mod->getFunction("__svf_check_alias");
-    if (!checkFn) {
+    // Find the function declarations to check the module is instrumented at all
+    if (!mod->getFunction("__svf_check_alias") && !mod->getFunction("__svf_check_alias_sized") &&
+        !mod->getFunction("__svf_check_alias_v2")) {
         outs() << "Warning: __svf_check_alias function not found in bitcode. Is it instrumented?\n";
         return 0;
     }
@@ -50,11 +65,13 @@ int main(int argc, char **argv) {
         for (BasicBlock& BB : F) {
             for (Instruction& I : BB) {
                 if (CallInst* CI = dyn_cast<CallInst>(&I)) {
-                    if (CI->getCalledFunction() == checkFn) {
-                        // Signature: void __svf_check_alias(i8* p, i8* q, i32 id)
+                    Function* calledFn = CI->getCalledFunction();
+                    unsigned qArg, idArg;
+                    if (calledFn && aliasCheckArgs(calledFn->getName(), qArg, idArg) && CI->arg_size() > idArg) {
+                        // Signature: see aliasCheckArgs
                         Value* argP = CI->getArgOperand(0);
-                        Value* argQ = CI->getArgOperand(1);
-                        Value* argID = CI->getArgOperand(2);
+                        Value* argQ = CI->getArgOperand(qArg);
+                        Value* argID = CI->getArgOperand(idArg);
 
                         // Extract ID
                         uint64_t idVal = 0;
@@ -0,0 +1,70 @@
+//! the lto plugin's access maps (`svf_access_ids_<module>.json`).
+//!
+//! the pts dumps' `unsafe_ptrs` do not record the access id the instrumentation
+//! passes to `__svf_check_heap_access[_sized]`, so the plugin writes one record
+//! per hook call instead: the id and the load or store the call guards. the
+//! records are joined with the dumps by
+//! [`WholeProgram::join_access_ids`](crate::merge::WholeProgram::join_access_ids),
+//! on the function, instruction and source location both sides have.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::model::SourceLoc;
+use crate::validate::Error;
+
+/// one instrumented access.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AccessRecord {
+    pub access_id: u64,
+    pub is_load: bool,
+    /// mangled name of the enclosing function.
+    pub function: String,
+    /// the guarded load or store.
+    pub instruction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+}
+
+/// a whole access map.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AccessMap {
+    pub module: String,
+    /// in instruction order.
+    pub accesses: Vec<AccessRecord>,
+}
+
+/// parse an access map; access ids must be unique.
+pub fn parse(text: &str) -> Result<AccessMap, Error> {
+    let map: AccessMap = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(text))?;
+    let mut seen: HashMap<u64, usize> = HashMap::new();
+    for (i, record) in map.accesses.iter().enumerate() {
+        if let Some(j) = seen.insert(record.access_id, i) {
+            return Err(Error::schema(
+                format!("accesses[{}].access_id", i),
+                format!("duplicate access_id {}, first at accesses[{}]", record.access_id, j),
+            ));
+        }
+    }
+    Ok(map)
+}
+
+/// read and parse the access map at `path`.
+pub fn load(path: &Path) -> Result<AccessMap, Error> {
+    let text = std::fs::read_to_string(path).map_err(|e| Error::io(e).in_file(path.to_path_buf()))?;
+    parse(&text).map_err(|e| e.in_file(path.to_path_buf()))
+}
+
+/// whether `path` is named like an access map.
+pub fn is_access_map(path: &Path) -> bool {
+    path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with(PREFIX) && n.ends_with(".json"))
+}
+
+/// every access map in the directory `path`, sorted.
+pub fn files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    crate::json_files(path, PREFIX)
+}
+
+const PREFIX: &str = "svf_access_ids_";
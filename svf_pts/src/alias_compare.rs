@@ -0,0 +1,207 @@
+//! per-check comparison of the runtime's alias ground truth with `svf-checker`.
+//!
+//! both sides are keyed by check id, the id the instrumentation passes to
+//! `__svf_check_alias` (without the prediction bit):
+//! - runtime: the `alias.checks` table of the json report (see `report`).
+//!   a check is an alias if any of its executions aliased under the chosen
+//!   [`GroundTruth`]. [`parse_id_lines`] also reads legacy `ID:<id> RES:<n>`
+//!   lines, with the same any-execution rule.
+//! - static: `svf-checker`'s `ID:<id> RES:<0|1>` lines; the last line of an id wins.
+//!
+//! only ids present on both sides enter the confusion matrix, "positive" being
+//! "alias".
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead};
+
+pub use crate::report::RuntimeCheck;
+use crate::validate::Error;
+
+/// runtime notion of "the two pointers alias", as in `svf_runtime::alias`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroundTruth {
+    Exact,
+    SameObject,
+    Overlap,
+}
+
+impl GroundTruth {
+    pub const ALL: [GroundTruth; 3] = [GroundTruth::Exact, GroundTruth::SameObject, GroundTruth::Overlap];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroundTruth::Exact => "exact",
+            GroundTruth::SameObject => "same_object",
+            GroundTruth::Overlap => "overlap",
+        }
+    }
+}
+
+impl RuntimeCheck {
+    /// executions that aliased under `truth`.
+    pub fn aliased_under(&self, truth: GroundTruth) -> u64 {
+        match truth {
+            GroundTruth::Exact => self.aliased,
+            GroundTruth::SameObject => self.aliased_same_object,
+            GroundTruth::Overlap => self.aliased_overlap,
+        }
+    }
+}
+
+/// the per-check table of a runtime json report, see [`crate::report::parse`].
+pub fn parse_runtime_report(text: &str) -> Result<Vec<RuntimeCheck>, Error> {
+    Ok(crate::report::parse(text)?.alias.checks)
+}
+
+/// `ID:<id> RES:<n>` lines. with `any`, an id is 1 if any of its lines is 1
+/// (runtime logs); otherwise its last line wins (checker output).
+pub fn parse_id_lines(log: impl BufRead, any: bool) -> io::Result<BTreeMap<u64, bool>> {
+    let mut results = BTreeMap::new();
+    for line in log.lines() {
+        let line = line?;
+        let Some((id, res)) = parse_id_line(&line) else { continue };
+        let entry = results.entry(id).or_insert(res);
+        *entry = if any { *entry || res } else { res };
+    }
+    Ok(results)
+}
+
+fn parse_id_line(line: &str) -> Option<(u64, bool)> {
+    let rest = &line[line.find("ID:")? + 3..];
+    let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    let id = rest[..digits].parse().ok()?;
+    let res = rest[digits..].trim_start().strip_prefix("RES:")?;
+    let res_digits = res.find(|c: char| !c.is_ascii_digit()).unwrap_or(res.len());
+    Some((id, res[..res_digits].parse::<u64>().ok()? != 0))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    TruePositive,
+    TrueNegative,
+    /// the checker says alias, the run never saw one.
+    FalsePositive,
+    /// the run saw an alias the checker missed: unsound.
+    FalseNegative,
+}
+
+impl Verdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Verdict::TruePositive => "TP",
+            Verdict::TrueNegative => "TN",
+            Verdict::FalsePositive => "FP",
+            Verdict::FalseNegative => "FN",
+        }
+    }
+}
+
+/// one check id present on both sides.
+#[derive(Clone, Debug)]
+pub struct CheckRow {
+    pub id: u64,
+    pub runtime_alias: bool,
+    pub static_alias: bool,
+    /// the runtime report's entry, when the runtime side is a report.
+    pub runtime: Option<RuntimeCheck>,
+}
+
+impl CheckRow {
+    pub fn verdict(&self) -> Verdict {
+        match (self.runtime_alias, self.static_alias) {
+            (true, true) => Verdict::TruePositive,
+            (false, false) => Verdict::TrueNegative,
+            (false, true) => Verdict::FalsePositive,
+            (true, false) => Verdict::FalseNegative,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metrics {
+    pub tp: u64,
+    pub tn: u64,
+    pub fp: u64,
+    pub fn_: u64,
+}
+
+fn ratio(num: u64, den: u64) -> Option<f64> {
+    (den > 0).then(|| num as f64 / den as f64)
+}
+
+impl Metrics {
+    pub fn total(&self) -> u64 {
+        self.tp + self.tn + self.fp + self.fn_
+    }
+
+    pub fn accuracy(&self) -> Option<f64> {
+        ratio(self.tp + self.tn, self.total())
+    }
+
+    /// how many predicted aliases were real.
+    pub fn precision(&self) -> Option<f64> {
+        ratio(self.tp, self.tp + self.fp)
+    }
+
+    /// how many real aliases were predicted.
+    pub fn recall(&self) -> Option<f64> {
+        ratio(self.tp, self.tp + self.fn_)
+    }
+
+    pub fn f1(&self) -> Option<f64> {
+        let (p, r) = (self.precision()?, self.recall()?);
+        (p + r > 0.0).then(|| 2.0 * p * r / (p + r))
+    }
+}
+
+/// the joined per-id table.
+#[derive(Clone, Debug, Default)]
+pub struct Comparison {
+    /// sorted by id.
+    pub rows: Vec<CheckRow>,
+    /// ids only the runtime saw: checks the checker did not find.
+    pub runtime_only: Vec<u64>,
+    /// ids only the checker saw: checks that never executed.
+    pub static_only: Vec<u64>,
+}
+
+impl Comparison {
+    /// join a runtime report with the checker output.
+    pub fn from_report(checks: &[RuntimeCheck], truth: GroundTruth, checker: &BTreeMap<u64, bool>) -> Self {
+        let runtime: BTreeMap<u64, (bool, Option<RuntimeCheck>)> =
+            checks.iter().map(|c| (c.id, (c.aliased_under(truth) > 0, Some(c.clone())))).collect();
+        Self::join(runtime, checker)
+    }
+
+    /// join legacy runtime `ID:<id> RES:<n>` results with the checker output.
+    pub fn from_id_lines(runtime: &BTreeMap<u64, bool>, checker: &BTreeMap<u64, bool>) -> Self {
+        Self::join(runtime.iter().map(|(&id, &alias)| (id, (alias, None))).collect(), checker)
+    }
+
+    fn join(runtime: BTreeMap<u64, (bool, Option<RuntimeCheck>)>, checker: &BTreeMap<u64, bool>) -> Self {
+        let mut comparison = Comparison {
+            static_only: checker.keys().copied().filter(|id| !runtime.contains_key(id)).collect(),
+            ..Default::default()
+        };
+        for (id, (runtime_alias, check)) in runtime {
+            match checker.get(&id) {
+                Some(&static_alias) => comparison.rows.push(CheckRow { id, runtime_alias, static_alias, runtime: check }),
+                None => comparison.runtime_only.push(id),
+            }
+        }
+        comparison
+    }
+
+    pub fn metrics(&self) -> Metrics {
+        let mut m = Metrics::default();
+        for row in &self.rows {
+            match row.verdict() {
+                Verdict::TruePositive => m.tp += 1,
+                Verdict::TrueNegative => m.tn += 1,
+                Verdict::FalsePositive => m.fp += 1,
+                Verdict::FalseNegative => m.fn_ += 1,
+            }
+        }
+        m
+    }
+}
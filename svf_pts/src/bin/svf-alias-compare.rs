@@ -0,0 +1,157 @@
+//! score svf's alias predictions against a run, per check id.
+//!
+//! ```text
+//! svf-alias-compare [--truth exact|same_object|overlap] [--min-recall R] [--all]
+//!                   <runtime report.json | runtime log> <svf-checker output>
+//! ```
+//!
+//! the runtime side is the json report (`SVF_REPORT_PATH`) or a log of legacy
+//! `ID:<id> RES:<n>` lines; see `svf_pts::alias_compare`. prints the confusion
+//! matrix, precision / recall / F1 and the wrongly predicted checks (`--all`:
+//! every check). checks that ran but are missing from the checker output are
+//! not scored and are warned about. exits with 1 when recall is below
+//! `--min-recall`, a fraction in `[0, 1]`, and with 2 on bad input.
+
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use svf_pts::alias_compare::{self, Comparison, GroundTruth, Verdict};
+
+const USAGE: &str = "usage: svf-alias-compare [--truth exact|same_object|overlap] [--min-recall R] [--all] \
+                     <runtime report.json | runtime log> <svf-checker output>";
+
+struct Options {
+    truth: GroundTruth,
+    min_recall: Option<f64>,
+    all: bool,
+    runtime: PathBuf,
+    checker: PathBuf,
+}
+
+fn parse_args() -> Option<Options> {
+    let mut truth = GroundTruth::Exact;
+    let mut min_recall = None;
+    let mut all = false;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--truth") => {
+                let name = args.next()?;
+                truth = *GroundTruth::ALL.iter().find(|t| name.to_str() == Some(t.as_str()))?;
+            }
+            Some("--min-recall") => {
+                let r: f64 = args.next()?.to_str()?.parse().ok()?;
+                if !(0.0..=1.0).contains(&r) {
+                    return None;
+                }
+                min_recall = Some(r);
+            }
+            Some("--all") => all = true,
+            _ => paths.push(arg.into()),
+        }
+    }
+    let [runtime, checker]: [PathBuf; 2] = paths.try_into().ok()?;
+    Some(Options { truth, min_recall, all, runtime, checker })
+}
+
+fn main() -> ExitCode {
+    if std::env::args().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let Some(options) = parse_args() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let checker = match File::open(&options.checker).and_then(|f| alias_compare::parse_id_lines(BufReader::new(f), false)) {
+        Ok(checker) => checker,
+        Err(e) => return fail(format!("{}: {}", options.checker.display(), e)),
+    };
+    let text = match std::fs::read_to_string(&options.runtime) {
+        Ok(text) => text,
+        Err(e) => return fail(format!("{}: {}", options.runtime.display(), e)),
+    };
+    let comparison = if text.trim_start().starts_with('{') {
+        match alias_compare::parse_runtime_report(&text) {
+            Ok(checks) => Comparison::from_report(&checks, options.truth, &checker),
+            Err(e) => return fail(format!("{}: {}", options.runtime.display(), e)),
+        }
+    } else {
+        // the legacy lines carry `p == q` only.
+        let runtime = alias_compare::parse_id_lines(text.as_bytes(), true).unwrap();
+        Comparison::from_id_lines(&runtime, &checker)
+    };
+
+    if !comparison.runtime_only.is_empty() {
+        eprintln!(
+            "svf-alias-compare: warning: {} checks ran but are not in the checker output and are not scored; \
+             is svf-checker older than the hooks the program calls?",
+            comparison.runtime_only.len()
+        );
+    }
+    let recall = comparison.metrics().recall();
+    if let Err(e) = write_report(&mut io::stdout().lock(), &comparison, &options) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return fail(e);
+        }
+    }
+    match (options.min_recall, recall) {
+        (Some(min), Some(recall)) if recall < min => {
+            eprintln!("svf-alias-compare: recall {:.4} is below {:.4}", recall, min);
+            ExitCode::FAILURE
+        }
+        _ => ExitCode::SUCCESS,
+    }
+}
+
+fn write_report(out: &mut dyn Write, comparison: &Comparison, options: &Options) -> io::Result<()> {
+    let m = comparison.metrics();
+    let metric = |v: Option<f64>| v.map_or(String::from("n/a"), |v| format!("{:.4}", v));
+    writeln!(out, "=== svf-alias-compare ({} ground truth) ===", options.truth.as_str())?;
+    writeln!(out, "checks compared: {}", m.total())?;
+    writeln!(out, "  only in the run: {}  only in the checker output: {}", comparison.runtime_only.len(), comparison.static_only.len())?;
+    writeln!(out, "True Positives  (Both Alias):    {}", m.tp)?;
+    writeln!(out, "True Negatives  (Both NoAlias):  {}", m.tn)?;
+    writeln!(out, "False Positives (Static Over):   {} (imprecision)", m.fp)?;
+    writeln!(out, "False Negatives (Static Under):  {} (unsoundness)", m.fn_)?;
+    writeln!(out, "Accuracy:  {}", metric(m.accuracy()))?;
+    writeln!(out, "Precision: {}", metric(m.precision()))?;
+    writeln!(out, "Recall:    {}", metric(m.recall()))?;
+    writeln!(out, "F1 Score:  {}", metric(m.f1()))?;
+
+    let mut rows: Vec<_> = comparison
+        .rows
+        .iter()
+        .filter(|r| options.all || matches!(r.verdict(), Verdict::FalseNegative | Verdict::FalsePositive))
+        .collect();
+    if rows.is_empty() {
+        return Ok(());
+    }
+    // unsound checks first.
+    rows.sort_by_key(|r| (std::cmp::Reverse(r.verdict() == Verdict::FalseNegative), r.verdict(), r.id));
+    writeln!(out, "\nper check:")?;
+    for row in rows {
+        write!(out, "  {} id {}: checker {}", row.verdict().as_str(), row.id, if row.static_alias { "alias" } else { "no alias" })?;
+        match &row.runtime {
+            Some(check) => {
+                write!(out, ", {} hits, {} aliased ({})", check.hits, check.aliased_under(options.truth), options.truth.as_str())?;
+                // the plugin's compiled-in prediction should match the checker.
+                if (check.prediction != "no_alias") != row.static_alias {
+                    write!(out, ", plugin predicted {}", check.prediction)?;
+                }
+                writeln!(out)?;
+            }
+            None => writeln!(out, ", run {}", if row.runtime_alias { "alias" } else { "no alias" })?,
+        }
+    }
+    Ok(())
+}
+
+fn fail(e: impl std::fmt::Display) -> ExitCode {
+    eprintln!("svf-alias-compare: {}", e);
+    ExitCode::from(2)
+}
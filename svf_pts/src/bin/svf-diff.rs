@@ -0,0 +1,214 @@
+//! compare the unsafe heap accuracy of two runs, per access id and per site.
+//!
+//! ```text
+//! svf-diff [--dumps-before <dump, access map or directory>]... [--dumps-after <...>]...
+//!          [--max-drop PP] [--all] <before report.json> <after report.json>
+//! ```
+//!
+//! the reports are the runtime's json reports (`SVF_REPORT_PATH`) of the two
+//! runs. with the dumps of both builds, sites are matched by their merged site
+//! key instead of the node id; with their access maps (`svf_access_ids_*.json`,
+//! also picked up from the directories) too, regressed accesses show their
+//! location and the heap targets they lost; see `svf_pts::diff`. prints the precision / recall deltas and the
+//! regressions and improvements (`--all`: every change). exits with 1 when an
+//! access or site regressed or precision or recall dropped by more than
+//! `--max-drop` percentage points, and with 2 on bad input.
+
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use svf_pts::diff::{self, Change, RunDiff, SiteRef};
+use svf_pts::merge::{SiteKey, WholeProgram};
+use svf_pts::report;
+
+const USAGE: &str = "usage: svf-diff [--dumps-before <dump, access map or directory>]... \
+                     [--dumps-after <dump, access map or directory>]... \
+                     [--max-drop PP] [--all] <before report.json> <after report.json>";
+
+struct Options {
+    dumps_before: Vec<PathBuf>,
+    dumps_after: Vec<PathBuf>,
+    max_drop: Option<f64>,
+    all: bool,
+    before: PathBuf,
+    after: PathBuf,
+}
+
+fn parse_args() -> Option<Options> {
+    let mut dumps_before = Vec::new();
+    let mut dumps_after = Vec::new();
+    let mut max_drop = None;
+    let mut all = false;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--dumps-before") => dumps_before.push(args.next()?.into()),
+            Some("--dumps-after") => dumps_after.push(args.next()?.into()),
+            Some("--max-drop") => {
+                let pp: f64 = args.next()?.to_str()?.parse().ok()?;
+                if pp.is_nan() || pp < 0.0 {
+                    return None;
+                }
+                max_drop = Some(pp);
+            }
+            Some("--all") => all = true,
+            _ => paths.push(arg.into()),
+        }
+    }
+    // sites can only be matched by key with the dumps of both builds.
+    if dumps_before.is_empty() != dumps_after.is_empty() {
+        return None;
+    }
+    let [before, after]: [PathBuf; 2] = paths.try_into().ok()?;
+    Some(Options { dumps_before, dumps_after, max_drop, all, before, after })
+}
+
+fn main() -> ExitCode {
+    if std::env::args().any(|a| a == "-h" || a == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    let Some(options) = parse_args() else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+
+    let (before, after) = match (report::load(&options.before), report::load(&options.after)) {
+        (Ok(before), Ok(after)) => (before, after),
+        (Err(e), _) | (_, Err(e)) => return fail(e),
+    };
+    let programs = if options.dumps_before.is_empty() {
+        None
+    } else {
+        match (merge(&options.dumps_before, "before"), merge(&options.dumps_after, "after")) {
+            (Ok(before), Ok(after)) => Some((before, after)),
+            (Err(e), _) | (_, Err(e)) => return fail(e),
+        }
+    };
+    let diff = RunDiff::new(&before.unsafe_heap, &after.unsafe_heap, programs.as_ref().map(|(b, a)| (b, a)));
+
+    if let Err(e) = write_report(&mut io::stdout().lock(), &diff, programs.as_ref(), &options) {
+        if e.kind() != io::ErrorKind::BrokenPipe {
+            return fail(e);
+        }
+    }
+    let mut failed = false;
+    if diff.regressions() > 0 {
+        eprintln!("svf-diff: {} regressions", diff.regressions());
+        failed = true;
+    }
+    if let Some(max) = options.max_drop {
+        for (name, delta) in [("precision", diff.precision_delta()), ("recall", diff.recall_delta())] {
+            if let Some(delta) = delta.filter(|d| -d > max) {
+                eprintln!("svf-diff: {} dropped by {:.2} points, more than {:.2}", name, -delta, max);
+                failed = true;
+            }
+        }
+    }
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
+
+fn merge(paths: &[PathBuf], build: &str) -> Result<WholeProgram, svf_pts::Error> {
+    let (program, warning) = svf_pts::load_inputs(paths)?.merge();
+    if let Some(warning) = warning {
+        eprintln!("svf-diff: warning: {} build: {}; accesses are not tied to their targets", build, warning);
+    }
+    Ok(program)
+}
+
+fn write_report(out: &mut dyn Write, diff: &RunDiff, programs: Option<&(WholeProgram, WholeProgram)>, options: &Options) -> io::Result<()> {
+    writeln!(out, "=== svf-diff: {} -> {} ===", options.before.display(), options.after.display())?;
+    let (b, a) = (&diff.before, &diff.after);
+    for (name, b, a) in [("TP", b.tp, a.tp), ("FP", b.fp, a.fp), ("FN", b.fn_, a.fn_), ("TN", b.tn, a.tn)] {
+        writeln!(out, "{}: {} -> {} ({:+})", name, b, a, a as i64 - b as i64)?;
+    }
+    writeln!(out, "Precision: {}", percent_change(b.precision, a.precision))?;
+    writeln!(out, "Recall:    {}", percent_change(b.recall, a.recall))?;
+    let counts = |count: &dyn Fn(Change) -> usize| {
+        Change::ALL.iter().map(|&c| format!("{} {}", count(c), c.as_str())).collect::<Vec<_>>().join(", ")
+    };
+    writeln!(out, "access ids: {}", counts(&|c| diff.access_count(c)))?;
+    writeln!(out, "sites:      {}", counts(&|c| diff.site_count(c)))?;
+
+    for change in Change::ALL {
+        if !options.all && !matches!(change, Change::Regression | Change::Improvement) {
+            continue;
+        }
+        let accesses: Vec<_> = diff.accesses.iter().filter(|d| d.change == change).collect();
+        let sites: Vec<_> = diff.sites.iter().filter(|d| d.change == change).collect();
+        if accesses.is_empty() && sites.is_empty() {
+            continue;
+        }
+        writeln!(out, "\n{}:", change.as_str())?;
+        for access in accesses {
+            let (b, a) = access.outcomes();
+            write!(out, "  access {}: {} -> {}", access.access_id, outcome(b), outcome(a))?;
+            if let Some(row) = &access.after {
+                write!(out, " ({} executions, {} FN, {} FP)", row.executions, row.fn_, row.fp)?;
+            }
+            writeln!(out)?;
+            let Some((before, after)) = programs else { continue };
+            if let Some(ptr) = after.access(access.access_id) {
+                let loc = ptr.source_loc.as_ref().map_or(String::from("?"), |l| l.to_string());
+                writeln!(out, "    at {} in {}", loc, ptr.function)?;
+            }
+            if let Some((lost, gained)) = diff::target_changes(before, after, access.access_id) {
+                for (verb, keys, program) in [("lost", lost, before), ("gained", gained, after)] {
+                    for key in keys {
+                        writeln!(out, "    {} target {}", verb, describe_key(key, program))?;
+                    }
+                }
+            }
+        }
+        for site in sites {
+            let (b, a) = site.outcomes();
+            write!(out, "  site {}: {} -> {}", site_name(site.site, programs), outcome(b), outcome(a))?;
+            if let (Some(b), Some(a)) = (&site.before, &site.after) {
+                write!(out, ", recall {}", percent_change(diff::site_recall(b), diff::site_recall(a)))?;
+            }
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+fn outcome(outcome: Option<diff::Outcome>) -> &'static str {
+    outcome.map_or("not run", |o| o.as_str())
+}
+
+/// `80.00% -> 75.00% (-5.00)`.
+fn percent_change(before: Option<f64>, after: Option<f64>) -> String {
+    let percent = |v: Option<f64>| v.map_or(String::from("n/a"), |v| format!("{:.2}%", v));
+    match (before, after) {
+        (Some(b), Some(a)) => format!("{} -> {} ({:+.2})", percent(before), percent(after), a - b),
+        _ => format!("{} -> {}", percent(before), percent(after)),
+    }
+}
+
+fn site_name(site: SiteRef, programs: Option<&(WholeProgram, WholeProgram)>) -> String {
+    match (site, programs) {
+        (SiteRef::Key(key), Some((before, after))) => {
+            describe_key(key, if after.site(key).is_some() { after } else { before })
+        }
+        (SiteRef::Key(key), None) => key.to_string(),
+        (SiteRef::Id(id), _) => id.to_string(),
+    }
+}
+
+/// `0123456789abcdef (__rust_alloc at alloc.rs:100:9)`.
+fn describe_key(key: SiteKey, program: &WholeProgram) -> String {
+    match program.site(key) {
+        Some(site) => match &site.source_loc {
+            Some(loc) => format!("{} ({} at {})", key, site.alloc_fn, loc),
+            None => format!("{} ({})", key, site.alloc_fn),
+        },
+        None => key.to_string(),
+    }
+}
+
+fn fail(e: impl std::fmt::Display) -> ExitCode {
+    eprintln!("svf-diff: {}", e);
+    ExitCode::from(2)
+}
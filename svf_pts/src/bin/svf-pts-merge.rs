@@ -0,0 +1,86 @@
+//! merge pts dumps into one whole-program json.
+//!
+//! ```text
+//! svf-pts-merge [-o merged.json] <dump, access map or directory>...
+//! ```
+//!
+//! directories contribute every `svf_pts_to_*.json` and access map
+//! (`svf_access_ids_*.json`) in them; the access maps fill in the unsafe
+//! pointers' access ids. the merged json
+//! (see `svf_pts::merge`) goes to stdout or `-o`; a summary and the node id
+//! collisions go to stderr.
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: svf-pts-merge [-o merged.json] <dump, access map or directory>...";
+
+fn main() -> ExitCode {
+    let mut output: Option<PathBuf> = None;
+    let mut inputs: Vec<PathBuf> = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("-o") => match args.next() {
+                Some(path) => output = Some(path.into()),
+                None => return usage(),
+            },
+            Some("-h" | "--help") => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => inputs.push(arg.into()),
+        }
+    }
+    if inputs.is_empty() {
+        return usage();
+    }
+
+    let inputs = match svf_pts::load_inputs(&inputs) {
+        Ok(inputs) => inputs,
+        Err(e) => return fail(e),
+    };
+
+    let (program, warning) = inputs.merge();
+    eprintln!(
+        "merged {} dumps ({} modules): {} sites ({} duplicates merged), {} unsafe ptrs, {} node id collisions",
+        inputs.dumps.len(),
+        program.modules.len(),
+        program.sites.len(),
+        program.duplicate_sites(),
+        program.unsafe_ptrs.len(),
+        program.collisions.len(),
+    );
+    if let Some(warning) = warning {
+        eprintln!("warning: {}", warning);
+    }
+    for collision in &program.collisions {
+        eprintln!("  node {} names {} sites:", collision.node_id, collision.sites.len());
+        for colliding in &collision.sites {
+            let site = program.site(colliding.key).unwrap();
+            let loc = site.source_loc.as_ref().map_or(String::from("?"), |l| l.to_string());
+            eprintln!("    {} {} at {} in {}", colliding.key, site.alloc_fn, loc, colliding.modules.join(", "));
+        }
+    }
+
+    let json = serde_json::to_string_pretty(&program).unwrap();
+    let written = match &output {
+        Some(path) => std::fs::write(path, json + "\n"),
+        None => writeln!(std::io::stdout().lock(), "{}", json),
+    };
+    if let Err(e) = written {
+        return fail(e);
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::from(2)
+}
+
+fn fail(e: impl std::fmt::Display) -> ExitCode {
+    eprintln!("svf-pts-merge: {}", e);
+    ExitCode::FAILURE
+}
@@ -0,0 +1,175 @@
+//! rank the false negatives of a run by likely root cause.
+//!
+//! ```text
+//! svf-triage [--top N] <run log | -> <dump, access map or directory>...
+//! ```
+//!
+//! the run log is the instrumented program's stdout, with the runtime's
+//! `svf_fn` lines; `-` reads it from stdin. the dumps are merged as by
+//! `svf-pts-merge` and joined with the events, see `svf_pts::triage`. the
+//! events name accesses by access id only, so the lto plugin's access maps
+//! (`svf_access_ids_*.json`, also picked up from the directories) are needed
+//! to tell which unsafe pointer an event is about; without them, events are
+//! triaged by site alone.
+
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use svf_pts::merge::{Resolved, WholeProgram};
+use svf_pts::triage::{self, FnKind, Group};
+
+const USAGE: &str = "usage: svf-triage [--top N] <run log | -> <dump, access map or directory>...";
+
+/// groups printed by default.
+const DEFAULT_TOP: usize = 20;
+/// predicted sites printed per group.
+const TOP_PREDICTED: usize = 5;
+
+fn main() -> ExitCode {
+    let mut top = DEFAULT_TOP;
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--top") => match args.next().and_then(|n| n.to_str()?.parse().ok()) {
+                Some(n) => top = n,
+                None => return usage(),
+            },
+            Some("-h" | "--help") => {
+                println!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            _ => paths.push(arg.into()),
+        }
+    }
+    if paths.len() < 2 {
+        return usage();
+    }
+
+    let log = paths.remove(0);
+    let parsed = if log.as_os_str() == "-" {
+        triage::parse_log(io::stdin().lock())
+    } else {
+        File::open(&log).and_then(|f| triage::parse_log(BufReader::new(f)))
+    };
+    let (events, errors) = match parsed {
+        Ok(parsed) => parsed,
+        Err(e) => return fail(format!("{}: {}", log.display(), e)),
+    };
+
+    let inputs = match svf_pts::load_inputs(&paths) {
+        Ok(inputs) => inputs,
+        Err(e) => return fail(e),
+    };
+    let (program, warning) = inputs.merge();
+    if let Some(warning) = warning {
+        eprintln!("svf-triage: warning: {}; events without a joined access are triaged by site only", warning);
+    }
+    let groups = triage::triage(&events, &program);
+
+    let mut out = io::stdout().lock();
+    match write_report(&mut out, &events, &errors, &groups, &program, top) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => fail(e),
+    }
+}
+
+fn write_report(
+    out: &mut dyn Write,
+    events: &[triage::FnEvent],
+    errors: &[triage::LogError],
+    groups: &[Group],
+    program: &WholeProgram,
+    top: usize,
+) -> io::Result<()> {
+    writeln!(out, "=== svf-triage: {} FN events in {} groups ===", events.len(), groups.len())?;
+    for kind in FnKind::ALL {
+        let count = events.iter().filter(|e| e.kind == kind).count();
+        writeln!(out, "  {}: {}", kind.as_str(), count)?;
+    }
+    let joined = events.iter().filter(|e| program.access(e.access_id).is_some()).count();
+    writeln!(out, "  events joined by access id: {} of {}", joined, events.len())?;
+    if let Some(first) = errors.first() {
+        writeln!(out, "  skipped {} malformed svf_fn lines (line {}: {})", errors.len(), first.line, first.message)?;
+    }
+
+    for (rank, group) in groups.iter().take(top).enumerate() {
+        writeln!(
+            out,
+            "\n#{} {}: {} events, {} access ids, {} objects",
+            rank + 1,
+            group.kind.as_str(),
+            group.events,
+            group.access_ids.len(),
+            group.tickets.len()
+        )?;
+        writeln!(out, "   cause: {}", group.cause.as_str())?;
+        match group.access {
+            Some(ptr) => {
+                let loc = ptr.source_loc.as_ref().map_or(String::from("?"), |l| l.to_string());
+                writeln!(out, "   access: {} in {}", loc, ptr.function)?;
+                writeln!(out, "     {}", ptr.instruction.trim())?;
+            }
+            None => writeln!(out, "   access ids: {}", id_list(group.access_ids.iter().copied()))?,
+        }
+        writeln!(out, "   object: {}", describe_site(group.runtime_site_id, program))?;
+        if group.predicted.is_empty() {
+            writeln!(out, "   predicted: nothing")?;
+        } else {
+            let mut predicted: Vec<(u64, u64)> = group.predicted.iter().map(|(&id, &n)| (id, n)).collect();
+            predicted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            writeln!(out, "   predicted ({} sites):", predicted.len())?;
+            for (site_id, n) in predicted.iter().take(TOP_PREDICTED) {
+                writeln!(out, "     {} ({} events)", describe_site(*site_id, program), n)?;
+            }
+            if predicted.len() > TOP_PREDICTED {
+                writeln!(out, "     ... {} more", predicted.len() - TOP_PREDICTED)?;
+            }
+        }
+    }
+    if groups.len() > top {
+        writeln!(out, "\n... {} more groups (--top)", groups.len() - top)?;
+    }
+    Ok(())
+}
+
+/// `site 8230: __rust_alloc at alloc.rs:100:9`, or why it cannot be resolved.
+fn describe_site(site_id: u64, program: &WholeProgram) -> String {
+    if site_id == triage::UNINSTRUMENTED_SITE_ID {
+        return String::from("uninstrumented allocation");
+    }
+    if site_id & triage::TRACKED_SITE_TAG != 0 {
+        return format!("tracking allocator site {:#x}", site_id);
+    }
+    match program.resolve(site_id) {
+        Resolved::Site(site) => match &site.source_loc {
+            Some(loc) => format!("site {}: {} at {}", site_id, site.alloc_fn, loc),
+            None => format!("site {}: {}", site_id, site.alloc_fn),
+        },
+        Resolved::Ambiguous(collision) => format!("site {}: ambiguous, names {} sites", site_id, collision.sites.len()),
+        Resolved::Unknown => format!("site {}: not in the dumps", site_id),
+    }
+}
+
+/// at most 8 ids, then a count.
+fn id_list(ids: impl ExactSizeIterator<Item = u64>) -> String {
+    let len = ids.len();
+    let mut list: Vec<String> = ids.take(8).map(|id| id.to_string()).collect();
+    if len > 8 {
+        list.push(format!("... ({} total)", len));
+    }
+    list.join(", ")
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::from(2)
+}
+
+fn fail(e: impl std::fmt::Display) -> ExitCode {
+    eprintln!("svf-triage: {}", e);
+    ExitCode::FAILURE
+}
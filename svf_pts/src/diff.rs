@@ -0,0 +1,251 @@
+//! run-to-run diff of the unsafe heap accuracy of two runtime reports, e.g.
+//! before and after changing svf options or upgrading the plugin.
+//!
+//! an access or site gets one [`Outcome`] per run, the worst class it saw, and
+//! a [`Change`] when the outcome differs between the runs. outcomes rank
+//! TP / TN below FP below FN: gaining an FP or FN is a regression, losing one
+//! an improvement.
+//!
+//! accesses are keyed by access id. sites are keyed by site id, or, given the
+//! merged dumps of each build, by [`SiteKey`], since node ids are renumbered
+//! between builds; rows of one report sharing a key are summed.
+
+use std::collections::BTreeMap;
+
+use crate::merge::{Resolved, SiteKey, WholeProgram};
+use crate::report::{AccessRow, Confusion, SiteConfusion, UnsafeHeap};
+
+/// the worst class an access or site saw in one run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Tp,
+    Tn,
+    Fp,
+    Fn,
+}
+
+impl Outcome {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Outcome::Tp => "TP",
+            Outcome::Tn => "TN",
+            Outcome::Fp => "FP",
+            Outcome::Fn => "FN",
+        }
+    }
+
+    /// 0 for correct, 1 for imprecise, 2 for unsound.
+    pub fn severity(self) -> u8 {
+        match self {
+            Outcome::Tp | Outcome::Tn => 0,
+            Outcome::Fp => 1,
+            Outcome::Fn => 2,
+        }
+    }
+
+    pub fn of_access(row: &AccessRow) -> Self {
+        if row.fn_ > 0 {
+            Outcome::Fn
+        } else if row.fp > 0 {
+            Outcome::Fp
+        } else if row.tp > 0 {
+            Outcome::Tp
+        } else {
+            Outcome::Tn
+        }
+    }
+
+    /// sites see no TN: a site with neither FN nor false predictions is TP.
+    pub fn of_site(row: &SiteConfusion) -> Self {
+        if row.fn_ > 0 {
+            Outcome::Fn
+        } else if row.fp_predictions > 0 {
+            Outcome::Fp
+        } else {
+            Outcome::Tp
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Change {
+    Regression,
+    Improvement,
+    /// same severity, different outcome, e.g. TP to TN.
+    Changed,
+    /// only executed in the first run.
+    Removed,
+    /// only executed in the second run.
+    Added,
+}
+
+impl Change {
+    pub const ALL: [Change; 5] = [Change::Regression, Change::Improvement, Change::Changed, Change::Removed, Change::Added];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Change::Regression => "regression",
+            Change::Improvement => "improvement",
+            Change::Changed => "changed",
+            Change::Removed => "only before",
+            Change::Added => "only after",
+        }
+    }
+
+    fn between(before: Option<Outcome>, after: Option<Outcome>) -> Option<Self> {
+        match (before, after) {
+            (Some(b), Some(a)) if a.severity() > b.severity() => Some(Change::Regression),
+            (Some(b), Some(a)) if a.severity() < b.severity() => Some(Change::Improvement),
+            (Some(b), Some(a)) => (a != b).then_some(Change::Changed),
+            (Some(_), None) => Some(Change::Removed),
+            (None, Some(_)) => Some(Change::Added),
+            (None, None) => None,
+        }
+    }
+}
+
+/// one access id whose outcome changed.
+#[derive(Clone, Debug)]
+pub struct AccessDiff {
+    pub access_id: u64,
+    pub before: Option<AccessRow>,
+    pub after: Option<AccessRow>,
+    pub change: Change,
+}
+
+impl AccessDiff {
+    pub fn outcomes(&self) -> (Option<Outcome>, Option<Outcome>) {
+        (self.before.as_ref().map(Outcome::of_access), self.after.as_ref().map(Outcome::of_access))
+    }
+}
+
+/// how a site is matched across runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SiteRef {
+    /// resolved through the dumps of its build.
+    Key(SiteKey),
+    /// the raw site id: no dumps, or the id is unknown or ambiguous in them.
+    Id(u64),
+}
+
+/// one site whose outcome changed. the rows keep the lowest site id of their run.
+#[derive(Clone, Debug)]
+pub struct SiteDiff {
+    pub site: SiteRef,
+    pub before: Option<SiteConfusion>,
+    pub after: Option<SiteConfusion>,
+    pub change: Change,
+}
+
+impl SiteDiff {
+    pub fn outcomes(&self) -> (Option<Outcome>, Option<Outcome>) {
+        (self.before.as_ref().map(Outcome::of_site), self.after.as_ref().map(Outcome::of_site))
+    }
+}
+
+/// TP / (TP + FN) of a site, in percent.
+pub fn site_recall(row: &SiteConfusion) -> Option<f64> {
+    let denom = row.tp + row.fn_;
+    (denom > 0).then(|| row.tp as f64 / denom as f64 * 100.0)
+}
+
+/// the changes between two runs.
+#[derive(Clone, Debug)]
+pub struct RunDiff {
+    pub before: Confusion,
+    pub after: Confusion,
+    /// changed accesses, sorted by access id.
+    pub accesses: Vec<AccessDiff>,
+    /// changed sites, sorted by [`SiteRef`].
+    pub sites: Vec<SiteDiff>,
+}
+
+impl RunDiff {
+    /// diff two runs; `programs` are the merged dumps of the two builds.
+    pub fn new(before: &UnsafeHeap, after: &UnsafeHeap, programs: Option<(&WholeProgram, &WholeProgram)>) -> Self {
+        let mut accesses: BTreeMap<u64, (Option<AccessRow>, Option<AccessRow>)> = BTreeMap::new();
+        for row in &before.accesses {
+            accesses.entry(row.access_id).or_default().0 = Some(row.clone());
+        }
+        for row in &after.accesses {
+            accesses.entry(row.access_id).or_default().1 = Some(row.clone());
+        }
+        let accesses = accesses
+            .into_iter()
+            .filter_map(|(access_id, (before, after))| {
+                let change = Change::between(before.as_ref().map(Outcome::of_access), after.as_ref().map(Outcome::of_access))?;
+                Some(AccessDiff { access_id, before, after, change })
+            })
+            .collect();
+
+        let mut sites: BTreeMap<SiteRef, (Option<SiteConfusion>, Option<SiteConfusion>)> = BTreeMap::new();
+        for row in &before.sites {
+            add_site(&mut sites.entry(site_ref(row.site_id, programs.map(|p| p.0))).or_default().0, row);
+        }
+        for row in &after.sites {
+            add_site(&mut sites.entry(site_ref(row.site_id, programs.map(|p| p.1))).or_default().1, row);
+        }
+        let sites = sites
+            .into_iter()
+            .filter_map(|(site, (before, after))| {
+                let change = Change::between(before.as_ref().map(Outcome::of_site), after.as_ref().map(Outcome::of_site))?;
+                Some(SiteDiff { site, before, after, change })
+            })
+            .collect();
+
+        RunDiff { before: before.confusion.clone(), after: after.confusion.clone(), accesses, sites }
+    }
+
+    /// change of precision, in percentage points.
+    pub fn precision_delta(&self) -> Option<f64> {
+        Some(self.after.precision? - self.before.precision?)
+    }
+
+    /// change of recall, in percentage points.
+    pub fn recall_delta(&self) -> Option<f64> {
+        Some(self.after.recall? - self.before.recall?)
+    }
+
+    pub fn access_count(&self, change: Change) -> usize {
+        self.accesses.iter().filter(|a| a.change == change).count()
+    }
+
+    pub fn site_count(&self, change: Change) -> usize {
+        self.sites.iter().filter(|s| s.change == change).count()
+    }
+
+    /// regressed accesses and sites.
+    pub fn regressions(&self) -> usize {
+        self.access_count(Change::Regression) + self.site_count(Change::Regression)
+    }
+}
+
+fn site_ref(site_id: u64, program: Option<&WholeProgram>) -> SiteRef {
+    match program.map(|p| p.resolve(site_id)) {
+        Some(Resolved::Site(site)) => SiteRef::Key(site.key),
+        _ => SiteRef::Id(site_id),
+    }
+}
+
+fn add_site(slot: &mut Option<SiteConfusion>, row: &SiteConfusion) {
+    match slot {
+        None => *slot = Some(row.clone()),
+        Some(sum) => {
+            sum.site_id = sum.site_id.min(row.site_id);
+            sum.tp += row.tp;
+            sum.fn_ += row.fn_;
+            sum.fp_predictions += row.fp_predictions;
+            sum.access_ids += row.access_ids;
+            sum.tickets += row.tickets;
+        }
+    }
+}
+
+/// heap targets of `access_id` lost and gained between two builds, when both
+/// dumps record the access.
+pub fn target_changes(before: &WholeProgram, after: &WholeProgram, access_id: u64) -> Option<(Vec<SiteKey>, Vec<SiteKey>)> {
+    let (b, a) = (before.access(access_id)?, after.access(access_id)?);
+    let lost = b.heap_targets.iter().filter(|k| !a.heap_targets.contains(k)).copied().collect();
+    let gained = a.heap_targets.iter().filter(|k| !b.heap_targets.contains(k)).copied().collect();
+    Some((lost, gained))
+}
@@ -0,0 +1,95 @@
+//! lookup indexes over a validated dump.
+
+use std::collections::HashMap;
+
+use crate::model::{AliasedAllocationSite, AllocationSite, HeapObject, PtsDump, UnsafePtr};
+
+/// borrowed lookup tables over one [`PtsDump`]. node ids are unique within a
+/// validated dump, so the node id lookups are exact.
+pub struct DumpIndex<'a> {
+    dump: &'a PtsDump,
+    objects: HashMap<u64, &'a HeapObject>,
+    sites: HashMap<u64, &'a AllocationSite>,
+    aliased: HashMap<u64, &'a AliasedAllocationSite>,
+    ptrs_by_function: HashMap<&'a str, Vec<&'a UnsafePtr>>,
+    ptrs_by_access: HashMap<u64, &'a UnsafePtr>,
+    sites_by_file: HashMap<&'a str, Vec<&'a AllocationSite>>,
+}
+
+impl<'a> DumpIndex<'a> {
+    pub fn new(dump: &'a PtsDump) -> Self {
+        let mut ptrs_by_function: HashMap<&str, Vec<&UnsafePtr>> = HashMap::new();
+        for ptr in &dump.unsafe_ptrs {
+            ptrs_by_function.entry(ptr.function.as_str()).or_default().push(ptr);
+        }
+        let mut sites_by_file: HashMap<&str, Vec<&AllocationSite>> = HashMap::new();
+        for site in &dump.allocation_sites {
+            if let Some(loc) = &site.source_loc {
+                sites_by_file.entry(loc.file.as_str()).or_default().push(site);
+            }
+        }
+        Self {
+            dump,
+            objects: dump.abstract_heap_objects.iter().map(|o| (o.node_id, o)).collect(),
+            sites: dump.allocation_sites.iter().map(|s| (s.node_id, s)).collect(),
+            aliased: dump.aliased_allocation_sites.iter().map(|s| (s.node_id, s)).collect(),
+            ptrs_by_function,
+            ptrs_by_access: dump.unsafe_ptrs.iter().filter_map(|p| Some((p.access_id?, p))).collect(),
+            sites_by_file,
+        }
+    }
+
+    pub fn dump(&self) -> &'a PtsDump {
+        self.dump
+    }
+
+    /// the abstract heap object `node_id`.
+    pub fn object(&self, node_id: u64) -> Option<&'a HeapObject> {
+        self.objects.get(&node_id).copied()
+    }
+
+    /// the allocation site `node_id`.
+    pub fn site(&self, node_id: u64) -> Option<&'a AllocationSite> {
+        self.sites.get(&node_id).copied()
+    }
+
+    /// the unsafe pointers that may point into site `node_id`, if any do.
+    pub fn aliased_site(&self, node_id: u64) -> Option<&'a AliasedAllocationSite> {
+        self.aliased.get(&node_id).copied()
+    }
+
+    /// unsafe pointers of the (mangled) function, in dump order.
+    pub fn unsafe_ptrs_in(&self, function: &str) -> &[&'a UnsafePtr] {
+        self.ptrs_by_function.get(function).map_or(&[], Vec::as_slice)
+    }
+
+    /// the unsafe pointer instrumented with `access_id`, in dumps recording it.
+    pub fn unsafe_ptr_for_access(&self, access_id: u64) -> Option<&'a UnsafePtr> {
+        self.ptrs_by_access.get(&access_id).copied()
+    }
+
+    /// allocation sites whose `source_loc` is in `file`, in dump order.
+    pub fn sites_in_file(&self, file: &str) -> &[&'a AllocationSite] {
+        self.sites_by_file.get(file).map_or(&[], Vec::as_slice)
+    }
+
+    /// functions with at least one unsafe pointer, sorted.
+    pub fn functions(&self) -> Vec<&'a str> {
+        let mut functions: Vec<&str> = self.ptrs_by_function.keys().copied().collect();
+        functions.sort_unstable();
+        functions
+    }
+
+    /// source files with at least one allocation site, sorted.
+    pub fn files(&self) -> Vec<&'a str> {
+        let mut files: Vec<&str> = self.sites_by_file.keys().copied().collect();
+        files.sort_unstable();
+        files
+    }
+
+    /// the heap objects an unsafe pointer may point to. targets that are not
+    /// abstract heap objects (stack or global objects) are skipped.
+    pub fn heap_targets(&self, ptr: &UnsafePtr) -> Vec<&'a HeapObject> {
+        ptr.targets.iter().filter_map(|&t| self.object(t)).collect()
+    }
+}
@@ -0,0 +1,136 @@
+//! reader for the static svf points-to dumps (`svf_pts_to_<module>_N.json`).
+//!
+//! the lto plugin writes one dump per module with five sections:
+//! - `abstract_heap_objects`: heap objects of svf's andersen analysis.
+//! - `unsafe_ptrs`: pointers dereferenced in unsafe regions, with the node ids
+//!   they may point to.
+//! - `allocation_sites`: allocation calls; their `node_id` is the site id the
+//!   runtime reports.
+//! - `aliased_allocation_sites`: allocation sites reached by unsafe pointers,
+//!   with those pointers.
+//! - `summary`: the module name and section counts.
+//!
+//! [`parse`] and [`load`] return a [`PtsDump`] only after validating it: type
+//! errors, duplicate node ids, dangling references and wrong summary counts
+//! are reported as an [`Error`] naming the offending entry, e.g.
+//! `unsafe_ptrs[3].targets[0]`. [`DumpIndex`] adds the usual lookups (node id
+//! to object or site, function to unsafe pointers, source file to sites).
+//!
+//! [`merge::WholeProgram`] merges the per-codegen-unit dumps of a build, see
+//! `svf-pts-merge`. [`triage`] joins the runtime's FN events with them, see
+//! `svf-triage`; the access ids the events carry are mapped to instructions by
+//! the plugin's [`access_map`]s. [`alias_compare`] scores the runtime's
+//! per-check alias table against `svf-checker`, see `svf-alias-compare`.
+//! [`diff`] compares the unsafe heap accuracy of two runs, see `svf-diff`;
+//! both read the runtime's json report through [`report`].
+//!
+//! the runtime keeps its own minimal reader (`svf_runtime::pts_dump`) so that
+//! it needs no dependencies; this crate is for offline tools.
+
+pub mod access_map;
+pub mod alias_compare;
+pub mod diff;
+pub mod index;
+pub mod merge;
+pub mod model;
+pub mod report;
+pub mod triage;
+mod validate;
+
+use std::path::{Path, PathBuf};
+
+use access_map::AccessMap;
+use merge::WholeProgram;
+
+pub use index::DumpIndex;
+pub use model::{AliasedAllocationSite, AllocationSite, HeapObject, PtrRef, PtsDump, SourceLoc, Summary, UnsafePtr};
+pub use validate::{Error, ErrorKind};
+
+/// parse and validate a dump.
+pub fn parse(text: &str) -> Result<PtsDump, Error> {
+    let dump: PtsDump = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(text))?;
+    validate::validate(&dump)?;
+    Ok(dump)
+}
+
+/// read, parse and validate the dump at `path`.
+pub fn load(path: &Path) -> Result<PtsDump, Error> {
+    let text = std::fs::read_to_string(path).map_err(|e| Error::io(e).in_file(path.to_path_buf()))?;
+    parse(&text).map_err(|e| e.in_file(path.to_path_buf()))
+}
+
+/// the dump files named by `path`: the file itself, or every `svf_pts_to_*.json`
+/// in a directory, sorted.
+pub fn dump_files(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    json_files(path, "svf_pts_to_")
+}
+
+/// the dumps and access maps a tool was pointed at, see [`load_inputs`].
+#[derive(Clone, Debug, Default)]
+pub struct Inputs {
+    pub dumps: Vec<PtsDump>,
+    pub access_maps: Vec<AccessMap>,
+}
+
+impl Inputs {
+    /// merge the dumps and join the access maps with them. the message says
+    /// when events or report rows cannot be tied to unsafe pointers by access
+    /// id: no access ids at all, or access map records matching no pointer.
+    pub fn merge(&self) -> (WholeProgram, Option<String>) {
+        let mut program = WholeProgram::merge(&self.dumps);
+        let records: usize = self.access_maps.iter().map(|m| m.accesses.len()).sum();
+        let joined = program.join_access_ids(&self.access_maps);
+        let warning = if program.unsafe_ptrs.iter().all(|p| p.access_ids.is_empty()) {
+            Some(if self.access_maps.is_empty() {
+                String::from("no dump or access map records access ids")
+            } else {
+                String::from("no access map record matches an unsafe pointer")
+            })
+        } else if joined < records {
+            Some(format!("{} of {} access map records match no unsafe pointer", records - joined, records))
+        } else {
+            None
+        };
+        (program, warning)
+    }
+}
+
+/// load the dumps and access maps named by `paths`: files, access maps told
+/// apart by their `svf_access_ids_` name, or directories holding either.
+pub fn load_inputs(paths: &[PathBuf]) -> Result<Inputs, Error> {
+    let mut inputs = Inputs::default();
+    for path in paths {
+        let (dumps, maps) = if path.is_dir() {
+            (dump_files(path)?, access_map::files(path)?)
+        } else if access_map::is_access_map(path) {
+            (Vec::new(), vec![path.clone()])
+        } else {
+            (vec![path.clone()], Vec::new())
+        };
+        for file in dumps {
+            inputs.dumps.push(load(&file)?);
+        }
+        for file in maps {
+            inputs.access_maps.push(access_map::load(&file)?);
+        }
+    }
+    Ok(inputs)
+}
+
+/// the `<prefix>*.json` files in the directory `path`, sorted.
+pub(crate) fn json_files(path: &Path, prefix: &str) -> Result<Vec<PathBuf>, Error> {
+    let io_error = |e| Error::io(e).in_file(path.to_path_buf());
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path).map_err(io_error)? {
+        let file = entry.map_err(io_error)?.path();
+        let name = file.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        if name.starts_with(prefix) && name.ends_with(".json") {
+            files.push(file);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,392 @@
+//! whole-program view over per-module dumps.
+//!
+//! every codegen unit gets its own dump with its own node id space, so the same
+//! node id can name different sites in different dumps, and a site inlined or
+//! instantiated in several units shows up in each of them. merging keys every
+//! site by a [`SiteKey`] instead, a hash of its `alloc_fn`, `source_loc` and
+//! allocating instruction. the instruction is hashed without what is numbered
+//! per module or per function: metadata attachments (`!dbg !12`), attribute
+//! groups (`#19`) and the result name, with local values (`%21`, `%_0.i.i`)
+//! renamed in order of appearance, since inlining changes their names.
+//! inlined copies of one site (or access) then normalize alike, so the copies
+//! within a module are told apart by their order in its dump.
+//!
+//! a node id used for sites with different keys is a [`Collision`]; runtime
+//! site ids (node ids) are then resolved with [`WholeProgram::resolve`], which
+//! says whether the id is ambiguous.
+//!
+//! runtime access ids are mapped to unsafe pointers by
+//! [`WholeProgram::access`], once [`WholeProgram::join_access_ids`] has joined
+//! the plugin's access maps, see [`crate::access_map`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::{Serialize, Serializer};
+
+use crate::access_map::AccessMap;
+use crate::model::{PtsDump, SourceLoc};
+
+/// stable identity of an allocation site across dumps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SiteKey(pub u64);
+
+impl SiteKey {
+    pub fn new(alloc_fn: &str, source_loc: Option<&SourceLoc>, instruction: &str) -> Self {
+        // fnv-1a, separating the fields with a 0 byte.
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut feed = |bytes: &[u8]| {
+            for &byte in bytes.iter().chain(&[0]) {
+                hash = (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+            }
+        };
+        feed(alloc_fn.as_bytes());
+        match source_loc {
+            Some(loc) => feed(loc.to_string().as_bytes()),
+            None => feed(b""),
+        }
+        feed(normalize_instruction(instruction).as_bytes());
+        Self(hash)
+    }
+
+    /// the key of the `copy`th site of a module that normalizes to this key;
+    /// the first copy keeps it.
+    pub fn copy(self, copy: usize) -> Self {
+        match copy {
+            0 => self,
+            _ => Self((self.0 ^ copy as u64).wrapping_mul(0x100_0000_01b3)),
+        }
+    }
+}
+
+impl fmt::Display for SiteKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl Serialize for SiteKey {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(self)
+    }
+}
+
+/// `instruction` without the per-module numbering, see the module docs.
+pub fn normalize_instruction(instruction: &str) -> String {
+    let code = instruction.split(", !").next().unwrap_or("").trim_start();
+    let code = match code.split_once(" = ") {
+        Some((result, rest)) if result.starts_with('%') && !result.contains(' ') => rest,
+        _ => code,
+    };
+    let code = rename_locals(code);
+    let tokens = code.split_whitespace().filter(|t| !(t.starts_with('#') && t[1..].bytes().all(|b| b.is_ascii_digit())));
+    tokens.collect::<Vec<_>>().join(" ")
+}
+
+/// `code` with every local name (`%x`, `%12`, `%"x y"`) replaced by `%N`, numbered
+/// in order of first appearance.
+fn rename_locals(code: &str) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    let mut out = String::with_capacity(code.len());
+    let mut rest = code;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start + 1..];
+        let len = match tail.strip_prefix('"') {
+            Some(quoted) => quoted.find('"').map_or(tail.len(), |end| end + 2),
+            None => tail.find(|c: char| !(c.is_ascii_alphanumeric() || "-$._".contains(c))).unwrap_or(tail.len()),
+        };
+        if len == 0 {
+            out.push('%');
+        } else {
+            let name = &tail[..len];
+            let n = names.iter().position(|&known| known == name).unwrap_or_else(|| {
+                names.push(name);
+                names.len() - 1
+            });
+            out.push_str(&format!("%{}", n));
+        }
+        rest = &tail[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// a node id of one module.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct ModuleNode {
+    pub module: String,
+    pub node_id: u64,
+}
+
+/// one allocation site of the whole program.
+#[derive(Clone, Debug, Serialize)]
+pub struct MergedSite {
+    pub key: SiteKey,
+    pub alloc_fn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+    /// the instruction as first seen.
+    pub instruction: String,
+    /// the static size of its abstract heap object, when one was dumped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// the node ids the site has in each module, sorted.
+    pub nodes: Vec<ModuleNode>,
+}
+
+/// one unsafe pointer of the whole program.
+#[derive(Clone, Debug, Serialize)]
+pub struct MergedPtr {
+    pub instruction: String,
+    pub function: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+    /// heap targets, re-keyed; the union over every module listing the pointer.
+    pub heap_targets: Vec<SiteKey>,
+    /// most targets that are not heap objects seen in one module.
+    pub other_targets: u64,
+    pub modules: Vec<String>,
+    /// the access ids of the pointer, from dumps recording them or joined from
+    /// the plugin's access maps, sorted.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub access_ids: Vec<u64>,
+}
+
+/// a node id naming different sites.
+#[derive(Clone, Debug, Serialize)]
+pub struct Collision {
+    pub node_id: u64,
+    /// sorted by key.
+    pub sites: Vec<CollidingSite>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CollidingSite {
+    pub key: SiteKey,
+    /// the modules using the node id for this site.
+    pub modules: Vec<String>,
+}
+
+/// what a runtime site id means in the whole program.
+#[derive(Clone, Copy, Debug)]
+pub enum Resolved<'a> {
+    Unknown,
+    Site(&'a MergedSite),
+    /// the node id collides; any of these sites may be meant.
+    Ambiguous(&'a Collision),
+}
+
+/// dumps merged into one deduplicated site and pointer list.
+#[derive(Default, Serialize)]
+pub struct WholeProgram {
+    /// modules merged, in merge order.
+    pub modules: Vec<String>,
+    /// sorted by key.
+    pub sites: Vec<MergedSite>,
+    pub unsafe_ptrs: Vec<MergedPtr>,
+    /// sorted by node id.
+    #[serde(rename = "node_id_collisions")]
+    pub collisions: Vec<Collision>,
+    #[serde(skip)]
+    by_key: HashMap<SiteKey, usize>,
+    #[serde(skip)]
+    by_node: HashMap<u64, usize>,
+    #[serde(skip)]
+    by_access: HashMap<u64, usize>,
+}
+
+impl WholeProgram {
+    /// merge `dumps`. a dump listed twice (same module) adds nothing.
+    pub fn merge<'a>(dumps: impl IntoIterator<Item = &'a PtsDump>) -> Self {
+        let mut sites: BTreeMap<SiteKey, MergedSite> = BTreeMap::new();
+        let mut ptrs: BTreeMap<(String, String, Option<String>, usize), MergedPtr> = BTreeMap::new();
+        let mut modules: Vec<String> = Vec::new();
+
+        for dump in dumps {
+            let module = &dump.summary.module;
+            if !modules.contains(module) {
+                modules.push(module.clone());
+            }
+            let mut keys: HashMap<u64, SiteKey> = HashMap::new();
+            let sizes: HashMap<u64, u64> = dump.abstract_heap_objects.iter().map(|o| (o.node_id, o.size)).collect();
+            let mut copies: HashMap<SiteKey, usize> = HashMap::new();
+            // allocation sites first: objects repeat them with `source` for `instruction`.
+            let entries = dump
+                .allocation_sites
+                .iter()
+                .map(|s| (s.node_id, &s.alloc_fn, s.source_loc.as_ref(), &s.instruction))
+                .chain(dump.abstract_heap_objects.iter().map(|o| (o.node_id, &o.alloc_fn, o.source_loc.as_ref(), &o.source)));
+            for (node_id, alloc_fn, source_loc, instruction) in entries {
+                if keys.contains_key(&node_id) {
+                    continue;
+                }
+                let key = SiteKey::new(alloc_fn, source_loc, instruction);
+                let copy = copies.entry(key).or_default();
+                let key = key.copy(*copy);
+                *copy += 1;
+                keys.insert(node_id, key);
+                let site = sites.entry(key).or_insert_with(|| MergedSite {
+                    key,
+                    alloc_fn: alloc_fn.clone(),
+                    source_loc: source_loc.cloned(),
+                    instruction: instruction.clone(),
+                    size: None,
+                    nodes: Vec::new(),
+                });
+                site.size = site.size.or(sizes.get(&node_id).copied());
+                let node = ModuleNode { module: module.clone(), node_id };
+                if !site.nodes.contains(&node) {
+                    site.nodes.push(node);
+                }
+            }
+
+            let mut ptr_copies: HashMap<(String, String, Option<String>), usize> = HashMap::new();
+            for ptr in &dump.unsafe_ptrs {
+                let id = (ptr.function.clone(), normalize_instruction(&ptr.instruction), ptr.source_loc.as_ref().map(SourceLoc::to_string));
+                let copy = ptr_copies.entry(id.clone()).or_default();
+                let id = (id.0, id.1, id.2, *copy);
+                *copy += 1;
+                let merged = ptrs.entry(id).or_insert_with(|| MergedPtr {
+                    instruction: ptr.instruction.clone(),
+                    function: ptr.function.clone(),
+                    source_loc: ptr.source_loc.clone(),
+                    heap_targets: Vec::new(),
+                    other_targets: 0,
+                    modules: Vec::new(),
+                    access_ids: Vec::new(),
+                });
+                let mut other = 0;
+                for target in &ptr.targets {
+                    match keys.get(target) {
+                        Some(key) if !merged.heap_targets.contains(key) => merged.heap_targets.push(*key),
+                        Some(_) => {}
+                        None => other += 1,
+                    }
+                }
+                merged.other_targets = merged.other_targets.max(other);
+                if !merged.modules.contains(module) {
+                    merged.modules.push(module.clone());
+                }
+                if let Some(access_id) = ptr.access_id.filter(|id| !merged.access_ids.contains(id)) {
+                    merged.access_ids.push(access_id);
+                }
+            }
+        }
+
+        let mut program = WholeProgram { modules, ..Default::default() };
+        program.sites = sites.into_values().collect();
+        for site in &mut program.sites {
+            site.nodes.sort();
+        }
+        program.unsafe_ptrs = ptrs.into_values().collect();
+        for ptr in &mut program.unsafe_ptrs {
+            ptr.heap_targets.sort();
+            ptr.access_ids.sort();
+        }
+        program.index();
+        program
+    }
+
+    /// record the access ids of `maps` on the unsafe pointers they guard. a
+    /// record joins the pointer with the same function, instruction (normalized)
+    /// and source location, telling inlined copies apart by their order like
+    /// [`merge`](Self::merge) does; failing that, the only pointer at its source
+    /// location in the function. returns how many records were joined.
+    pub fn join_access_ids<'a>(&mut self, maps: impl IntoIterator<Item = &'a AccessMap>) -> usize {
+        let mut by_id: HashMap<(&str, String, Option<String>), Vec<usize>> = HashMap::new();
+        let mut by_loc: HashMap<(&str, String), Vec<usize>> = HashMap::new();
+        // sorted by id and then copy, see `merge`.
+        for (i, ptr) in self.unsafe_ptrs.iter().enumerate() {
+            let loc = ptr.source_loc.as_ref().map(SourceLoc::to_string);
+            if let Some(loc) = &loc {
+                by_loc.entry((&ptr.function, loc.clone())).or_default().push(i);
+            }
+            by_id.entry((&ptr.function, normalize_instruction(&ptr.instruction), loc)).or_default().push(i);
+        }
+
+        let mut joins: Vec<(usize, u64)> = Vec::new();
+        for map in maps {
+            let mut copies: HashMap<(&str, String, Option<String>), usize> = HashMap::new();
+            for record in &map.accesses {
+                let loc = record.source_loc.as_ref().map(SourceLoc::to_string);
+                let id = (record.function.as_str(), normalize_instruction(&record.instruction), loc);
+                let copy = copies.entry(id.clone()).or_default();
+                let exact = by_id.get(&id).and_then(|ptrs| ptrs.get(*copy));
+                *copy += 1;
+                let at_loc = || match id.2.clone().and_then(|loc| by_loc.get(&(id.0, loc))) {
+                    Some(ptrs) if ptrs.len() == 1 => ptrs.first(),
+                    _ => None,
+                };
+                if let Some(&i) = exact.or_else(at_loc) {
+                    joins.push((i, record.access_id));
+                }
+            }
+        }
+
+        let joined = joins.len();
+        for (i, access_id) in joins {
+            let ptr = &mut self.unsafe_ptrs[i];
+            if let Err(at) = ptr.access_ids.binary_search(&access_id) {
+                ptr.access_ids.insert(at, access_id);
+            }
+        }
+        self.index_accesses();
+        joined
+    }
+
+    fn index_accesses(&mut self) {
+        self.by_access.clear();
+        for (i, ptr) in self.unsafe_ptrs.iter().enumerate() {
+            for &access_id in &ptr.access_ids {
+                self.by_access.insert(access_id, i);
+            }
+        }
+    }
+
+    fn index(&mut self) {
+        self.index_accesses();
+        let mut by_node: BTreeMap<u64, BTreeMap<SiteKey, Vec<String>>> = BTreeMap::new();
+        for (i, site) in self.sites.iter().enumerate() {
+            self.by_key.insert(site.key, i);
+            for node in &site.nodes {
+                by_node.entry(node.node_id).or_default().entry(site.key).or_default().push(node.module.clone());
+            }
+        }
+        for (node_id, sites) in by_node {
+            if sites.len() == 1 {
+                let key = sites.keys().next().unwrap();
+                self.by_node.insert(node_id, self.by_key[key]);
+            } else {
+                let sites = sites.into_iter().map(|(key, modules)| CollidingSite { key, modules }).collect();
+                self.collisions.push(Collision { node_id, sites });
+            }
+        }
+    }
+
+    pub fn site(&self, key: SiteKey) -> Option<&MergedSite> {
+        self.by_key.get(&key).map(|&i| &self.sites[i])
+    }
+
+    /// the site a runtime site id (a node id) stands for.
+    pub fn resolve(&self, node_id: u64) -> Resolved<'_> {
+        if let Some(&i) = self.by_node.get(&node_id) {
+            return Resolved::Site(&self.sites[i]);
+        }
+        match self.collisions.binary_search_by_key(&node_id, |c| c.node_id) {
+            Ok(i) => Resolved::Ambiguous(&self.collisions[i]),
+            Err(_) => Resolved::Unknown,
+        }
+    }
+
+    /// the unsafe pointer instrumented with `access_id`, in dumps recording it
+    /// or after [`join_access_ids`](Self::join_access_ids).
+    pub fn access(&self, access_id: u64) -> Option<&MergedPtr> {
+        self.by_access.get(&access_id).map(|&i| &self.unsafe_ptrs[i])
+    }
+
+    /// site entries that were merged away as duplicates.
+    pub fn duplicate_sites(&self) -> usize {
+        self.sites.iter().map(|s| s.nodes.len() - 1).sum()
+    }
+}
@@ -0,0 +1,99 @@
+//! serde models of one `svf_pts_to_<module>_N.json` dump.
+//!
+//! field names follow the json keys. unknown keys are ignored, so newer plugin
+//! versions can add fields without breaking older readers.
+
+use serde::{Deserialize, Serialize};
+
+/// debug location of an instruction.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SourceLoc {
+    pub file: String,
+    pub line: u64,
+    pub col: u64,
+}
+
+impl std::fmt::Display for SourceLoc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.col)
+    }
+}
+
+/// an abstract heap object of svf's andersen analysis.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeapObject {
+    pub node_id: u64,
+    pub alloc_fn: String,
+    /// static size in bytes, 0 when svf does not know it.
+    pub size: u64,
+    /// the allocating llvm instruction.
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+}
+
+/// a pointer dereferenced inside an unsafe region, with its points-to set.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnsafePtr {
+    pub instruction: String,
+    /// mangled name of the enclosing function.
+    pub function: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+    /// node ids of every object the pointer may point to.
+    pub targets: Vec<u64>,
+    /// how many of `targets` are heap objects.
+    pub num_heap_targets: u64,
+    /// the id the instrumentation passes to `__svf_check_heap_access` for this
+    /// access, in dumps that record it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_id: Option<u64>,
+}
+
+/// a call to an allocation function; `node_id` is the runtime site id.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AllocationSite {
+    pub instruction: String,
+    pub node_id: u64,
+    pub alloc_fn: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+}
+
+/// an unsafe pointer as listed under an aliased allocation site.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PtrRef {
+    pub instruction: String,
+    pub function: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+}
+
+/// an allocation site some unsafe pointer may point into.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AliasedAllocationSite {
+    pub instruction: String,
+    pub node_id: u64,
+    pub alloc_fn: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_loc: Option<SourceLoc>,
+    pub aliased_by_ptrs: Vec<PtrRef>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub module: String,
+    pub abstract_heap_objects_count: u64,
+    pub unsafe_ptrs_count: u64,
+    pub allocation_sites_count: u64,
+}
+
+/// a whole dump, sections in file order.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PtsDump {
+    pub abstract_heap_objects: Vec<HeapObject>,
+    pub unsafe_ptrs: Vec<UnsafePtr>,
+    pub allocation_sites: Vec<AllocationSite>,
+    pub aliased_allocation_sites: Vec<AliasedAllocationSite>,
+    pub summary: Summary,
+}
@@ -0,0 +1,100 @@
+//! serde models of the runtime's json report (`SVF_REPORT_PATH`), limited to the
+//! tables the offline tools read. see `svf_runtime::report` for the writer; the
+//! models serialize back only the fields they read.
+
+use serde::{Deserialize, Serialize};
+
+use crate::validate::Error;
+
+/// the `schema_version` these models follow.
+pub const SCHEMA_VERSION: u64 = 1;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RuntimeReport {
+    pub schema: String,
+    pub schema_version: u64,
+    pub alias: AliasSection,
+    pub unsafe_heap: UnsafeHeap,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AliasSection {
+    pub checks: Vec<RuntimeCheck>,
+}
+
+/// one entry of `alias.checks`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RuntimeCheck {
+    pub id: u64,
+    pub hits: u64,
+    pub aliased: u64,
+    pub aliased_same_object: u64,
+    pub aliased_overlap: u64,
+    /// the prediction the lto plugin compiled in, e.g. `may_alias`.
+    pub prediction: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnsafeHeap {
+    pub confusion: Confusion,
+    pub sites: Vec<SiteConfusion>,
+    pub accesses: Vec<AccessRow>,
+}
+
+/// the per-access confusion matrix; precision and recall in percent.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Confusion {
+    pub tp: u64,
+    pub fp: u64,
+    #[serde(rename = "fn")]
+    pub fn_: u64,
+    pub tn: u64,
+    pub precision: Option<f64>,
+    pub recall: Option<f64>,
+}
+
+/// one entry of `unsafe_heap.sites`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SiteConfusion {
+    pub site_id: u64,
+    pub tp: u64,
+    #[serde(rename = "fn")]
+    pub fn_: u64,
+    pub fp_predictions: u64,
+    pub access_ids: u64,
+    pub tickets: u64,
+}
+
+/// one entry of `unsafe_heap.accesses`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AccessRow {
+    pub access_id: u64,
+    pub executions: u64,
+    pub tp: u64,
+    pub fp: u64,
+    #[serde(rename = "fn")]
+    pub fn_: u64,
+    pub tn: u64,
+    pub site_ids: Vec<u64>,
+}
+
+/// parse a runtime report, rejecting other documents and schema versions.
+pub fn parse(text: &str) -> Result<RuntimeReport, Error> {
+    let report: RuntimeReport = serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_str(text))?;
+    if report.schema != "svf_runtime_report" {
+        return Err(Error::schema(String::from("schema"), format!("not a runtime report: {}", report.schema)));
+    }
+    if report.schema_version != SCHEMA_VERSION {
+        return Err(Error::schema(
+            String::from("schema_version"),
+            format!("unsupported version {}, expected {}", report.schema_version, SCHEMA_VERSION),
+        ));
+    }
+    Ok(report)
+}
+
+/// read and parse the report at `path`.
+pub fn load(path: &std::path::Path) -> Result<RuntimeReport, Error> {
+    let text = std::fs::read_to_string(path).map_err(|e| Error::io(e).in_file(path.to_path_buf()))?;
+    parse(&text).map_err(|e| e.in_file(path.to_path_buf()))
+}
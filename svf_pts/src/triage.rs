@@ -0,0 +1,216 @@
+//! offline triage of the runtime's false negatives.
+//!
+//! the runtime prints one `{"event":"svf_fn", ...}` line per FN access (see
+//! `print_fn_event_json` in `svf_runtime::unsafe_heap_access`), mixed into the
+//! program's stdout. [`parse_log`] picks them out of a run log and [`triage`]
+//! joins them with a merged dump:
+//! - `access_id` with the unsafe pointer of that access, through
+//!   [`WholeProgram::access`] once the plugin's access maps are joined (or for
+//!   dumps recording `access_id` in `unsafe_ptrs`); other events keep only
+//!   their id.
+//! - `runtime_site_id` and `predicted_site_ids` with the allocation sites
+//!   (node ids), through [`WholeProgram::resolve`].
+//!
+//! events are grouped by kind, access location and runtime site, and the groups
+//! are ranked by event count. each group gets a [`Cause`] guessed from the join.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, BufRead};
+
+use serde::Deserialize;
+
+use crate::merge::{MergedPtr, Resolved, WholeProgram};
+
+/// `svf_runtime::heap::UNINSTRUMENTED_SITE_ID`: objects seen only by the
+/// preload interposers.
+pub const UNINSTRUMENTED_SITE_ID: u64 = u64::MAX;
+/// `svf_runtime::tracking_alloc::TRACKED_SITE_TAG`: objects of the tracking
+/// global allocator.
+pub const TRACKED_SITE_TAG: u64 = 1 << 63;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FnKind {
+    /// svf predicted no heap object at all.
+    EmptyPrediction,
+    /// svf predicted heap objects, but not the accessed one's site.
+    SiteMismatch,
+    /// as `SiteMismatch`, with more predictions than the runtime buffer holds.
+    SiteMismatchPossiblyTruncated,
+}
+
+impl FnKind {
+    pub const ALL: [FnKind; 3] = [FnKind::EmptyPrediction, FnKind::SiteMismatch, FnKind::SiteMismatchPossiblyTruncated];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FnKind::EmptyPrediction => "empty_prediction",
+            FnKind::SiteMismatch => "site_mismatch",
+            FnKind::SiteMismatchPossiblyTruncated => "site_mismatch_possibly_truncated",
+        }
+    }
+}
+
+/// one `svf_fn` line.
+#[derive(Clone, Debug, Deserialize)]
+pub struct FnEvent {
+    pub kind: FnKind,
+    pub access_id: u64,
+    pub ptr: String,
+    pub is_load: bool,
+    pub heap_ticket: u64,
+    pub runtime_site_id: u64,
+    #[serde(default)]
+    pub stack_id: Option<u32>,
+    pub predicted_site_ids: Vec<u64>,
+    pub predicted_site_count: u64,
+    pub predicted_site_count_uncapped: u64,
+}
+
+/// an `svf_fn` line that could not be parsed, e.g. one interleaved with
+/// another thread's output.
+#[derive(Clone, Debug)]
+pub struct LogError {
+    /// 1-based.
+    pub line: usize,
+    pub message: String,
+}
+
+const EVENT_PREFIX: &str = "{\"event\":\"svf_fn\"";
+
+/// the fn events of a run log, skipping every other line.
+pub fn parse_log(log: impl BufRead) -> io::Result<(Vec<FnEvent>, Vec<LogError>)> {
+    let mut events = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in log.split(b'\n').enumerate() {
+        let line = String::from_utf8_lossy(&line?).into_owned();
+        let Some(start) = line.find(EVENT_PREFIX) else { continue };
+        match serde_json::Deserializer::from_str(&line[start..]).into_iter::<FnEvent>().next() {
+            Some(Ok(event)) => events.push(event),
+            Some(Err(e)) => errors.push(LogError { line: i + 1, message: e.to_string() }),
+            None => unreachable!("the line holds an event prefix"),
+        }
+    }
+    Ok((events, errors))
+}
+
+/// the most likely reason for a group of false negatives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cause {
+    /// the object comes from an allocation the instrumentation never saw.
+    UninstrumentedAlloc,
+    /// the runtime site id is not a node id of any merged dump.
+    UnknownSite,
+    /// the runtime site id names different sites in different dumps.
+    AmbiguousSite,
+    /// the predictions were cut at the runtime's buffer size.
+    Truncated,
+    /// the dump's points-to set has the runtime site, but the runtime did not
+    /// get it: the instrumentation lost predictions.
+    DroppedPrediction,
+    /// svf predicted another site with the same `alloc_fn` and source location,
+    /// e.g. another inlined copy of the same allocation.
+    SiblingSite,
+    /// the dump's points-to set misses the runtime site: svf is unsound here.
+    MissingTarget,
+    /// the access id is not joined with the dumps, so nothing more is known.
+    Unjoined,
+}
+
+impl Cause {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Cause::UninstrumentedAlloc => "object allocated outside the instrumentation",
+            Cause::UnknownSite => "runtime site not in the dumps",
+            Cause::AmbiguousSite => "runtime site id collides across modules",
+            Cause::Truncated => "prediction truncated by the runtime buffer",
+            Cause::DroppedPrediction => "dump predicts the site, runtime did not receive it",
+            Cause::SiblingSite => "predicted a sibling site at the same source location",
+            Cause::MissingTarget => "svf points-to set misses the site",
+            Cause::Unjoined => "access not in the dumps",
+        }
+    }
+}
+
+/// false negatives sharing a kind, access location and runtime site.
+pub struct Group<'a> {
+    pub kind: FnKind,
+    /// the accessing pointer, when its access id is in the dumps.
+    pub access: Option<&'a MergedPtr>,
+    pub runtime_site_id: u64,
+    pub site: Resolved<'a>,
+    pub events: u64,
+    pub access_ids: BTreeSet<u64>,
+    pub tickets: BTreeSet<u64>,
+    /// predicted site id and how many events predicted it.
+    pub predicted: BTreeMap<u64, u64>,
+    pub cause: Cause,
+}
+
+/// group and rank `events`, most events first.
+pub fn triage<'a>(events: &[FnEvent], program: &'a WholeProgram) -> Vec<Group<'a>> {
+    type Key = (FnKind, Option<(String, Option<String>)>, u64);
+    let mut groups: HashMap<Key, Group<'a>> = HashMap::new();
+    for event in events {
+        let access = program.access(event.access_id);
+        let location = access.map(|p| (p.function.clone(), p.source_loc.as_ref().map(|l| l.to_string())));
+        let group = groups.entry((event.kind, location, event.runtime_site_id)).or_insert_with(|| Group {
+            kind: event.kind,
+            access,
+            runtime_site_id: event.runtime_site_id,
+            site: program.resolve(event.runtime_site_id),
+            events: 0,
+            access_ids: BTreeSet::new(),
+            tickets: BTreeSet::new(),
+            predicted: BTreeMap::new(),
+            cause: Cause::Unjoined,
+        });
+        group.events += 1;
+        group.access_ids.insert(event.access_id);
+        group.tickets.insert(event.heap_ticket);
+        for &site_id in &event.predicted_site_ids {
+            *group.predicted.entry(site_id).or_default() += 1;
+        }
+    }
+
+    let mut groups: Vec<Group> = groups.into_values().collect();
+    for group in &mut groups {
+        group.cause = cause(group, program);
+    }
+    groups.sort_by(|a, b| {
+        b.events
+            .cmp(&a.events)
+            .then(b.access_ids.len().cmp(&a.access_ids.len()))
+            .then(a.kind.cmp(&b.kind))
+            .then(a.runtime_site_id.cmp(&b.runtime_site_id))
+            .then(a.access_ids.cmp(&b.access_ids))
+    });
+    groups
+}
+
+fn cause(group: &Group, program: &WholeProgram) -> Cause {
+    let site = match group.site {
+        _ if group.runtime_site_id == UNINSTRUMENTED_SITE_ID || group.runtime_site_id & TRACKED_SITE_TAG != 0 => {
+            return Cause::UninstrumentedAlloc
+        }
+        Resolved::Unknown => return Cause::UnknownSite,
+        Resolved::Ambiguous(_) => return Cause::AmbiguousSite,
+        Resolved::Site(site) => site,
+    };
+    if group.kind == FnKind::SiteMismatchPossiblyTruncated {
+        return Cause::Truncated;
+    }
+    if let Some(access) = group.access {
+        if access.heap_targets.contains(&site.key) {
+            return Cause::DroppedPrediction;
+        }
+    }
+    let sibling = group.predicted.keys().any(|&id| match program.resolve(id) {
+        Resolved::Site(p) => p.key != site.key && p.alloc_fn == site.alloc_fn && p.source_loc.is_some() && p.source_loc == site.source_loc,
+        _ => false,
+    });
+    if sibling {
+        return Cause::SiblingSite;
+    }
+    if group.access.is_some() { Cause::MissingTarget } else { Cause::Unjoined }
+}
@@ -0,0 +1,143 @@
+//! errors, and the consistency checks serde cannot express.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+use crate::model::PtsDump;
+
+#[derive(Debug)]
+pub enum ErrorKind {
+    Io(io::Error),
+    /// the file is not json.
+    Syntax(serde_json::Error),
+    /// the json does not describe a valid dump.
+    Schema(String),
+}
+
+/// an error reading a dump, pointing at the offending file and entry.
+#[derive(Debug)]
+pub struct Error {
+    pub file: Option<PathBuf>,
+    /// json path of the offending value, e.g. `unsafe_ptrs[3].targets`; empty
+    /// for errors about the whole file.
+    pub at: String,
+    pub kind: ErrorKind,
+}
+
+impl Error {
+    pub(crate) fn schema(at: String, message: String) -> Self {
+        Self { file: None, at, kind: ErrorKind::Schema(message) }
+    }
+
+    pub(crate) fn io(e: io::Error) -> Self {
+        Self { file: None, at: String::new(), kind: ErrorKind::Io(e) }
+    }
+
+    pub(crate) fn in_file(mut self, file: PathBuf) -> Self {
+        self.file = Some(file);
+        self
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for Error {
+    fn from(e: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let at = e.path().to_string();
+        let inner = e.into_inner();
+        if inner.is_data() {
+            Self::schema(at, inner.to_string())
+        } else {
+            Self { file: None, at: String::new(), kind: ErrorKind::Syntax(inner) }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(file) = &self.file {
+            write!(f, "{}: ", file.display())?;
+        }
+        if !self.at.is_empty() && self.at != "." {
+            write!(f, "{}: ", self.at)?;
+        }
+        match &self.kind {
+            ErrorKind::Io(e) => write!(f, "{}", e),
+            ErrorKind::Syntax(e) => write!(f, "invalid json: {}", e),
+            ErrorKind::Schema(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.kind {
+            ErrorKind::Io(e) => Some(e),
+            ErrorKind::Syntax(e) => Some(e),
+            ErrorKind::Schema(_) => None,
+        }
+    }
+}
+
+/// check what serde cannot: summary counts, unique node and access ids and
+/// references between sections. reports the first problem in file order.
+pub(crate) fn validate(dump: &PtsDump) -> Result<(), Error> {
+    unique_ids("abstract_heap_objects", dump.abstract_heap_objects.iter().map(|o| o.node_id))?;
+
+    for (i, ptr) in dump.unsafe_ptrs.iter().enumerate() {
+        if ptr.num_heap_targets > ptr.targets.len() as u64 {
+            return Err(Error::schema(
+                format!("unsafe_ptrs[{}].num_heap_targets", i),
+                format!("{} heap targets but only {} targets", ptr.num_heap_targets, ptr.targets.len()),
+            ));
+        }
+    }
+
+    let mut accesses: HashMap<u64, usize> = HashMap::new();
+    for (i, ptr) in dump.unsafe_ptrs.iter().enumerate() {
+        let Some(access_id) = ptr.access_id else { continue };
+        if let Some(j) = accesses.insert(access_id, i) {
+            return Err(Error::schema(
+                format!("unsafe_ptrs[{}].access_id", i),
+                format!("duplicate access_id {}, first at unsafe_ptrs[{}]", access_id, j),
+            ));
+        }
+    }
+
+    unique_ids("allocation_sites", dump.allocation_sites.iter().map(|s| s.node_id))?;
+    unique_ids("aliased_allocation_sites", dump.aliased_allocation_sites.iter().map(|s| s.node_id))?;
+    let sites: HashSet<u64> = dump.allocation_sites.iter().map(|s| s.node_id).collect();
+    for (i, aliased) in dump.aliased_allocation_sites.iter().enumerate() {
+        if !sites.contains(&aliased.node_id) {
+            return Err(Error::schema(
+                format!("aliased_allocation_sites[{}].node_id", i),
+                format!("node_id {} is not in allocation_sites", aliased.node_id),
+            ));
+        }
+    }
+
+    let counts = [
+        ("abstract_heap_objects_count", dump.summary.abstract_heap_objects_count, dump.abstract_heap_objects.len()),
+        ("unsafe_ptrs_count", dump.summary.unsafe_ptrs_count, dump.unsafe_ptrs.len()),
+        ("allocation_sites_count", dump.summary.allocation_sites_count, dump.allocation_sites.len()),
+    ];
+    for (field, count, len) in counts {
+        if count != len as u64 {
+            return Err(Error::schema(format!("summary.{}", field), format!("count is {} but the section has {} entries", count, len)));
+        }
+    }
+    Ok(())
+}
+
+fn unique_ids(section: &str, ids: impl Iterator<Item = u64>) -> Result<(), Error> {
+    let mut first: HashMap<u64, usize> = HashMap::new();
+    for (i, id) in ids.enumerate() {
+        if let Some(j) = first.insert(id, i) {
+            return Err(Error::schema(
+                format!("{}[{}].node_id", section, i),
+                format!("duplicate node_id {}, first at {}[{}]", id, section, j),
+            ));
+        }
+    }
+    Ok(())
+}
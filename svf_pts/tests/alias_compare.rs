@@ -0,0 +1,41 @@
+//! a real runtime report joined with checker output gives the expected matrix.
+
+use svf_pts::alias_compare::{parse_id_lines, parse_runtime_report, Comparison, GroundTruth, Verdict};
+use svf_runtime::alias::__svf_check_alias;
+use svf_runtime::report::Report;
+
+const ALIAS: u32 = 1 << 31;
+
+#[test]
+fn report_and_checker_output_join_per_id() {
+    let (a, b) = (0x1000usize, 0x2000usize);
+    unsafe {
+        __svf_check_alias(a, a, 1 | ALIAS);
+        __svf_check_alias(a, b, 2 | ALIAS);
+        __svf_check_alias(a, b, 3);
+        __svf_check_alias(b, b, 3);
+        __svf_check_alias(a, b, 4);
+        __svf_check_alias(a, b, 5);
+    }
+    let checks = parse_runtime_report(&Report::collect().to_json()).unwrap();
+    let checker = "SVF Analysis Done. Checking instrumentation points...\nID:1 RES:1\nID:2 RES:0\nID:2 RES:1\nID:3 RES:0\nID:4 RES:0\nID:6 RES:1\n";
+    let checker = parse_id_lines(checker.as_bytes(), false).unwrap();
+
+    let comparison = Comparison::from_report(&checks, GroundTruth::Exact, &checker);
+    let verdicts: Vec<(u64, Verdict)> = comparison.rows.iter().map(|r| (r.id, r.verdict())).collect();
+    assert_eq!(
+        verdicts,
+        [(1, Verdict::TruePositive), (2, Verdict::FalsePositive), (3, Verdict::FalseNegative), (4, Verdict::TrueNegative)]
+    );
+    assert_eq!(comparison.runtime_only, [5]);
+    assert_eq!(comparison.static_only, [6]);
+    assert_eq!(comparison.rows[2].runtime.as_ref().unwrap().hits, 2);
+
+    let m = comparison.metrics();
+    assert_eq!((m.precision(), m.recall(), m.f1(), m.accuracy()), (Some(0.5), Some(0.5), Some(0.5), Some(0.5)));
+
+    // legacy runtime lines: an id aliases if any execution did.
+    let runtime = parse_id_lines("ID:1 RES:1\nID:3 RES:0\nID:3 RES:1\nID:4 RES:0\n".as_bytes(), true).unwrap();
+    let m = Comparison::from_id_lines(&runtime, &checker).metrics();
+    assert_eq!((m.tp, m.tn, m.fp, m.fn_), (1, 1, 0, 1));
+}
@@ -0,0 +1,76 @@
+//! fixtures shared by the tests: the sample dumps next to `svf_runtime`, the
+//! access map the plugin writes for a dump, a dump as another build numbers it,
+//! and build directories holding both.
+
+#![allow(dead_code)]
+
+use std::path::{Path, PathBuf};
+
+use svf_pts::access_map::{AccessMap, AccessRecord};
+use svf_pts::PtsDump;
+
+pub const CGU_A: &str = "svf_pts_to_svf_runtime.4c13d981843c8c2d-cgu.0_0.json";
+pub const CGU_B: &str = "svf_pts_to_svf_runtime.9b70b18189514b16-cgu.0_0.json";
+
+/// the directory holding the sample dumps.
+pub fn samples() -> &'static Path {
+    Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/../svf_runtime"))
+}
+
+pub fn sample(name: &str) -> PtsDump {
+    svf_pts::load(&samples().join(name)).unwrap()
+}
+
+/// the map the plugin writes for `dump`, access ids 1.. in pointer order. the
+/// lto module inlines further, so local names and metadata differ.
+pub fn access_map(dump: &PtsDump) -> AccessMap {
+    let accesses = dump
+        .unsafe_ptrs
+        .iter()
+        .enumerate()
+        .map(|(i, p)| AccessRecord {
+            access_id: i as u64 + 1,
+            is_load: p.instruction.contains(" load "),
+            function: p.function.clone(),
+            instruction: p.instruction.replace(".i", ".i.i").replace("!dbg !", "!dbg !9"),
+            source_loc: p.source_loc.clone(),
+        })
+        .collect();
+    AccessMap { module: String::from("ld-temp.o"), accesses }
+}
+
+/// a fresh directory holding `dump` and its access map, as a build leaves them.
+pub fn build_dir(name: &str, dump: &PtsDump) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("svf_pts_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let module = &dump.summary.module;
+    std::fs::write(dir.join(format!("svf_pts_to_{}_0.json", module)), serde_json::to_string(dump).unwrap()).unwrap();
+    std::fs::write(dir.join(format!("svf_access_ids_{}.json", module)), serde_json::to_string(&access_map(dump)).unwrap()).unwrap();
+    dir
+}
+
+/// `dump` as another codegen unit or build would number it: other node ids,
+/// metadata, attribute groups and local value names (deeper inlining, other
+/// numbering).
+pub fn renumbered(dump: &PtsDump, module: &str) -> PtsDump {
+    let renumber = |s: &str| s.replace(" #19", " #3").replace("!dbg !", "!dbg !7").replace(".i", ".i.i").replace("%1", "%71");
+    let mut dump = dump.clone();
+    dump.summary.module = module.to_string();
+    for o in &mut dump.abstract_heap_objects {
+        o.node_id += 100_000;
+        o.source = renumber(&o.source);
+    }
+    for s in &mut dump.allocation_sites {
+        s.node_id += 100_000;
+        s.instruction = renumber(&s.instruction);
+    }
+    for s in &mut dump.aliased_allocation_sites {
+        s.node_id += 100_000;
+    }
+    for p in &mut dump.unsafe_ptrs {
+        p.instruction = renumber(&p.instruction);
+        p.targets.iter_mut().for_each(|t| *t += 100_000);
+    }
+    dump
+}
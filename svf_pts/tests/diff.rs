@@ -0,0 +1,135 @@
+//! two runs diff per access and per site; with the dumps of both builds, sites
+//! are matched across renumbered node ids.
+
+mod common;
+
+use svf_pts::diff::{target_changes, Change, Outcome, RunDiff, SiteRef};
+use svf_pts::merge::Resolved;
+use svf_pts::report::{self, AccessRow, AliasSection, Confusion, RuntimeReport, SiteConfusion, UnsafeHeap};
+use svf_runtime::heap::__svf_report_alloc;
+use svf_runtime::report::Report;
+use svf_runtime::unsafe_heap_access::{__svf_analyze_heap_obj, __svf_check_heap_access};
+
+use common::{build_dir, renumbered, sample, CGU_A};
+
+fn report(tp_fp_fn_tn: [u64; 4], sites: &[(u64, u64, u64, u64)], accesses: &[(u64, [u64; 4])]) -> UnsafeHeap {
+    let [tp, fp, fn_, tn] = tp_fp_fn_tn;
+    let percent = |num: u64, den: u64| (den > 0).then(|| num as f64 / den as f64 * 100.0);
+    let unsafe_heap = UnsafeHeap {
+        confusion: Confusion { tp, fp, fn_, tn, precision: percent(tp, tp + fp), recall: percent(tp, tp + fn_) },
+        sites: sites
+            .iter()
+            .map(|&(site_id, tp, fn_, fp_predictions)| SiteConfusion { site_id, tp, fn_, fp_predictions, access_ids: 1, tickets: 1 })
+            .collect(),
+        accesses: accesses
+            .iter()
+            .map(|&(access_id, [tp, fp, fn_, tn])| AccessRow { access_id, executions: tp + fp + fn_ + tn, tp, fp, fn_, tn, site_ids: Vec::new() })
+            .collect(),
+    };
+    let report = RuntimeReport {
+        schema: String::from("svf_runtime_report"),
+        schema_version: report::SCHEMA_VERSION,
+        alias: AliasSection { checks: Vec::new() },
+        unsafe_heap,
+    };
+    // through json, as the tools read it.
+    report::parse(&serde_json::to_string(&report).unwrap()).unwrap().unsafe_heap
+}
+
+#[test]
+fn runs_diff_per_access_and_site() {
+    let before = report(
+        [9, 1, 1, 2],
+        &[(8230, 5, 0, 0), (4520, 1, 0, 3), (777, 1, 0, 0)],
+        &[(1, [5, 0, 0, 0]), (2, [3, 0, 1, 0]), (3, [0, 0, 0, 2]), (4, [0, 1, 0, 0]), (5, [1, 0, 0, 0])],
+    );
+    let after = report(
+        [6, 2, 2, 0],
+        &[(108230, 4, 2, 0), (104520, 1, 0, 0)],
+        &[(1, [0, 0, 2, 0]), (2, [4, 0, 0, 0]), (3, [1, 0, 0, 0]), (4, [0, 2, 0, 0]), (6, [1, 0, 0, 0])],
+    );
+
+    let dump = sample(CGU_A);
+    // the next build renumbers every node, and access 1 loses its target.
+    let mut rebuilt = renumbered(&dump, &dump.summary.module);
+    rebuilt.unsafe_ptrs[0].targets.clear();
+    rebuilt.unsafe_ptrs[0].num_heap_targets = 0;
+    let load = |dir| {
+        let (program, warning) = svf_pts::load_inputs(&[dir]).unwrap().merge();
+        assert_eq!(warning, None);
+        program
+    };
+    let (old_dir, new_dir) = (build_dir("diff_old", &dump), build_dir("diff_new", &rebuilt));
+    let (old, new) = (load(old_dir.clone()), load(new_dir));
+    // a dump alone ties no access to its pointer.
+    let dump_only = std::fs::read_dir(&old_dir).unwrap().map(|e| e.unwrap().path()).find(|p| !svf_pts::access_map::is_access_map(p));
+    let (program, warning) = svf_pts::load_inputs(&[dump_only.unwrap()]).unwrap().merge();
+    assert!(program.access(1).is_none());
+    assert_eq!(warning.as_deref(), Some("no dump or access map records access ids"));
+
+    let diff = RunDiff::new(&before, &after, Some((&old, &new)));
+    let accesses: Vec<(u64, Change)> = diff.accesses.iter().map(|a| (a.access_id, a.change)).collect();
+    assert_eq!(
+        accesses,
+        [(1, Change::Regression), (2, Change::Improvement), (3, Change::Changed), (5, Change::Removed), (6, Change::Added)]
+    );
+    assert_eq!(diff.accesses[0].outcomes(), (Some(Outcome::Tp), Some(Outcome::Fn)));
+
+    let Resolved::Site(site) = old.resolve(8230) else { panic!("8230 is a site") };
+    let Resolved::Site(rebuilt_site) = new.resolve(108230) else { panic!("108230 is a site") };
+    assert_eq!(site.key, rebuilt_site.key);
+    let Resolved::Site(improved) = old.resolve(4520) else { panic!("4520 is a site") };
+    let mut sites: Vec<(SiteRef, Change)> = diff.sites.iter().map(|s| (s.site, s.change)).collect();
+    sites.sort_by_key(|s| s.1);
+    assert_eq!(
+        sites,
+        [(SiteRef::Key(site.key), Change::Regression), (SiteRef::Key(improved.key), Change::Improvement), (SiteRef::Id(777), Change::Removed)]
+    );
+    assert_eq!(diff.regressions(), 2);
+    assert_eq!(diff.recall_delta(), Some(-15.0));
+    assert_eq!(diff.precision_delta(), Some(-15.0));
+    assert_eq!(target_changes(&old, &new, 1), Some((vec![site.key], vec![])));
+
+    // without the dumps the renumbered sites do not line up.
+    let diff = RunDiff::new(&before, &after, None);
+    assert_eq!(diff.site_count(Change::Removed), 3);
+    assert_eq!(diff.site_count(Change::Added), 2);
+    assert_eq!(diff.regressions(), 1);
+}
+
+#[test]
+fn other_documents_are_rejected() {
+    let e = report::parse("{\"schema\":\"svf_pts\",\"schema_version\":1,\"alias\":{\"checks\":[]},\"unsafe_heap\":{\"confusion\":{\"tp\":0,\"fp\":0,\"fn\":0,\"tn\":0,\"precision\":null,\"recall\":null},\"sites\":[],\"accesses\":[]}}")
+        .unwrap_err();
+    assert_eq!(e.to_string(), "schema: not a runtime report: svf_pts");
+}
+
+#[test]
+fn runtime_reports_diff() {
+    let collect = || report::parse(&Report::collect().to_json()).unwrap().unsafe_heap;
+    let (a, not_heap) = (0x7700_0000usize, &0u64 as *const u64 as *const u8);
+    unsafe {
+        __svf_report_alloc(a as *mut u8, 64, 8230);
+        for access_id in [1, 2] {
+            __svf_analyze_heap_obj(a as *const u8, 8230);
+            __svf_check_heap_access(a as *const u8, true, access_id);
+        }
+    }
+    let before = collect();
+    // the second run loses the prediction of access 2 and runs access 3 too.
+    unsafe {
+        __svf_check_heap_access((a + 8) as *const u8, false, 2);
+        __svf_check_heap_access(not_heap, true, 3);
+    }
+    let after = collect();
+
+    let diff = RunDiff::new(&before, &after, None);
+    let accesses: Vec<(u64, Change)> = diff.accesses.iter().map(|a| (a.access_id, a.change)).collect();
+    assert_eq!(accesses, [(2, Change::Regression), (3, Change::Added)]);
+    assert_eq!(diff.accesses[1].outcomes(), (None, Some(Outcome::Tn)));
+    let sites: Vec<(SiteRef, Change)> = diff.sites.iter().map(|s| (s.site, s.change)).collect();
+    assert_eq!(sites, [(SiteRef::Id(8230), Change::Regression)]);
+    assert_eq!(diff.regressions(), 2);
+    assert_eq!((diff.before.tp, diff.after.fn_, diff.after.tn), (2, 1, 1));
+    assert_eq!(diff.recall_delta().map(|d| d.round()), Some(-33.0));
+}
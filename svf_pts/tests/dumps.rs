@@ -0,0 +1,80 @@
+//! the sample dumps next to `svf_runtime` load and index, and broken dumps are
+//! rejected with the path of the offending entry.
+
+mod common;
+
+use svf_pts::{DumpIndex, ErrorKind};
+
+use common::{sample, samples, CGU_A};
+
+fn sample_json() -> serde_json::Value {
+    serde_json::from_str(&std::fs::read_to_string(samples().join(CGU_A)).unwrap()).unwrap()
+}
+
+/// the error for the sample with `edit` applied.
+fn error_after(edit: impl FnOnce(&mut serde_json::Value)) -> svf_pts::Error {
+    let mut json = sample_json();
+    edit(&mut json);
+    svf_pts::parse(&json.to_string()).unwrap_err()
+}
+
+#[test]
+fn samples_load_and_index() {
+    let files = svf_pts::dump_files(samples()).unwrap();
+    assert_eq!(files.len(), 3);
+    for file in &files {
+        svf_pts::load(file).unwrap();
+    }
+
+    let dump = sample(CGU_A);
+    assert_eq!(dump.summary.module, "svf_runtime.4c13d981843c8c2d-cgu.0");
+    let index = DumpIndex::new(&dump);
+
+    assert_eq!(index.object(8230).unwrap().alloc_fn, "__rust_alloc");
+    assert!(index.object(1).is_none());
+    assert_eq!(index.aliased_site(8230).unwrap().aliased_by_ptrs.len(), 6);
+
+    let functions = index.functions();
+    assert_eq!(functions.len(), 1);
+    let ptrs = index.unsafe_ptrs_in(functions[0]);
+    assert_eq!(ptrs.len(), 6);
+    assert!(ptrs.iter().all(|p| index.heap_targets(p).iter().map(|o| o.node_id).eq([8230])));
+    assert!(index.unsafe_ptrs_in("main").is_empty());
+
+    let alloc_rs = index.files().into_iter().find(|f| f.ends_with("library/alloc/src/alloc.rs")).unwrap();
+    assert_eq!(index.sites_in_file(alloc_rs).len(), dump.allocation_sites.len());
+
+    // serializing keeps every field.
+    assert_eq!(svf_pts::parse(&serde_json::to_string(&dump).unwrap()).unwrap(), dump);
+}
+
+#[test]
+fn errors_point_at_the_entry() {
+    let e = error_after(|j| j["unsafe_ptrs"][3]["targets"][0] = "8230".into());
+    assert_eq!(e.at, "unsafe_ptrs[3].targets[0]");
+    assert!(matches!(e.kind, ErrorKind::Schema(_)));
+
+    let e = error_after(|j| {
+        j["allocation_sites"][2].as_object_mut().unwrap().remove("alloc_fn");
+    });
+    assert_eq!(e.at, "allocation_sites[2]");
+    assert!(e.to_string().contains("missing field `alloc_fn`"), "{}", e);
+
+    let e = error_after(|j| j["unsafe_ptrs"][1]["num_heap_targets"] = 2.into());
+    assert_eq!(e.at, "unsafe_ptrs[1].num_heap_targets");
+
+    let e = error_after(|j| j["allocation_sites"][4]["node_id"] = j["allocation_sites"][1]["node_id"].clone());
+    assert_eq!(e.at, "allocation_sites[4].node_id");
+    assert!(e.to_string().contains("first at allocation_sites[1]"), "{}", e);
+
+    let e = error_after(|j| j["aliased_allocation_sites"][0]["node_id"] = 1.into());
+    assert_eq!(e.at, "aliased_allocation_sites[0].node_id");
+
+    let e = error_after(|j| j["summary"]["unsafe_ptrs_count"] = 7.into());
+    assert_eq!(e.at, "summary.unsafe_ptrs_count");
+
+    let e = svf_pts::parse("{\"abstract_heap_objects\": [").unwrap_err();
+    assert!(matches!(e.kind, ErrorKind::Syntax(_)));
+    let e = svf_pts::load(&samples().join("svf_pts_to_missing_0.json")).unwrap_err();
+    assert!(matches!(e.kind, ErrorKind::Io(_)) && e.to_string().contains("svf_pts_to_missing_0.json"));
+}
@@ -0,0 +1,61 @@
+//! merging dedups sites across modules by their stable key and flags node ids
+//! that name different sites.
+
+mod common;
+
+use svf_pts::merge::{normalize_instruction, Resolved, WholeProgram};
+
+use common::{renumbered, sample, CGU_A, CGU_B};
+
+#[test]
+fn instructions_lose_per_module_numbering() {
+    assert_eq!(
+        normalize_instruction("  %_0 = call ptr @__rust_alloc(i64 %_3, i64 %_12) #21, !dbg !1225"),
+        "call ptr @__rust_alloc(i64 %0, i64 %1)"
+    );
+    // the same call inlined one level deeper, in another function.
+    assert_eq!(
+        normalize_instruction("  %21 = call ptr @__rust_alloc(i64 %_3.i, i64 %12) #3, !dbg !7"),
+        "call ptr @__rust_alloc(i64 %0, i64 %1)"
+    );
+    assert_eq!(
+        normalize_instruction("  store i8 %ctrl.i, ptr %\"x y\", align 1, !dbg !3721"),
+        "store i8 %0, ptr %1, align 1"
+    );
+    // reused values stay distinct from fresh ones.
+    assert_ne!(normalize_instruction("  %s = add i64 %a, %a"), normalize_instruction("  %s = add i64 %a, %b"));
+    assert_eq!(normalize_instruction("  %s = add i64 %a, %a"), "add i64 %0, %0");
+}
+
+#[test]
+fn sites_merge_across_modules() {
+    let a = sample(CGU_A);
+    let b = renumbered(&a, "other-cgu.1");
+    let program = WholeProgram::merge([&a, &a, &b]);
+
+    assert_eq!(program.modules.len(), 2);
+    assert_eq!(program.sites.len(), a.allocation_sites.len());
+    assert_eq!(program.duplicate_sites(), a.allocation_sites.len());
+    assert!(program.collisions.is_empty());
+
+    let Resolved::Site(site) = program.resolve(8230) else { panic!("8230 should resolve") };
+    assert!(matches!(program.resolve(108230), Resolved::Site(s) if s.key == site.key));
+    assert_eq!(site.nodes.len(), 2);
+    assert!(matches!(program.resolve(1), Resolved::Unknown));
+
+    assert_eq!(program.unsafe_ptrs.len(), a.unsafe_ptrs.len());
+    for ptr in &program.unsafe_ptrs {
+        assert_eq!(ptr.heap_targets, [site.key]);
+        assert_eq!(ptr.modules.len(), 2);
+    }
+}
+
+#[test]
+fn colliding_node_ids_are_ambiguous() {
+    // two builds from different checkouts: same node ids, different source paths.
+    let program = WholeProgram::merge([&sample(CGU_A), &sample(CGU_B)]);
+    assert_eq!(program.collisions.len(), 9);
+    let Resolved::Ambiguous(collision) = program.resolve(8230) else { panic!("8230 should collide") };
+    assert_eq!(collision.sites.len(), 2);
+    assert!(collision.sites.iter().all(|s| s.modules.len() == 1));
+}
@@ -0,0 +1,95 @@
+//! fn events are picked out of a noisy run log, joined with the dumps through
+//! an access map, and grouped by likely cause.
+
+mod common;
+
+use svf_pts::access_map::AccessRecord;
+use svf_pts::merge::WholeProgram;
+use svf_pts::triage::{parse_log, triage, Cause, FnKind};
+
+use common::{access_map, sample, CGU_A};
+
+fn event(kind: &str, access_id: u64, site_id: u64, predicted: &[u64], ticket: u64) -> String {
+    format!(
+        "{{\"event\":\"svf_fn\",\"kind\":\"{}\",\"access_id\":{},\"ptr\":\"0x5555deadbeef\",\"is_load\":true,\"heap_ticket\":{},\"runtime_site_id\":{},\"predicted_site_ids\":{:?},\"predicted_site_count\":{},\"predicted_site_count_uncapped\":{}}}",
+        kind, access_id, ticket, site_id, predicted, predicted.len(), predicted.len()
+    )
+}
+
+#[test]
+fn fn_events_group_by_cause() {
+    let dump = sample(CGU_A);
+    let mut program = WholeProgram::merge([&dump]);
+    assert_eq!(program.join_access_ids([&access_map(&dump)]), dump.unsafe_ptrs.len());
+
+    let log = [
+        String::from("program output"),
+        event("empty_prediction", 1, 8230, &[], 10),
+        event("empty_prediction", 1, 8230, &[], 11),
+        format!("interleaved {}", event("site_mismatch", 2, 4520, &[8230], 12)),
+        event("empty_prediction", 99, 4520, &[], 13),
+        event("empty_prediction", 3, u64::MAX, &[], 14),
+        event("site_mismatch", 4, 77, &[8230], 15).replace("\"predicted_site_ids\"", "\"stack_id\":3,\"predicted_site_ids\""),
+        String::from("{\"event\":\"svf_fn\",\"kind\":\"site_mis"),
+    ]
+    .join("\n");
+    let (events, errors) = parse_log(log.as_bytes()).unwrap();
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[5].stack_id, Some(3));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].line, 8);
+
+    let groups = triage(&events, &program);
+    let causes: Vec<(FnKind, u64, Cause)> = groups.iter().map(|g| (g.kind, g.events, g.cause)).collect();
+    assert_eq!(
+        causes,
+        [
+            (FnKind::EmptyPrediction, 2, Cause::DroppedPrediction),
+            (FnKind::EmptyPrediction, 1, Cause::Unjoined),
+            (FnKind::EmptyPrediction, 1, Cause::UninstrumentedAlloc),
+            (FnKind::SiteMismatch, 1, Cause::UnknownSite),
+            (FnKind::SiteMismatch, 1, Cause::SiblingSite),
+        ]
+    );
+    assert_eq!(groups[0].tickets.len(), 2);
+    assert_eq!(groups[0].access.unwrap().access_ids, [1]);
+    assert!(groups[1].access.is_none());
+}
+
+#[test]
+fn access_maps_join_the_dumps() {
+    let dump = sample(CGU_A);
+    let mut map = access_map(&dump);
+    // an access the dumps do not list, and one found by its location alone.
+    map.accesses.push(AccessRecord {
+        access_id: 50,
+        is_load: false,
+        function: String::from("main"),
+        instruction: String::from("  store i8 0, ptr %p, align 1"),
+        source_loc: None,
+    });
+    let mut moved = map.accesses[2].clone();
+    moved.access_id = 51;
+    moved.instruction = String::from("  %v = load i64, ptr %other, align 8");
+    map.accesses.push(moved);
+
+    let text = serde_json::to_string(&map).unwrap();
+    let map = svf_pts::access_map::parse(&text).unwrap();
+    let mut program = WholeProgram::merge([&dump]);
+    assert!(program.access(1).is_none());
+    assert_eq!(program.join_access_ids([&map]), dump.unsafe_ptrs.len() + 1);
+
+    // inlined copies normalizing alike keep their order.
+    for (i, ptr) in dump.unsafe_ptrs.iter().enumerate() {
+        assert_eq!(program.access(i as u64 + 1).unwrap().instruction, ptr.instruction);
+    }
+    assert!(program.access(50).is_none());
+    assert_eq!(program.access(51).unwrap().access_ids, [3, 51]);
+    // joining twice adds nothing.
+    program.join_access_ids([&map]);
+    assert_eq!(program.access(3).unwrap().access_ids, [3, 51]);
+
+    let duplicate = text.replace("\"access_id\":51", "\"access_id\":2");
+    let e = svf_pts::access_map::parse(&duplicate).unwrap_err();
+    assert_eq!(e.to_string(), "accesses[7].access_id: duplicate access_id 2, first at accesses[1]");
+}
@@ -0,0 +1,114 @@
+//! throughput of the live heap index against the single `RwLock<BTreeMap>` it
+//! replaced. every thread allocates, looks up and frees objects in its own
+//! address range, like threads working in separate allocator arenas.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Barrier, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use svf_runtime::heap_index::{Entry, HeapIndex};
+
+/// objects each thread keeps live at a time.
+const OBJECTS: usize = 256;
+const OBJECT_SIZE: usize = 48;
+/// lookups per allocated object.
+const LOOKUPS: usize = 4;
+/// distance between the address ranges of two threads.
+const THREAD_SPAN: usize = 64 << 20;
+
+trait Index: Send + Sync + 'static {
+    fn insert(&self, base: usize, entry: Entry);
+    fn remove(&self, base: usize);
+    fn containing(&self, addr: usize) -> bool;
+}
+
+struct Baseline(RwLock<BTreeMap<usize, Entry>>);
+
+impl Index for Baseline {
+    fn insert(&self, base: usize, entry: Entry) {
+        self.0.write().unwrap().insert(base, entry);
+    }
+
+    fn remove(&self, base: usize) {
+        self.0.write().unwrap().remove(&base);
+    }
+
+    fn containing(&self, addr: usize) -> bool {
+        let map = self.0.read().unwrap();
+        map.range(..=addr).next_back().is_some_and(|(&base, &(size, _, _))| addr < base + size)
+    }
+}
+
+impl Index for HeapIndex {
+    fn insert(&self, base: usize, entry: Entry) {
+        HeapIndex::insert(self, base, entry);
+    }
+
+    fn remove(&self, base: usize) {
+        HeapIndex::remove(self, base);
+    }
+
+    fn containing(&self, addr: usize) -> bool {
+        HeapIndex::containing(self, addr).is_some()
+    }
+}
+
+/// `rounds` rounds of allocating, looking up and freeing `OBJECTS` objects in
+/// the range of thread `t`.
+fn workload(index: &dyn Index, t: usize, rounds: u64) {
+    let start = 0x1000_0000_0000 + t * THREAD_SPAN;
+    for round in 0..rounds {
+        // shift the objects every round so they also move between regions.
+        let offset = (round as usize % 1024) * 4096;
+        let base = |i: usize| start + offset + i * OBJECT_SIZE * 2;
+        for i in 0..OBJECTS {
+            index.insert(base(i), (OBJECT_SIZE, t as u64, round));
+        }
+        for i in 0..OBJECTS * LOOKUPS {
+            assert!(index.containing(base(i % OBJECTS) + i % OBJECT_SIZE));
+        }
+        for i in 0..OBJECTS {
+            index.remove(base(i));
+        }
+    }
+}
+
+/// wall time for `threads` threads each running `rounds` rounds concurrently.
+fn run(index: Arc<dyn Index>, threads: usize, rounds: u64) -> Duration {
+    let barrier = Arc::new(Barrier::new(threads + 1));
+    let workers: Vec<_> = (0..threads)
+        .map(|t| {
+            let (index, barrier) = (Arc::clone(&index), Arc::clone(&barrier));
+            thread::spawn(move || {
+                barrier.wait();
+                workload(&*index, t, rounds);
+            })
+        })
+        .collect();
+    barrier.wait();
+    let start = Instant::now();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    start.elapsed()
+}
+
+fn bench_heap_index(c: &mut Criterion) {
+    let mut group = c.benchmark_group("live_heap");
+    for threads in [1, 4, 16] {
+        // operations per round of all threads together.
+        group.throughput(Throughput::Elements((threads * OBJECTS * (2 + LOOKUPS)) as u64));
+        group.bench_with_input(BenchmarkId::new("btree_rwlock", threads), &threads, |b, &threads| {
+            b.iter_custom(|rounds| run(Arc::new(Baseline(RwLock::new(BTreeMap::new()))), threads, rounds))
+        });
+        group.bench_with_input(BenchmarkId::new("sharded", threads), &threads, |b, &threads| {
+            b.iter_custom(|rounds| run(Arc::new(HeapIndex::new()), threads, rounds))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_heap_index);
+criterion_main!(benches);
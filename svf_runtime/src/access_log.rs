@@ -0,0 +1,218 @@
+//! lossless collection of the unsafe access sets and tables.
+//!
+//! the access hooks used to update global `Mutex<BTreeSet<..>>`s with
+//! `try_lock()`, skipping the update on contention, so multithreaded runs
+//! reported different numbers every time. instead, each thread now appends
+//! fixed-size [`Event`]s to a buffer in its own [`ThreadRegistry`] slot:
+//! - pushing an event never allocates, and only contends with a report being
+//!   aggregated;
+//! - a full buffer is folded into the slot's [`AccessSets`] (allocating, with
+//!   hooks disabled) once every `EVENT_BUFFER_CAP` events;
+//! - aggregation replays the still-pending events of every slot, and a thread's
+//!   pending events are kept when its slot is retired at thread exit.
+//!
+//! an event is only lost when the thread's slot is unavailable, i.e. a hook runs
+//! while the thread-locals are being destroyed. those are counted in
+//! [`dropped_events`] rather than silently ignored.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::registry::{Mergeable, ThreadRegistry, ThreadSlot};
+use crate::unsafe_heap_access::AccessClass;
+
+/// events buffered per thread before they are folded into its sets.
+const EVENT_BUFFER_CAP: usize = 256;
+
+/// one update to the access sets, recorded by the hooks.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Event {
+    /// svf predicted `site_id` for an access.
+    Analyzed { site_id: u64 },
+    /// a classified access. `hit` is the runtime heap object's (ticket, site id),
+    /// `predicted` the uncapped predicted set size.
+    Access { access_id: u64, class: AccessClass, hit: Option<(u64, u64)>, predicted: usize },
+    /// `site_id` was predicted for an access that hit none of its predicted
+    /// sites; `fp_access` marks an FP (not heap) access.
+    FalsePrediction { site_id: u64, fp_access: bool },
+    /// an access into a freed object of `site_id`.
+    UseAfterFree { site_id: u64 },
+    /// an out-of-bounds access by `access_id`.
+    OutOfBounds { access_id: u64 },
+}
+
+/// per-site counters; see "per-site confusion" in `unsafe_heap_access`.
+#[derive(Clone, Default)]
+pub(crate) struct SiteAccumulator {
+    pub tp: u64,
+    pub fn_: u64,
+    pub fp_predictions: u64,
+    pub access_ids: BTreeSet<u64>,
+    pub tickets: BTreeSet<u64>,
+}
+
+/// per-access row; see "per-access table" in `unsafe_heap_access`.
+#[derive(Clone, Default)]
+pub(crate) struct AccessAccumulator {
+    pub classes: [u64; 4],
+    pub site_ids: BTreeSet<u64>,
+    pub max_predicted: usize,
+}
+
+/// the folded sets and tables.
+#[derive(Default)]
+pub(crate) struct AccessSets {
+    /// unique allocation site ids svf analyzed.
+    pub analyzed_site_ids: BTreeSet<u64>,
+    /// tickets touched by unsafe pointers. tickets (not addresses) avoid
+    /// double-counting after free/reuse.
+    pub touched_tickets: BTreeSet<u64>,
+    /// tickets where svf identified the allocation site (true positives).
+    pub matched_tickets: BTreeSet<u64>,
+    pub matched_site_ids: BTreeSet<u64>,
+    pub missed_site_ids: BTreeSet<u64>,
+    pub fp_site_ids: BTreeSet<u64>,
+    pub uaf_site_ids: BTreeSet<u64>,
+    pub oob_access_ids: BTreeSet<u64>,
+    pub sites: HashMap<u64, SiteAccumulator>,
+    pub accesses: HashMap<u64, AccessAccumulator>,
+}
+
+impl AccessSets {
+    fn apply(&mut self, event: &Event) {
+        match *event {
+            Event::Analyzed { site_id } => {
+                self.analyzed_site_ids.insert(site_id);
+            }
+            Event::Access { access_id, class, hit, predicted } => {
+                let row = self.accesses.entry(access_id).or_default();
+                row.classes[class as usize] += 1;
+                row.max_predicted = row.max_predicted.max(predicted);
+                let Some((ticket, site_id)) = hit else { return };
+                row.site_ids.insert(site_id);
+                self.touched_tickets.insert(ticket);
+
+                let site = self.sites.entry(site_id).or_default();
+                site.access_ids.insert(access_id);
+                site.tickets.insert(ticket);
+                if class == AccessClass::Tp {
+                    site.tp += 1;
+                    self.matched_tickets.insert(ticket);
+                    self.matched_site_ids.insert(site_id);
+                } else {
+                    site.fn_ += 1;
+                    self.missed_site_ids.insert(site_id);
+                }
+            }
+            Event::FalsePrediction { site_id, fp_access } => {
+                self.sites.entry(site_id).or_default().fp_predictions += 1;
+                if fp_access {
+                    self.fp_site_ids.insert(site_id);
+                }
+            }
+            Event::UseAfterFree { site_id } => {
+                self.uaf_site_ids.insert(site_id);
+            }
+            Event::OutOfBounds { access_id } => {
+                self.oob_access_ids.insert(access_id);
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &AccessSets) {
+        for (mine, theirs) in [
+            (&mut self.analyzed_site_ids, &other.analyzed_site_ids),
+            (&mut self.touched_tickets, &other.touched_tickets),
+            (&mut self.matched_tickets, &other.matched_tickets),
+            (&mut self.matched_site_ids, &other.matched_site_ids),
+            (&mut self.missed_site_ids, &other.missed_site_ids),
+            (&mut self.fp_site_ids, &other.fp_site_ids),
+            (&mut self.uaf_site_ids, &other.uaf_site_ids),
+            (&mut self.oob_access_ids, &other.oob_access_ids),
+        ] {
+            mine.extend(theirs.iter().copied());
+        }
+        for (&site_id, theirs) in other.sites.iter() {
+            let mine = self.sites.entry(site_id).or_default();
+            mine.tp += theirs.tp;
+            mine.fn_ += theirs.fn_;
+            mine.fp_predictions += theirs.fp_predictions;
+            mine.access_ids.extend(theirs.access_ids.iter().copied());
+            mine.tickets.extend(theirs.tickets.iter().copied());
+        }
+        for (&access_id, theirs) in other.accesses.iter() {
+            let mine = self.accesses.entry(access_id).or_default();
+            for (m, t) in mine.classes.iter_mut().zip(theirs.classes) {
+                *m += t;
+            }
+            mine.site_ids.extend(theirs.site_ids.iter().copied());
+            mine.max_predicted = mine.max_predicted.max(theirs.max_predicted);
+        }
+    }
+}
+
+/// a thread's event buffer and the sets its earlier events were folded into.
+pub(crate) struct ThreadLog {
+    pending: [Event; EVENT_BUFFER_CAP],
+    len: usize,
+    sets: AccessSets,
+}
+
+impl Default for ThreadLog {
+    fn default() -> Self {
+        Self { pending: [Event::Analyzed { site_id: 0 }; EVENT_BUFFER_CAP], len: 0, sets: AccessSets::default() }
+    }
+}
+
+impl ThreadLog {
+    fn push(&mut self, event: Event) {
+        if self.len == EVENT_BUFFER_CAP {
+            crate::without_hooks(|| {
+                for e in &self.pending {
+                    self.sets.apply(e);
+                }
+            });
+            self.len = 0;
+        }
+        self.pending[self.len] = event;
+        self.len += 1;
+    }
+}
+
+impl Mergeable for ThreadLog {
+    fn merge_from(&mut self, other: &Self) {
+        self.sets.merge(&other.sets);
+        for e in &other.pending[..other.len] {
+            self.sets.apply(e);
+        }
+    }
+}
+
+/// events that could not be recorded, see the module docs.
+static DROPPED_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    static ref LOG_REGISTRY: ThreadRegistry<ThreadLog> = ThreadRegistry::new();
+}
+
+thread_local! {
+    static LOCAL_LOG: ThreadSlot<ThreadLog> = ThreadSlot::new(&LOG_REGISTRY);
+}
+
+/// record `event` in this thread's buffer.
+pub(crate) fn record(event: Event) {
+    let recorded = LOCAL_LOG.try_with(|slot| slot.with(|log| log.push(event)));
+    if !matches!(recorded, Ok(Some(()))) {
+        DROPPED_EVENTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// the sets over exited threads and the current state of all live threads.
+pub(crate) fn aggregate() -> AccessSets {
+    LOG_REGISTRY.aggregate().sets
+}
+
+/// number of events that could not be recorded.
+pub(crate) fn dropped_events() -> u64 {
+    DROPPED_EVENTS.load(Ordering::Relaxed)
+}
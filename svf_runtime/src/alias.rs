@@ -1,119 +1,511 @@
 //! alias checking module for svf runtime.
 //! contains __svf_check_alias and related statistics.
+//!
+//! besides the global confusion counters, every instrumented check is tracked
+//! individually by its check id (the low 31 bits of `id`), so each svf-lto check
+//! can be judged on its own. a check's runtime ground truth is "alias" if *any*
+//! of its executions saw `p == q`.
+//!
+//! counters are kept per thread and registered with a `ThreadRegistry`, so a
+//! report sees the checks of threads that are still running (including main).
+//!
+//! ## ground truth notions
+//! address equality alone calls two pointers into the same struct or buffer
+//! "disjoint", while svf reasons about objects. every check is therefore judged
+//! against several notions of runtime aliasing, each with its own confusion matrix:
+//! - `exact`: `p == q`. this is the legacy notion behind the top-level counters.
+//! - `same_object`: `p` and `q` point into the same live heap object (same
+//!   `LIVE_HEAP` ticket). undecided when neither pointer is on the heap.
+//! - `overlap`: the accessed ranges `[p, p + p_size)` and `[q, q + q_size)`
+//!   intersect. only decidable through `__svf_check_alias_sized`.
+//!
+//! equal addresses alias under every notion.
+//!
+//! ## predictions
+//! the legacy `__svf_check_alias` hook carries a single bit (bit 31 of `id`),
+//! decoded as `MayAlias` / `NoAlias`. `__svf_check_alias_v2` carries svf's full
+//! `AliasResult` in a separate byte. for the confusion matrices any prediction
+//! other than `NoAlias` counts as "predicted alias"; a `MustAlias` prediction whose
+//! addresses differ at runtime is additionally counted as a must-alias violation,
+//! a soundness failure of its own. unknown prediction bytes are counted as no info.
 
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
 
-use crate::IN_CHECKER;
+use crate::{IN_CHECKER, ReentrancyGuard};
+use crate::heap::get_live_heap_ticket;
+use crate::json::{JsonArray, JsonObject};
+use crate::registry::{Mergeable, ThreadRegistry, ThreadSlot};
 
-// global alias statistics
-static CNT_TRUE_ALIAS: AtomicUsize = AtomicUsize::new(0);
-static CNT_TRUE_DISJOINT: AtomicUsize = AtomicUsize::new(0);
-static CNT_FALSE_ALIAS: AtomicUsize = AtomicUsize::new(0);
-static CNT_FALSE_DISJOINT: AtomicUsize = AtomicUsize::new(0);
-static CNT_TOTAL: AtomicUsize = AtomicUsize::new(0);
-static CNT_NO_INFO: AtomicUsize = AtomicUsize::new(0);
+/// bit 31 of the legacy hook's `id` carries the svf prediction, the rest is the check id.
+const PREDICTION_BIT: u32 = 1 << 31;
+
+/// svf `AliasResult`, as passed to `__svf_check_alias_v2`. the discriminants are
+/// the wire encoding, see `predictionByte` in the lto plugin.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Prediction {
+    #[default]
+    NoAlias = 0,
+    MayAlias = 1,
+    MustAlias = 2,
+    PartialAlias = 3,
+}
+
+impl Prediction {
+    pub const ALL: [Prediction; 4] =
+        [Prediction::NoAlias, Prediction::MayAlias, Prediction::MustAlias, Prediction::PartialAlias];
+
+    pub fn from_u8(v: u8) -> Option<Prediction> {
+        Self::ALL.get(v as usize).copied()
+    }
+
+    /// whether the prediction counts as "alias" in the confusion matrices.
+    pub fn is_alias(&self) -> bool {
+        *self != Prediction::NoAlias
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Prediction::NoAlias => "no_alias",
+            Prediction::MayAlias => "may_alias",
+            Prediction::MustAlias => "must_alias",
+            Prediction::PartialAlias => "partial_alias",
+        }
+    }
+}
+
+/// executions per prediction kind.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PredictionCounts {
+    pub executions: usize,
+    /// executions where `p == q` at runtime.
+    pub aliased: usize,
+}
+
+/// runtime notion of "the two pointers alias", see the module docs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GroundTruth {
+    Exact,
+    SameObject,
+    Overlap,
+}
+
+impl GroundTruth {
+    pub const ALL: [GroundTruth; 3] = [GroundTruth::Exact, GroundTruth::SameObject, GroundTruth::Overlap];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroundTruth::Exact => "exact",
+            GroundTruth::SameObject => "same_object",
+            GroundTruth::Overlap => "overlap",
+        }
+    }
+}
+
+/// runtime aliasing of one execution under each `GroundTruth` notion,
+/// `None` where the notion cannot be decided.
+type Observation = [Option<bool>; 3];
+
+fn observe(p: usize, p_size: usize, q: usize, q_size: usize) -> Observation {
+    if p == q {
+        return [Some(true); 3];
+    }
+    let same_object = match (get_live_heap_ticket(p as *const u8), get_live_heap_ticket(q as *const u8)) {
+        (Some((p_ticket, _)), Some((q_ticket, _))) => Some(p_ticket == q_ticket),
+        (None, None) => None,
+        // a heap object and a non-heap object are never the same object.
+        _ => Some(false),
+    };
+    let overlap = (p_size > 0 && q_size > 0)
+        .then(|| p < q.saturating_add(q_size) && q < p.saturating_add(p_size));
+    [Some(false), same_object, overlap]
+}
+
+/// confusion matrix of svf predictions against one ground truth notion.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Confusion {
+    pub true_alias: usize,
+    pub true_disjoint: usize,
+    pub false_alias: usize,
+    pub false_disjoint: usize,
+    /// executions where the notion could not be decided.
+    pub undecided: usize,
+}
+
+impl Confusion {
+    fn record(&mut self, predicted_alias: bool, actual: Option<bool>) {
+        match (predicted_alias, actual) {
+            (_, None) => self.undecided += 1,
+            (true, Some(true)) => self.true_alias += 1,
+            (true, Some(false)) => self.false_alias += 1,
+            (false, Some(true)) => self.false_disjoint += 1,
+            (false, Some(false)) => self.true_disjoint += 1,
+        }
+    }
+
+    fn merge(&mut self, other: &Confusion) {
+        self.true_alias += other.true_alias;
+        self.true_disjoint += other.true_disjoint;
+        self.false_alias += other.false_alias;
+        self.false_disjoint += other.false_disjoint;
+        self.undecided += other.undecided;
+    }
+
+    pub fn decided(&self) -> usize {
+        self.true_alias + self.true_disjoint + self.false_alias + self.false_disjoint
+    }
+
+    /// correct predictions over decided executions, in percent.
+    pub fn accuracy(&self) -> Option<f64> {
+        let decided = self.decided();
+        (decided > 0).then(|| (self.true_alias + self.true_disjoint) as f64 / decided as f64 * 100.0)
+    }
+
+    fn write_json(&self, o: &mut JsonObject) {
+        o.u64("true_alias", self.true_alias as u64)
+            .u64("true_disjoint", self.true_disjoint as u64)
+            .u64("false_alias", self.false_alias as u64)
+            .u64("false_disjoint", self.false_disjoint as u64)
+            .u64("undecided", self.undecided as u64)
+            .opt_f64("accuracy", self.accuracy());
+    }
+}
+
+/// per-check-id statistics.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CheckStats {
+    /// number of times the check executed.
+    pub hits: u64,
+    /// number of executions where the two pointers were equal at runtime.
+    pub aliased: u64,
+    /// number of executions where the pointers were in the same heap object.
+    pub aliased_same_object: u64,
+    /// number of executions where the accessed ranges overlapped.
+    pub aliased_overlap: u64,
+    /// `MustAlias` executions whose addresses differed.
+    pub must_alias_violations: u64,
+    /// the static svf prediction.
+    pub prediction: Prediction,
+}
+
+impl CheckStats {
+    fn merge(&mut self, other: &CheckStats) {
+        self.hits += other.hits;
+        self.aliased += other.aliased;
+        self.aliased_same_object += other.aliased_same_object;
+        self.aliased_overlap += other.aliased_overlap;
+        self.must_alias_violations += other.must_alias_violations;
+        if other.hits > 0 { self.prediction = other.prediction; }
+    }
+
+    pub fn predicted_alias(&self) -> bool {
+        self.prediction.is_alias()
+    }
+
+    /// final judgement of the check across all of its executions.
+    pub fn verdict(&self) -> CheckVerdict {
+        match (self.predicted_alias(), self.aliased > 0) {
+            (true, true) => CheckVerdict::TrueAlias,
+            (true, false) => CheckVerdict::FalseAlias,
+            (false, true) => CheckVerdict::FalseDisjoint,
+            (false, false) => CheckVerdict::TrueDisjoint,
+        }
+    }
+}
+
+/// per-check verdict, mirroring the per-execution confusion matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CheckVerdict {
+    TrueAlias,
+    TrueDisjoint,
+    FalseAlias,
+    /// svf predicted no alias but the pointers aliased at least once (unsound).
+    FalseDisjoint,
+}
+
+impl CheckVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CheckVerdict::TrueAlias => "true_alias",
+            CheckVerdict::TrueDisjoint => "true_disjoint",
+            CheckVerdict::FalseAlias => "false_alias",
+            CheckVerdict::FalseDisjoint => "false_disjoint",
+        }
+    }
+}
+
+lazy_static! {
+    /// per-thread alias statistics, see `registry` for how they are aggregated.
+    static ref ALIAS_REGISTRY: ThreadRegistry<ThreadStats> = ThreadRegistry::new();
+}
 
 #[repr(C)]
+#[derive(Default)]
 struct ThreadStats {
-    true_alias: usize,
-    true_disjoint: usize,
-    false_alias: usize,
-    false_disjoint: usize,
+    /// one matrix per `GroundTruth` notion, in `GroundTruth::ALL` order.
+    ground_truth: [Confusion; 3],
+    /// one entry per `Prediction`, in `Prediction::ALL` order.
+    by_prediction: [PredictionCounts; 4],
+    must_alias_violations: usize,
     total: usize,
     no_info: usize,
+    checks: HashMap<u64, CheckStats>,
 }
 
-impl ThreadStats {
-    fn new() -> Self {
-        Self {
-            true_alias: 0,
-            true_disjoint: 0,
-            false_alias: 0,
-            false_disjoint: 0,
-            total: 0,
-            no_info: 0,
+impl Mergeable for ThreadStats {
+    fn merge_from(&mut self, other: &Self) {
+        for (mine, theirs) in self.ground_truth.iter_mut().zip(other.ground_truth.iter()) {
+            mine.merge(theirs);
+        }
+        for (mine, theirs) in self.by_prediction.iter_mut().zip(other.by_prediction.iter()) {
+            mine.executions += theirs.executions;
+            mine.aliased += theirs.aliased;
+        }
+        self.must_alias_violations += other.must_alias_violations;
+        self.total += other.total;
+        self.no_info += other.no_info;
+        for (id, check) in other.checks.iter() {
+            self.checks.entry(*id).or_default().merge(check);
         }
     }
 }
 
-impl Drop for ThreadStats {
-    fn drop(&mut self) {
-        CNT_TRUE_ALIAS.fetch_add(self.true_alias, Ordering::Relaxed);
-        CNT_TRUE_DISJOINT.fetch_add(self.true_disjoint, Ordering::Relaxed);
-        CNT_FALSE_ALIAS.fetch_add(self.false_alias, Ordering::Relaxed);
-        CNT_FALSE_DISJOINT.fetch_add(self.false_disjoint, Ordering::Relaxed);
-        CNT_TOTAL.fetch_add(self.total, Ordering::Relaxed);
-        CNT_NO_INFO.fetch_add(self.no_info, Ordering::Relaxed);
+thread_local! {
+    static LOCAL_STATS: ThreadSlot<ThreadStats> = ThreadSlot::new(&ALIAS_REGISTRY);
+}
+
+/// run `f` on this thread's stats. a check made during thread-local teardown,
+/// after the slot was retired, goes straight into the retired totals, so the
+/// totals still add up.
+fn with_stats(f: impl FnOnce(&mut ThreadStats)) {
+    let mut f = Some(f);
+    let _ = LOCAL_STATS.try_with(|slot| slot.with(|s| f.take().map(|f| f(s))));
+    if let Some(f) = f {
+        ALIAS_REGISTRY.with_retired(f);
     }
 }
 
-thread_local! {
-    static LOCAL_STATS: RefCell<ThreadStats> = RefCell::new(ThreadStats::new());
+/// snapshot of the alias check counters, shared by the text and json renderers.
+#[derive(Clone, Debug, Default)]
+pub struct AliasStats {
+    pub total: usize,
+    pub true_alias: usize,
+    pub true_disjoint: usize,
+    pub false_alias: usize,
+    pub false_disjoint: usize,
+    pub no_info: usize,
+    /// one matrix per `GroundTruth` notion, in `GroundTruth::ALL` order.
+    /// the top-level counters above are the `Exact` matrix.
+    pub ground_truth: [Confusion; 3],
+    /// one entry per `Prediction`, in `Prediction::ALL` order.
+    pub by_prediction: [PredictionCounts; 4],
+    /// `MustAlias` predictions whose addresses differed at runtime.
+    pub must_alias_violations: usize,
+    /// per-check-id table, sorted by check id.
+    pub checks: Vec<(u64, CheckStats)>,
+}
+
+impl AliasStats {
+    /// fraction of checks whose prediction matched the runtime result, in percent.
+    pub fn accuracy(&self) -> Option<f64> {
+        if self.total == 0 { return None; }
+        let correct = self.true_alias + self.true_disjoint;
+        Some(correct as f64 / self.total as f64 * 100.0)
+    }
+
+    pub(crate) fn write_json(&self, o: &mut JsonObject) {
+        o.u64("total", self.total as u64)
+            .u64("true_alias", self.true_alias as u64)
+            .u64("true_disjoint", self.true_disjoint as u64)
+            .u64("false_alias", self.false_alias as u64)
+            .u64("false_disjoint", self.false_disjoint as u64)
+            .u64("no_info", self.no_info as u64)
+            .opt_f64("accuracy", self.accuracy())
+            .u64("must_alias_violations", self.must_alias_violations as u64)
+            .object("by_prediction", |b| {
+                for (prediction, counts) in Prediction::ALL.iter().zip(self.by_prediction.iter()) {
+                    b.object(prediction.as_str(), |c| {
+                        c.u64("executions", counts.executions as u64)
+                            .u64("aliased", counts.aliased as u64);
+                    });
+                }
+            })
+            .object("ground_truth", |g| {
+                for (truth, matrix) in GroundTruth::ALL.iter().zip(self.ground_truth.iter()) {
+                    g.object(truth.as_str(), |m| matrix.write_json(m));
+                }
+            })
+            .array("checks", |a| self.write_checks_json(a));
+    }
+
+    fn write_checks_json(&self, a: &mut JsonArray) {
+        for (id, check) in self.checks.iter() {
+            a.object(|o| {
+                o.u64("id", *id)
+                    .u64("hits", check.hits)
+                    .u64("aliased", check.aliased)
+                    .u64("aliased_same_object", check.aliased_same_object)
+                    .u64("aliased_overlap", check.aliased_overlap)
+                    .u64("must_alias_violations", check.must_alias_violations)
+                    .str("prediction", check.prediction.as_str())
+                    .bool("predicted_alias", check.predicted_alias())
+                    .str("verdict", check.verdict().as_str());
+            });
+        }
+    }
+
+    /// number of checks per verdict, in `CheckVerdict` declaration order.
+    pub fn verdict_counts(&self) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for (_, check) in self.checks.iter() {
+            counts[check.verdict() as usize] += 1;
+        }
+        counts
+    }
+
+    /// human-readable rendering, the format previously printed by `print_alias_stats`.
+    pub fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "\n=== SVF Alias Analysis Statistics ===")?;
+        writeln!(out, "Total Checks: {}", self.total)?;
+        writeln!(out, "Correct Predictions:")?;
+        writeln!(out, "  True Alias (Predicted Alias & is Alias): {}", self.true_alias)?;
+        writeln!(out, "  True Disjoint (Predicted NoAlias & No Alias): {}", self.true_disjoint)?;
+        writeln!(out, "Incorrect Predictions:")?;
+        writeln!(out, "  False Alias (False Positive): {}", self.false_alias)?;
+        writeln!(out, "  False Disjoint (False Negative): {}", self.false_disjoint)?;
+        writeln!(out, "No Info / Low Confidence: {}", self.no_info)?;
+        writeln!(out, "MustAlias Violations (Predicted MustAlias & Addresses Differ): {}", self.must_alias_violations)?;
+        if let Some(accuracy) = self.accuracy() {
+            writeln!(out, "Accuracy: {:.2}%", accuracy)?;
+        }
+        writeln!(out, "Ground Truth Notions (TA / TD / FA / FD / undecided):")?;
+        for (truth, m) in GroundTruth::ALL.iter().zip(self.ground_truth.iter()) {
+            write!(out, "  {:<12} {} / {} / {} / {} / {}", truth.as_str(), m.true_alias, m.true_disjoint, m.false_alias, m.false_disjoint, m.undecided)?;
+            match m.accuracy() {
+                Some(accuracy) => writeln!(out, "  accuracy {:.2}%", accuracy)?,
+                None => writeln!(out)?,
+            }
+        }
+        if !self.checks.is_empty() {
+            let [ta, td, fa, fd] = self.verdict_counts();
+            writeln!(out, "Per-Check Verdicts ({} unique check ids):", self.checks.len())?;
+            writeln!(out, "  True Alias: {}  True Disjoint: {}  False Alias: {}  False Disjoint: {}", ta, td, fa, fd)?;
+            if fd > 0 {
+                write!(out, "  False Disjoint check IDs: ")?;
+                for (id, check) in self.checks.iter() {
+                    if check.verdict() == CheckVerdict::FalseDisjoint { write!(out, "{} ", id)?; }
+                }
+                writeln!(out)?;
+            }
+        }
+        writeln!(out, "==============================\n")
+    }
+}
+
+/// collect the alias statistics of all threads, including ones still running.
+pub fn alias_stats() -> AliasStats {
+    let merged = ALIAS_REGISTRY.aggregate();
+    let mut checks: Vec<(u64, CheckStats)> = merged.checks.iter().map(|(&id, &c)| (id, c)).collect();
+    checks.sort_unstable_by_key(|&(id, _)| id);
+    let exact = merged.ground_truth[GroundTruth::Exact as usize];
+    AliasStats {
+        total: merged.total,
+        true_alias: exact.true_alias,
+        true_disjoint: exact.true_disjoint,
+        false_alias: exact.false_alias,
+        false_disjoint: exact.false_disjoint,
+        no_info: merged.no_info,
+        ground_truth: merged.ground_truth,
+        by_prediction: merged.by_prediction,
+        must_alias_violations: merged.must_alias_violations,
+        checks,
+    }
 }
 
 /// print alias analysis statistics.
 pub fn print_alias_stats() {
-    let t_alias = CNT_TRUE_ALIAS.load(Ordering::Relaxed);
-    let t_disjoint = CNT_TRUE_DISJOINT.load(Ordering::Relaxed);
-    let f_alias = CNT_FALSE_ALIAS.load(Ordering::Relaxed);
-    let f_disjoint = CNT_FALSE_DISJOINT.load(Ordering::Relaxed);
-    let no_info = CNT_NO_INFO.load(Ordering::Relaxed);
-    let total = CNT_TOTAL.load(Ordering::Relaxed);
-
-    println!("\n=== SVF Alias Analysis Statistics ===");
-    println!("Total Checks: {}", total);
-    println!("Correct Predictions:");
-    println!("  True Alias (Predicted Alias & is Alias): {}", t_alias);
-    println!("  True Disjoint (Predicted NoAlias & No Alias): {}", t_disjoint);
-    println!("Incorrect Predictions:");
-    println!("  False Alias (False Positive): {}", f_alias);
-    println!("  False Disjoint (False Negative): {}", f_disjoint);
-    println!("No Info / Low Confidence: {}", no_info);
-
-    if total > 0 {
-        let correct = t_alias + t_disjoint;
-        let accuracy = (correct as f64 / total as f64) * 100.0;
-        println!("Accuracy: {:.2}%", accuracy);
-    }
-    println!("==============================\n");
+    let _ = alias_stats().write_text(&mut io::stdout().lock());
 }
 
+/// runtime hook: compares the svf prediction encoded in bit 31 of `id`
+/// against the runtime aliasing of `p` and `q`. access sizes are unknown, so the
+/// `overlap` notion stays undecided unless the pointers are equal.
+///
+/// # Safety
+/// only reads the pointer values; it is `unsafe` solely because it is an
+/// instrumentation entry point called from foreign code.
 #[no_mangle]
 #[inline(never)]
 pub unsafe extern "C" fn __svf_check_alias(p: usize, q: usize, id: u32) {
-    if IN_CHECKER.with(|c| c.get()) { return; }
-    IN_CHECKER.with(|c| c.set(true));
+    let (check_id, prediction) = decode_legacy_id(id);
+    check_alias(p, 0, q, 0, check_id, Some(prediction));
+}
 
-    struct GuardReset;
-    impl Drop for GuardReset {
-        fn drop(&mut self) {
-            IN_CHECKER.with(|c| c.set(false));
-        }
-    }
-    let _guard_reset = GuardReset;
+/// runtime hook: like `__svf_check_alias`, but the instrumentation also passes
+/// the number of bytes accessed through each pointer (0 = unknown), which
+/// decides the `overlap` notion.
+///
+/// # Safety
+/// only reads the pointer values; the pointers are never dereferenced.
+#[no_mangle]
+#[inline(never)]
+pub unsafe extern "C" fn __svf_check_alias_sized(p: usize, p_size: usize, q: usize, q_size: usize, id: u32) {
+    let (check_id, prediction) = decode_legacy_id(id);
+    check_alias(p, p_size, q, q_size, check_id, Some(prediction));
+}
 
-    let is_actual_alias = p == q;
+/// runtime hook: alias check carrying svf's full `AliasResult` (see `Prediction`)
+/// and a 64-bit check id. unknown `prediction` values are counted as no info.
+/// check ids share one table with the legacy hooks.
+///
+/// # Safety
+/// only reads the pointer values; the pointers are never dereferenced.
+#[no_mangle]
+#[inline(never)]
+pub unsafe extern "C" fn __svf_check_alias_v2(p: usize, q: usize, id: u64, prediction: u8) {
+    check_alias(p, 0, q, 0, id, Prediction::from_u8(prediction));
+}
 
-    let prediction_bit = (id >> 31) & 1;
-    let predicted_alias = prediction_bit == 1;
+/// split a legacy hook id into the check id and the one-bit prediction.
+fn decode_legacy_id(id: u32) -> (u64, Prediction) {
+    let prediction = if id & PREDICTION_BIT != 0 { Prediction::MayAlias } else { Prediction::NoAlias };
+    ((id & !PREDICTION_BIT) as u64, prediction)
+}
 
-    LOCAL_STATS.with(|stats| {
-        let mut s = stats.borrow_mut();
-        s.total += 1;
+fn check_alias(p: usize, p_size: usize, q: usize, q_size: usize, check_id: u64, prediction: Option<Prediction>) {
+    if IN_CHECKER.with(|c| c.get()) { return; }
+    IN_CHECKER.with(|c| c.set(true));
+    let _guard = ReentrancyGuard;
 
-        if predicted_alias {
-            if is_actual_alias {
-                s.true_alias += 1;
-            } else {
-                s.false_alias += 1;
-            }
-        } else {
-            if is_actual_alias {
-                s.false_disjoint += 1;
-            } else {
-                s.true_disjoint += 1;
-            }
+    let Some(prediction) = prediction else {
+        with_stats(|s| {
+            s.total += 1;
+            s.no_info += 1;
+        });
+        return;
+    };
+    let predicted_alias = prediction.is_alias();
+    let observed = observe(p, p_size, q, q_size);
+    let must_alias_violation = prediction == Prediction::MustAlias && p != q;
+
+    with_stats(|s| {
+        s.total += 1;
+        for (matrix, actual) in s.ground_truth.iter_mut().zip(observed) {
+            matrix.record(predicted_alias, actual);
         }
+        let counts = &mut s.by_prediction[prediction as usize];
+        counts.executions += 1;
+        counts.aliased += (p == q) as usize;
+        s.must_alias_violations += must_alias_violation as usize;
+
+        let check = s.checks.entry(check_id).or_default();
+        check.hits += 1;
+        let [exact, same_object, overlap] = observed.map(|a| a == Some(true));
+        check.aliased += exact as u64;
+        check.aliased_same_object += same_object as u64;
+        check.aliased_overlap += overlap as u64;
+        check.must_alias_violations += must_alias_violation as u64;
+        check.prediction = prediction;
     });
 }
@@ -1,65 +1,367 @@
 //! heap checking module for svf runtime.
-//! contains __svf_report_alloc, __svf_report_dealloc
+//! contains __svf_report_alloc, __svf_report_dealloc, __svf_report_realloc
 //! and the LIVE_HEAP map shared with unsafe_heap_access module.
+//!
+//! ## realloc policy
+//! a realloc resizes the *same* logical object, so it keeps its allocation
+//! ticket whether it was resized in place or moved. the site id passed to
+//! `__svf_report_realloc` updates the object's site, with 0 meaning "keep the
+//! current site". when the site changes, the old site records a free of the
+//! old size and the new site an alloc of the new size, so per-site live bytes
+//! stay consistent. a realloc of an untracked (or null) pointer is recorded as
+//! a fresh allocation with a new ticket.
+//!
+//! ## re-reported objects
+//! an alloc reported at the base of a live object replaces it, and the old object
+//! counts as freed on its site. this happens when the preload interposers (see
+//! `preload`) recorded a malloc before its instrumented call site reported it.
+//!
+//! ## invalid frees
+//! a dealloc of an address that is not the base of a live object is classified as
+//! - `interior_pointer`: the address points inside a live object,
+//! - `double_free`: the address is the base of a recently freed object, found in
+//!   the bounded `QUARANTINE` (older frees are forgotten),
+//! - `never_allocated`: neither of the above.
+//!
+//! a realloc of a freed or interior pointer is classified the same way; a realloc
+//! of any other untracked pointer is not, since it may come from an uninstrumented
+//! allocation. each bad free is counted on the site of the object it refers to,
+//! and emitted as a single-line `{"event":"svf_bad_free","op":"free"|"realloc",...}`
+//! record. with
+//! `SVF_ABORT_ON_BAD_FREE=1` the process aborts right after the record, for ci.
+//!
+//! ## quarantine
+//! the last `QUARANTINE_CAP` freed objects keep their range, ticket and site id
+//! in `QUARANTINE`, indexed by base address. besides double frees, it lets the
+//! unsafe access checker recognise accesses into freed memory (use-after-free).
+//! `LIVE_HEAP` always takes precedence: once an address is reused by a new
+//! allocation, stale quarantine entries covering it are never consulted.
+//! the access hooks never wait for the quarantine lock; lookups that find it
+//! held are skipped and counted.
 
-use std::sync::Mutex;
+use std::sync::{Mutex, OnceLock, TryLockError};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
 
 use crate::{IN_CHECKER, ReentrancyGuard};
+use crate::heap_index::{Entry, HeapIndex, Object};
+use crate::json::{JsonArray, JsonObject};
+use crate::pts_dump;
+use crate::shadow::{self, Lookup, SHADOW};
+use crate::stacks;
 
 /// global monotonic ticket counter for unique allocation id tracking
 static ALLOCATION_TICKET_COUNTER: AtomicU64 = AtomicU64::new(1);
 
 /// per-site statistics for heap verification.
-struct SiteStats {
-    alloc_count: u64,
-    alloc_bytes: u64,
-    free_count: u64,
-    free_bytes: u64,
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SiteStats {
+    pub alloc_count: u64,
+    pub alloc_bytes: u64,
+    pub free_count: u64,
+    pub free_bytes: u64,
+    /// reallocs of tracked objects belonging to this site.
+    pub realloc_count: u64,
+    /// bytes gained by growing reallocs.
+    pub realloc_grow_bytes: u64,
+    /// bytes released by shrinking reallocs.
+    pub realloc_shrink_bytes: u64,
+    /// deallocs of an already freed object of this site.
+    pub double_frees: u64,
+    /// deallocs of a pointer into the middle of a live object of this site.
+    pub interior_frees: u64,
 }
 
-impl SiteStats {
+/// a live heap object as recorded in `LIVE_HEAP`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct LiveObject {
+    pub base: usize,
+    pub size: usize,
+    pub site_id: u64,
+    pub ticket: u64,
+}
+
+impl LiveObject {
+    fn from_entry((base, (size, site_id, ticket)): Object) -> Self {
+        Self { base, size, site_id, ticket }
+    }
+}
+
+/// site id of the objects recorded by the preload interposers rather than an
+/// instrumented call site.
+pub const UNINSTRUMENTED_SITE_ID: u64 = u64::MAX;
+
+/// env var enabling abort-on-bad-free.
+pub const ABORT_ON_BAD_FREE_ENV: &str = "SVF_ABORT_ON_BAD_FREE";
+
+/// classification of a dealloc that does not match a live object base.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BadFree {
+    NeverAllocated,
+    DoubleFree,
+    InteriorPointer,
+}
+
+impl BadFree {
+    pub const ALL: [BadFree; 3] = [BadFree::NeverAllocated, BadFree::DoubleFree, BadFree::InteriorPointer];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BadFree::NeverAllocated => "never_allocated",
+            BadFree::DoubleFree => "double_free",
+            BadFree::InteriorPointer => "interior_pointer",
+        }
+    }
+}
+
+/// bad free counts, in `BadFree::ALL` order.
+static BAD_FREES: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+/// number of freed objects kept in the quarantine.
+const QUARANTINE_CAP: usize = 4096;
+/// bases below an address that a containing lookup inspects.
+const QUARANTINE_SCAN: usize = 64;
+
+/// use-after-free lookups skipped because the quarantine was locked.
+static QUARANTINE_SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+/// fixed-capacity fifo of the most recently freed objects, with a base address index.
+struct Quarantine {
+    entries: Vec<LiveObject>,
+    /// slot the next push writes to; the oldest entry once the ring is full.
+    next: usize,
+    /// base address -> slot in `entries`.
+    by_base: BTreeMap<usize, usize>,
+    /// largest size ever pushed, bounding how far below an address a
+    /// containing object can start.
+    max_size: usize,
+}
+
+impl Quarantine {
     fn new() -> Self {
-        Self {
-            alloc_count: 0,
-            alloc_bytes: 0,
-            free_count: 0,
-            free_bytes: 0,
+        Self { entries: Vec::with_capacity(QUARANTINE_CAP), next: 0, by_base: BTreeMap::new(), max_size: 0 }
+    }
+
+    fn push(&mut self, obj: LiveObject) {
+        let slot = self.next;
+        if self.entries.len() < QUARANTINE_CAP {
+            self.entries.push(obj);
+        } else {
+            let evicted = std::mem::replace(&mut self.entries[slot], obj);
+            // only drop the index entry if a newer free did not take over its base.
+            if self.by_base.get(&evicted.base) == Some(&slot) {
+                self.by_base.remove(&evicted.base);
+            }
         }
+        self.by_base.insert(obj.base, slot);
+        self.next = (slot + 1) % QUARANTINE_CAP;
+        self.max_size = self.max_size.max(obj.size);
+    }
+
+    /// the most recently freed object starting at `addr`.
+    fn find_base(&self, addr: usize) -> Option<LiveObject> {
+        self.by_base.get(&addr).map(|&slot| self.entries[slot])
+    }
+
+    /// the freed object containing `addr` with the closest base. best effort: an
+    /// object is missed when more than `QUARANTINE_SCAN` freed bases lie between
+    /// its base and `addr`.
+    fn find_containing(&self, addr: usize) -> Option<LiveObject> {
+        self.by_base
+            .range(addr.saturating_sub(self.max_size)..=addr)
+            .rev()
+            .take(QUARANTINE_SCAN)
+            .map(|(_, &slot)| self.entries[slot])
+            .find(|obj| addr < obj.base + obj.size)
     }
 }
 
 lazy_static! {
-    /// map address -> (size, site_id, ticket). uses BTreeMap for range queries.
+    /// map address -> (size, site_id, ticket), sharded for concurrent use (see `heap_index`).
     /// shared with unsafe_heap_access module for heap lookups.
-    pub(crate) static ref LIVE_HEAP: std::sync::RwLock<BTreeMap<usize, (usize, u64, u64)>> =
-        std::sync::RwLock::new(BTreeMap::new());
+    pub(crate) static ref LIVE_HEAP: HeapIndex = HeapIndex::new();
 
     static ref SITE_STATS: Mutex<HashMap<u64, SiteStats>> = Mutex::new(HashMap::new());
+
+    /// recently deallocated objects, newest overwriting oldest.
+    static ref QUARANTINE: Mutex<Quarantine> = Mutex::new(Quarantine::new());
+}
+
+/// record a live object in `LIVE_HEAP` and, with the shadow backend, the shadow.
+/// returns the object it replaced.
+fn insert_live(base: usize, entry: Entry) -> Option<Entry> {
+    let replaced = LIVE_HEAP.insert(base, entry);
+    if shadow::enabled() {
+        if let Some((size, _, _)) = replaced {
+            SHADOW.remove(base, size);
+        }
+        SHADOW.insert(base, entry);
+    }
+    replaced
+}
+
+/// remove the live object starting at `base` from `LIVE_HEAP` and the shadow.
+fn remove_live(base: usize) -> Option<Entry> {
+    let removed = LIVE_HEAP.remove(base);
+    if let Some((size, _, _)) = removed.filter(|_| shadow::enabled()) {
+        SHADOW.remove(base, size);
+    }
+    removed
+}
+
+/// the site of the live object starting at `base`, if any.
+#[cfg(feature = "preload")]
+pub(crate) fn live_site(base: usize) -> Option<u64> {
+    LIVE_HEAP.get(base).map(|(_, site_id, _)| site_id)
+}
+
+/// the live heap object containing `ptr`, if any.
+pub(crate) fn get_live_heap_object(ptr: *const u8) -> Option<LiveObject> {
+    if shadow::enabled() {
+        match SHADOW.lookup(ptr as usize) {
+            Lookup::Hit(obj) => return Some(LiveObject::from_entry(obj)),
+            Lookup::Miss => return None,
+            Lookup::Unknown => {}
+        }
+    }
+    LIVE_HEAP.containing(ptr as usize).map(LiveObject::from_entry)
+}
+
+/// the live objects nearest to `ptr`: the last one starting at or before it and
+/// the first one starting after it, within one index region. neither needs to contain `ptr`.
+pub(crate) fn get_live_heap_neighbours(ptr: *const u8) -> (Option<LiveObject>, Option<LiveObject>) {
+    let (before, after) = LIVE_HEAP.neighbours(ptr as usize);
+    (before.map(LiveObject::from_entry), after.map(LiveObject::from_entry))
+}
+
+/// the quarantined (recently freed) object containing `ptr`, if any.
+/// callers should check `LIVE_HEAP` first, since freed memory may have been reused.
+/// never blocks: while a dealloc holds the quarantine the lookup is skipped and
+/// counted, see `quarantine_skipped`.
+pub(crate) fn get_quarantined_object(ptr: *const u8) -> Option<LiveObject> {
+    match QUARANTINE.try_lock() {
+        Ok(quarantine) => quarantine.find_containing(ptr as usize),
+        Err(TryLockError::WouldBlock) => {
+            QUARANTINE_SKIPPED.fetch_add(1, Ordering::Relaxed);
+            None
+        }
+        Err(TryLockError::Poisoned(_)) => None,
+    }
+}
+
+/// quarantine lookups skipped because the quarantine was locked.
+pub(crate) fn quarantine_skipped() -> u64 {
+    QUARANTINE_SKIPPED.load(Ordering::Relaxed)
 }
 
 /// Helper method to quickly identify if a given pointer hits a live heap object
 /// Returns (ticket, site_id) if found, or None.
 pub(crate) fn get_live_heap_ticket(ptr: *const u8) -> Option<(u64, u64)> {
-    let addr = ptr as usize;
-    let heap_map = LIVE_HEAP.read().unwrap();
-    if let Some((&base_addr, &(size, site_id, ticket))) = heap_map.range(..=addr).next_back() {
-        if addr < base_addr + size {
-            return Some((ticket, site_id));
+    get_live_heap_object(ptr).map(|obj| (obj.ticket, obj.site_id))
+}
+
+/// objects of one allocation site still in `LIVE_HEAP`, i.e. never deallocated.
+#[derive(Clone, Copy, Debug)]
+pub struct SiteLeak {
+    pub site_id: u64,
+    pub objects: u64,
+    pub bytes: u64,
+    /// smallest (earliest) allocation ticket among the leaked objects.
+    pub oldest_ticket: u64,
+}
+
+/// group the objects still in `LIVE_HEAP` by site, largest leaks first.
+/// hooks are disabled while `LIVE_HEAP` shards are held, since grouping allocates.
+pub fn leak_summary() -> Vec<SiteLeak> {
+    let mut by_site: HashMap<u64, SiteLeak> = HashMap::new();
+    crate::without_hooks(|| {
+        LIVE_HEAP.for_each(|_, &(size, site_id, ticket)| {
+            let leak = by_site.entry(site_id).or_insert(SiteLeak { site_id, objects: 0, bytes: 0, oldest_ticket: ticket });
+            leak.objects += 1;
+            leak.bytes += size as u64;
+            leak.oldest_ticket = leak.oldest_ticket.min(ticket);
+        });
+    });
+    let mut leaks: Vec<SiteLeak> = by_site.into_values().collect();
+    leaks.sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes).then(a.site_id.cmp(&b.site_id)));
+    leaks
+}
+
+/// leak sites shown in the text report; the json report lists all of them.
+const TEXT_LEAK_SITES: usize = 20;
+
+pub(crate) fn write_leaks_json(leaks: &[SiteLeak], a: &mut JsonArray) {
+    for leak in leaks {
+        a.object(|o| {
+            o.u64("site_id", leak.site_id)
+                .u64("objects", leak.objects)
+                .u64("bytes", leak.bytes)
+                .u64("oldest_ticket", leak.oldest_ticket);
+            if let Some(info) = pts_dump::site_info(leak.site_id) {
+                info.write_json(o);
+            }
+        });
+    }
+}
+
+/// human-readable leak summary, joined with the static site metadata when available.
+pub fn write_leaks_text(leaks: &[SiteLeak], out: &mut dyn Write) -> io::Result<()> {
+    let objects: u64 = leaks.iter().map(|l| l.objects).sum();
+    let bytes: u64 = leaks.iter().map(|l| l.bytes).sum();
+    writeln!(out, "\n=== SVF Heap Leak Summary ===")?;
+    writeln!(out, "Live heap objects at exit (never deallocated): {} ({} bytes) from {} sites", objects, bytes, leaks.len())?;
+    for leak in leaks.iter().take(TEXT_LEAK_SITES) {
+        write!(out, "  site {}: {} objects, {} bytes, oldest ticket {}", leak.site_id, leak.objects, leak.bytes, leak.oldest_ticket)?;
+        match pts_dump::site_info(leak.site_id) {
+            Some(info) => writeln!(out, " -- {}", info.describe())?,
+            None => writeln!(out)?,
         }
     }
-    None
+    if leaks.len() > TEXT_LEAK_SITES {
+        writeln!(out, "  ... {} more sites in the json report", leaks.len() - TEXT_LEAK_SITES)?;
+    }
+    writeln!(out, "==============================\n")
+}
+
+/// print the exit-time leak summary.
+pub fn print_heap_stats() {
+    let leaks = crate::without_hooks(leak_summary);
+    let _ = write_leaks_text(&leaks, &mut io::stdout().lock());
 }
 
-/// heap stats are now reported by unsafe_heap_access::print_unsafe_heap_stats().
-/// this function is kept as a no-op for backward compatibility.
-pub fn print_heap_stats() {}
+/// snapshot of the per-site allocation statistics, sorted by site id.
+/// hooks are disabled while `SITE_STATS` is held, since collecting allocates.
+pub fn site_stats() -> Vec<(u64, SiteStats)> {
+    let mut sites: Vec<(u64, SiteStats)> = crate::without_hooks(|| {
+        let stats = SITE_STATS.lock().unwrap();
+        stats.iter().map(|(&id, &s)| (id, s)).collect()
+    });
+    sites.sort_unstable_by_key(|&(id, _)| id);
+    sites
+}
+
+pub(crate) fn write_site_stats_json(sites: &[(u64, SiteStats)], a: &mut JsonArray) {
+    for (site_id, s) in sites {
+        a.object(|o| {
+            o.u64("site_id", *site_id)
+                .u64("alloc_count", s.alloc_count)
+                .u64("alloc_bytes", s.alloc_bytes)
+                .u64("free_count", s.free_count)
+                .u64("free_bytes", s.free_bytes)
+                .u64("realloc_count", s.realloc_count)
+                .u64("realloc_grow_bytes", s.realloc_grow_bytes)
+                .u64("realloc_shrink_bytes", s.realloc_shrink_bytes)
+                .u64("double_frees", s.double_frees)
+                .u64("interior_frees", s.interior_frees);
+        });
+    }
+}
 
 /// Helper function for `unsafe_heap_access` to query dynamic allocation volumes
 /// for SVF statically predicted `site_id`s.
 pub(crate) fn get_site_alloc_bytes(site_id: u64) -> u64 {
-    if let Ok(stats) = SITE_STATS.try_lock() {
+    if let Ok(stats) = SITE_STATS.lock() {
         if let Some(entry) = stats.get(&site_id) {
             return entry.alloc_bytes;
         }
@@ -70,7 +372,7 @@ pub(crate) fn get_site_alloc_bytes(site_id: u64) -> u64 {
 /// Helper function for `unsafe_heap_access` to query dynamic allocation frequencies
 /// for SVF statically predicted `site_id`s.
 pub(crate) fn get_site_alloc_count(site_id: u64) -> u64 {
-    if let Ok(stats) = SITE_STATS.try_lock() {
+    if let Ok(stats) = SITE_STATS.lock() {
         if let Some(entry) = stats.get(&site_id) {
             return entry.alloc_count;
         }
@@ -78,6 +380,10 @@ pub(crate) fn get_site_alloc_count(site_id: u64) -> u64 {
     0
 }
 
+/// runtime hook: records a new heap object `[ptr, ptr + size)` allocated at `site_id`.
+///
+/// # Safety
+/// `ptr` is only used as an address key and never dereferenced.
 #[no_mangle]
 pub unsafe extern "C" fn __svf_report_alloc(ptr: *mut u8, size: usize, site_id: u64) {
     if ptr.is_null() { return; }
@@ -85,22 +391,33 @@ pub unsafe extern "C" fn __svf_report_alloc(ptr: *mut u8, size: usize, site_id:
     IN_CHECKER.with(|c| c.set(true));
     let _guard = ReentrancyGuard;
 
-    let addr = ptr as usize;
+    record_alloc(ptr as usize, size, site_id);
+}
+
+pub(crate) fn record_alloc(addr: usize, size: usize, site_id: u64) {
     let ticket = ALLOCATION_TICKET_COUNTER.fetch_add(1, Ordering::SeqCst);
+    stacks::record_alloc(ticket, site_id, size);
 
-    {
-        let mut heap_map = LIVE_HEAP.write().unwrap();
-        heap_map.insert(addr, (size, site_id, ticket));
-    }
+    let replaced = insert_live(addr, (size, site_id, ticket));
 
     {
         let mut stats = SITE_STATS.lock().unwrap();
-        let entry = stats.entry(site_id).or_insert_with(SiteStats::new);
+        if let Some((old_size, old_site, old_ticket)) = replaced {
+            stacks::forget(old_ticket);
+            let old_entry = stats.entry(old_site).or_default();
+            old_entry.free_count += 1;
+            old_entry.free_bytes += old_size as u64;
+        }
+        let entry = stats.entry(site_id).or_default();
         entry.alloc_count += 1;
         entry.alloc_bytes += size as u64;
     }
 }
 
+/// runtime hook: removes the heap object starting at `ptr` from `LIVE_HEAP`.
+///
+/// # Safety
+/// `ptr` is only used as an address key and never dereferenced.
 #[no_mangle]
 pub unsafe extern "C" fn __svf_report_dealloc(ptr: *mut u8) {
     if ptr.is_null() { return; }
@@ -108,19 +425,225 @@ pub unsafe extern "C" fn __svf_report_dealloc(ptr: *mut u8) {
     IN_CHECKER.with(|c| c.set(true));
     let _guard = ReentrancyGuard;
 
-    let addr = ptr as usize;
+    if !record_dealloc(ptr as usize) {
+        let (kind, object) = classify_bad_free(ptr as usize);
+        report_bad_free(ptr, kind, object, false);
+    }
+}
+
+/// remove the live object starting at `addr`, returning false if there is none.
+pub(crate) fn record_dealloc(addr: usize) -> bool {
+    let Some((size, site_id, ticket)) = remove_live(addr) else { return false };
+    stacks::forget(ticket);
+    if let Ok(mut freed) = QUARANTINE.lock() {
+        freed.push(LiveObject { base: addr, size, site_id, ticket });
+    }
+    let mut stats = SITE_STATS.lock().unwrap();
+    if let Some(entry) = stats.get_mut(&site_id) {
+        entry.free_count += 1;
+        entry.free_bytes += size as u64;
+    }
+    true
+}
 
-    let removed_info = {
-        let mut heap_map = LIVE_HEAP.write().unwrap();
-        heap_map.remove(&addr)
-    };
+/// classify a free of `addr` that matched no live object base, with the object it refers to.
+fn classify_bad_free(addr: usize) -> (BadFree, Option<LiveObject>) {
+    if let Some(live) = get_live_heap_object(addr as *const u8) {
+        return (BadFree::InteriorPointer, Some(live));
+    }
+    let freed = QUARANTINE.lock().ok().and_then(|q| q.find_base(addr));
+    match freed {
+        Some(obj) => (BadFree::DoubleFree, Some(obj)),
+        None => (BadFree::NeverAllocated, None),
+    }
+}
 
-    if let Some((size, site_id, _ticket)) = removed_info {
+/// count and emit a bad free of `ptr`, by a dealloc or, with `realloc`, a realloc.
+fn report_bad_free(ptr: *const u8, kind: BadFree, object: Option<LiveObject>, realloc: bool) {
+    let op = if realloc { "realloc" } else { "free" };
+    BAD_FREES[kind as usize].fetch_add(1, Ordering::Relaxed);
+    if let Some(obj) = object {
         let mut stats = SITE_STATS.lock().unwrap();
-        if let Some(entry) = stats.get_mut(&site_id) {
-            entry.free_count += 1;
-            entry.free_bytes += size as u64;
+        let entry = stats.entry(obj.site_id).or_default();
+        match kind {
+            BadFree::DoubleFree => entry.double_frees += 1,
+            BadFree::InteriorPointer => entry.interior_frees += 1,
+            BadFree::NeverAllocated => {}
+        }
+    }
+
+    match object {
+        Some(obj) => println!(
+            "{{\"event\":\"svf_bad_free\",\"kind\":\"{}\",\"op\":\"{}\",\"ptr\":\"{:p}\",\"site_id\":{},\"ticket\":{},\"object_base\":\"{:#x}\",\"object_size\":{}}}",
+            kind.as_str(), op, ptr, obj.site_id, obj.ticket, obj.base, obj.size,
+        ),
+        None => println!("{{\"event\":\"svf_bad_free\",\"kind\":\"{}\",\"op\":\"{}\",\"ptr\":\"{:p}\"}}", kind.as_str(), op, ptr),
+    }
+
+    if abort_on_bad_free() {
+        let _ = io::stdout().flush();
+        eprintln!("[svf_runtime] {} of {:p} by {}, aborting ({}=1)", kind.as_str(), ptr, op, ABORT_ON_BAD_FREE_ENV);
+        std::process::abort();
+    }
+}
+
+fn abort_on_bad_free() -> bool {
+    static ABORT: OnceLock<bool> = OnceLock::new();
+    *ABORT.get_or_init(|| matches!(std::env::var(ABORT_ON_BAD_FREE_ENV).as_deref(), Ok("1" | "true" | "on")))
+}
+
+/// number of bad frees per kind, in `BadFree::ALL` order.
+pub fn bad_free_counts() -> [u64; 3] {
+    [0, 1, 2].map(|i| BAD_FREES[i].load(Ordering::Relaxed))
+}
+
+pub(crate) fn write_bad_frees_json(counts: &[u64; 3], o: &mut JsonObject) {
+    for (kind, count) in BadFree::ALL.iter().zip(counts) {
+        o.u64(kind.as_str(), *count);
+    }
+}
+
+/// human-readable bad free counts; prints nothing when every dealloc was valid.
+pub fn write_bad_frees_text(counts: &[u64; 3], out: &mut dyn Write) -> io::Result<()> {
+    if counts.iter().all(|&c| c == 0) { return Ok(()); }
+    writeln!(out, "\n=== SVF Invalid Deallocations ===")?;
+    for (kind, count) in BadFree::ALL.iter().zip(counts) {
+        writeln!(out, "  {}: {}", kind.as_str(), count)?;
+    }
+    writeln!(out, "==============================\n")
+}
+
+/// runtime hook: records a realloc of `old_ptr` into `[new_ptr, new_ptr + new_size)`.
+/// see the module docs for how tickets and site ids are carried over.
+/// a null `new_ptr` means the realloc failed and the old object is left untouched.
+///
+/// # Safety
+/// `old_ptr` and `new_ptr` are only used as address keys and never dereferenced.
+#[no_mangle]
+pub unsafe extern "C" fn __svf_report_realloc(old_ptr: *mut u8, new_ptr: *mut u8, new_size: usize, site_id: u64) {
+    if new_ptr.is_null() { return; }
+    if IN_CHECKER.with(|c| c.get()) { return; }
+    IN_CHECKER.with(|c| c.set(true));
+    let _guard = ReentrancyGuard;
+
+    let (old_addr, new_addr) = (old_ptr as usize, new_ptr as usize);
+    if !record_realloc(old_addr, new_addr, new_size, site_id) {
+        // reallocating a freed or interior pointer is as invalid as freeing it; other
+        // untracked pointers may come from uninstrumented allocations.
+        if old_addr != 0 {
+            let (kind, object) = classify_bad_free(old_addr);
+            if kind != BadFree::NeverAllocated {
+                report_bad_free(old_ptr, kind, object, true);
+            }
+        }
+        record_alloc(new_addr, new_size, site_id);
+    }
+}
+
+/// move the live object at `old_addr` (0 for none) to `[new_addr, new_addr + new_size)`,
+/// returning false, without recording anything, if there is no such object.
+pub(crate) fn record_realloc(old_addr: usize, new_addr: usize, new_size: usize, site_id: u64) -> bool {
+    let Some(taken) = begin_realloc(old_addr) else { return false };
+    end_realloc(old_addr, taken, new_addr, new_size, site_id);
+    true
+}
+
+/// first half of `record_realloc` for allocator wrappers, called before the block
+/// is reallocated: take the live object at `old_addr` (0 for none) out of
+/// `LIVE_HEAP`, so a thread getting the freed address back cannot be confused with it.
+pub(crate) fn begin_realloc(old_addr: usize) -> Option<Entry> {
+    if old_addr == 0 { None } else { remove_live(old_addr) }
+}
+
+/// second half of `record_realloc`: `new_addr` is the realloc's result. a failed
+/// realloc (0) leaves the block where it was, so the object is put back.
+pub(crate) fn end_realloc(old_addr: usize, taken: Entry, new_addr: usize, new_size: usize, site_id: u64) {
+    if new_addr == 0 {
+        insert_live(old_addr, taken);
+        return;
+    }
+    let (old_size, old_site, ticket) = taken;
+    let new_site = if site_id != 0 { site_id } else { old_site };
+    insert_live(new_addr, (new_size, new_site, ticket));
+
+    // a moved object's old block is gone; freeing it again is a double free.
+    if old_addr != new_addr {
+        if let Ok(mut freed) = QUARANTINE.lock() {
+            freed.push(LiveObject { base: old_addr, size: old_size, site_id: old_site, ticket });
         }
     }
+
+    let mut stats = SITE_STATS.lock().unwrap();
+    if new_site != old_site {
+        let old_entry = stats.entry(old_site).or_default();
+        old_entry.free_count += 1;
+        old_entry.free_bytes += old_size as u64;
+        let new_entry = stats.entry(new_site).or_default();
+        new_entry.alloc_count += 1;
+        new_entry.alloc_bytes += new_size as u64;
+    }
+    let entry = stats.entry(new_site).or_default();
+    entry.realloc_count += 1;
+    if new_size >= old_size {
+        entry.realloc_grow_bytes += (new_size - old_size) as u64;
+    } else {
+        entry.realloc_shrink_bytes += (old_size - new_size) as u64;
+    }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::{LiveObject, Quarantine, QUARANTINE_CAP, QUARANTINE_SCAN};
+
+    fn freed(base: usize, size: usize) -> LiveObject {
+        LiveObject { base, size, site_id: base as u64, ticket: base as u64 }
+    }
+
+    fn found(q: &Quarantine, addr: usize) -> Option<usize> {
+        q.find_containing(addr).map(|obj| obj.base)
+    }
+
+    #[test]
+    fn containing_object_behind_other_bases_is_found() {
+        let mut q = Quarantine::new();
+        q.push(freed(0x1000, 0x100));
+        // smaller objects freed later inside the same range.
+        q.push(freed(0x1010, 0x10));
+        q.push(freed(0x1040, 0x10));
+        assert_eq!(found(&q, 0x1080), Some(0x1000));
+        assert_eq!(found(&q, 0x1044), Some(0x1040));
+        assert_eq!(found(&q, 0x1100), None);
+        assert_eq!(found(&q, 0xfff), None);
+        assert_eq!(q.find_base(0x1010).map(|o| o.size), Some(0x10));
+    }
+
+    #[test]
+    fn scan_is_bounded() {
+        let mut q = Quarantine::new();
+        q.push(freed(0x10000, 0x1000));
+        for i in 0..QUARANTINE_SCAN {
+            q.push(freed(0x10001 + i, 1));
+        }
+        // exactly QUARANTINE_SCAN bases from 0x10001 up to the address.
+        assert_eq!(found(&q, 0x10000 + QUARANTINE_SCAN), Some(0x10000 + QUARANTINE_SCAN));
+        assert_eq!(found(&q, 0x10800), None);
+        q.push(freed(0x20000, 8));
+        assert_eq!(found(&q, 0x20007), Some(0x20000));
+    }
+
+    #[test]
+    fn evicted_objects_are_forgotten() {
+        let mut q = Quarantine::new();
+        for i in 0..QUARANTINE_CAP + 1 {
+            q.push(freed(0x100000 + i * 0x10, 0x10));
+        }
+        assert_eq!(found(&q, 0x100008), None);
+        assert_eq!(found(&q, 0x100018), Some(0x100010));
+        // a newer free at an evicted base keeps its index entry.
+        q.push(freed(0x100020, 0x20));
+        for i in 0..QUARANTINE_CAP - 1 {
+            q.push(freed(0x900000 + i * 0x10, 0x10));
+        }
+        assert_eq!(q.find_base(0x100020).map(|o| o.size), Some(0x20));
+    }
+}
@@ -0,0 +1,356 @@
+//! sharded interval index of live heap objects, behind `heap::LIVE_HEAP`. the
+//! stack and global objects of `objects::LIVE_OBJECTS` are kept in one as well.
+//!
+//! a single `RwLock<BTreeMap>` serializes allocation-heavy multithreaded programs:
+//! every alloc/dealloc takes it for write and every unsafe access for read.
+//! [`HeapIndex`] splits the address space into `REGION_SIZE` (1 MiB) regions and
+//! spreads the regions over `SHARDS` independently locked btree maps. allocators
+//! hand different threads different arenas, so threads mostly hit different shards.
+//! - an object of at most `REGION_SIZE` bytes is kept in the shard of its base's
+//!   region. it can run into the next region, so a lookup checks the shard of the
+//!   address's region and the one before it.
+//! - larger objects are kept in a separate `large` map, only consulted while it
+//!   is non-empty.
+//!
+//! live objects never overlap, so lookups return exactly what a single btree map
+//! keyed by base address would. the exception is [`HeapIndex::neighbours`], which
+//! only finds objects reaching into the region below the address's or starting
+//! in the region above it.
+
+use std::collections::BTreeMap;
+use std::ops::Bound;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// (size, site_id, ticket) of a live object.
+pub type Entry = (usize, u64, u64);
+/// (base, entry) of a live object.
+pub type Object = (usize, Entry);
+
+const REGION_SHIFT: u32 = 20;
+/// largest object kept in the sharded maps.
+pub const REGION_SIZE: usize = 1 << REGION_SHIFT;
+/// number of independently locked shards, a power of two.
+const SHARDS: usize = 64;
+const _: () = assert!(SHARDS.is_power_of_two());
+
+type Shard = RwLock<BTreeMap<usize, Entry>>;
+
+pub struct HeapIndex {
+    shards: Vec<Shard>,
+    large: Shard,
+    /// objects in `large`, so lookups can skip its lock while it is empty.
+    large_count: AtomicUsize,
+}
+
+impl Default for HeapIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn region(addr: usize) -> usize {
+    addr >> REGION_SHIFT
+}
+
+/// the object in `shard` with the closest base at or below `addr`.
+fn below(shard: &Shard, addr: usize) -> Option<Object> {
+    shard.read().unwrap().range(..=addr).next_back().map(|(&base, &entry)| (base, entry))
+}
+
+/// the object in `shard` with the closest base above `addr`.
+fn above(shard: &Shard, addr: usize) -> Option<Object> {
+    let map = shard.read().unwrap();
+    map.range((Bound::Excluded(addr), Bound::Unbounded)).next().map(|(&base, &entry)| (base, entry))
+}
+
+fn contains((base, (size, _, _)): Object, addr: usize) -> bool {
+    addr < base + size
+}
+
+impl HeapIndex {
+    pub fn new() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| RwLock::new(BTreeMap::new())).collect(),
+            large: RwLock::new(BTreeMap::new()),
+            large_count: AtomicUsize::new(0),
+        }
+    }
+
+    fn shard(&self, region: usize) -> &Shard {
+        // glibc arenas are 64 MiB apart, a multiple of `SHARDS` regions, so a plain
+        // `region % SHARDS` would put the same offset of every arena in one shard.
+        let hash = (region as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15) >> (64 - SHARDS.trailing_zeros());
+        &self.shards[hash as usize]
+    }
+
+    fn has_large(&self) -> bool {
+        self.large_count.load(Ordering::Acquire) > 0
+    }
+
+    fn remove_large(&self, base: usize) -> Option<Entry> {
+        let removed = self.large.write().unwrap().remove(&base);
+        if removed.is_some() {
+            self.large_count.fetch_sub(1, Ordering::AcqRel);
+        }
+        removed
+    }
+
+    /// record the object at `base`, returning the entry it replaced.
+    pub fn insert(&self, base: usize, entry: Entry) -> Option<Entry> {
+        if entry.0 > REGION_SIZE {
+            let replaced = self.large.write().unwrap().insert(base, entry);
+            if replaced.is_none() {
+                self.large_count.fetch_add(1, Ordering::AcqRel);
+                return self.shard(region(base)).write().unwrap().remove(&base);
+            }
+            replaced
+        } else {
+            let replaced = self.shard(region(base)).write().unwrap().insert(base, entry);
+            match replaced {
+                None if self.has_large() => self.remove_large(base),
+                _ => replaced,
+            }
+        }
+    }
+
+    /// remove the object starting at `base`.
+    pub fn remove(&self, base: usize) -> Option<Entry> {
+        let removed = self.shard(region(base)).write().unwrap().remove(&base);
+        match removed {
+            None if self.has_large() => self.remove_large(base),
+            _ => removed,
+        }
+    }
+
+    /// the entry of the object starting at `base`.
+    pub fn get(&self, base: usize) -> Option<Entry> {
+        let entry = self.shard(region(base)).read().unwrap().get(&base).copied();
+        match entry {
+            None if self.has_large() => self.large.read().unwrap().get(&base).copied(),
+            _ => entry,
+        }
+    }
+
+    /// the object containing `addr`, as (base, entry).
+    pub fn containing(&self, addr: usize) -> Option<Object> {
+        let r = region(addr);
+        if let Some(hit) = below(self.shard(r), addr).filter(|&obj| contains(obj, addr)) {
+            return Some(hit);
+        }
+        if r > 0 {
+            if let Some(hit) = below(self.shard(r - 1), addr).filter(|&obj| contains(obj, addr)) {
+                return Some(hit);
+            }
+        }
+        if self.has_large() {
+            return below(&self.large, addr).filter(|&obj| contains(obj, addr));
+        }
+        None
+    }
+
+    /// the closest objects starting at or below `addr` and above it, as long as
+    /// they are at most one region away. neither needs to contain `addr`.
+    pub fn neighbours(&self, addr: usize) -> (Option<Object>, Option<Object>) {
+        let r = region(addr);
+        let low = r.saturating_sub(1) << REGION_SHIFT;
+        let high = (r + 2).saturating_mul(REGION_SIZE);
+
+        let has_large = self.has_large();
+        let lower = [
+            below(self.shard(r), addr),
+            if r > 0 { below(self.shard(r - 1), addr) } else { None },
+            // small objects from two regions down can still reach into the window.
+            if r > 1 { below(self.shard(r - 2), addr) } else { None },
+            if has_large { below(&self.large, addr) } else { None },
+        ];
+        let upper = [
+            above(self.shard(r), addr),
+            above(self.shard(r + 1), addr),
+            if has_large { above(&self.large, addr) } else { None },
+        ];
+        // candidates outside the scanned window may not be the closest ones.
+        let before = lower.into_iter().flatten().filter(|&(base, (size, _, _))| base + size > low).max_by_key(|obj| obj.0);
+        let after = upper.into_iter().flatten().filter(|&(base, _)| base < high).min_by_key(|obj| obj.0);
+        (before, after)
+    }
+
+    /// visit every live object, in no particular order.
+    pub fn for_each(&self, mut f: impl FnMut(usize, &Entry)) {
+        for shard in self.shards.iter().chain(std::iter::once(&self.large)) {
+            for (&base, entry) in shard.read().unwrap().iter() {
+                f(base, entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Entry, HeapIndex, Object, REGION_SIZE};
+    use std::collections::BTreeMap;
+    use std::sync::atomic::Ordering;
+
+    const MIB: usize = REGION_SIZE;
+
+    /// the plain btree map `HeapIndex` stands in for.
+    #[derive(Default)]
+    struct Reference(BTreeMap<usize, Entry>);
+
+    impl Reference {
+        fn containing(&self, addr: usize) -> Option<Object> {
+            let (&base, &entry) = self.0.range(..=addr).next_back()?;
+            (addr < base + entry.0).then_some((base, entry))
+        }
+
+        /// as documented: neighbours within a region of the address's region.
+        fn neighbours(&self, addr: usize) -> (Option<Object>, Option<Object>) {
+            let r = addr / MIB;
+            let (low, high) = (r.saturating_sub(1) * MIB, (r + 2) * MIB);
+            let before = self.0.range(..=addr).next_back().filter(|(&base, e)| base + e.0 > low);
+            let after = self.0.range(addr + 1..).next().filter(|(&base, _)| base < high);
+            (before.map(|(&b, &e)| (b, e)), after.map(|(&b, &e)| (b, e)))
+        }
+
+        fn large(&self) -> usize {
+            self.0.values().filter(|e| e.0 > REGION_SIZE).count()
+        }
+    }
+
+    fn entry(size: usize, ticket: u64) -> Entry {
+        (size, ticket % 7, ticket)
+    }
+
+    fn large_count(index: &HeapIndex) -> usize {
+        index.large_count.load(Ordering::Relaxed)
+    }
+
+    #[test]
+    fn objects_crossing_a_region_boundary_are_found() {
+        let index = HeapIndex::new();
+        let base = 5 * MIB - 16;
+        index.insert(base, entry(64, 1));
+        assert_eq!(index.containing(5 * MIB + 40), Some((base, entry(64, 1))));
+        assert_eq!(index.containing(5 * MIB + 48), None);
+        assert_eq!(index.containing(base - 1), None);
+        // a full region, ending exactly at the next boundary.
+        index.insert(7 * MIB, entry(MIB, 2));
+        assert_eq!(index.containing(8 * MIB - 1).map(|o| o.0), Some(7 * MIB));
+        assert_eq!(index.containing(8 * MIB), None);
+        assert_eq!(large_count(&index), 0);
+    }
+
+    #[test]
+    fn large_objects_and_replacement_across_size_classes() {
+        let index = HeapIndex::new();
+        let base = 20 * MIB + 4096;
+        assert_eq!(index.insert(base, entry(3 * MIB, 1)), None);
+        assert_eq!(large_count(&index), 1);
+        assert_eq!(index.containing(base + 5 * MIB / 2), Some((base, entry(3 * MIB, 1))));
+        assert_eq!(index.get(base), Some(entry(3 * MIB, 1)));
+
+        // large -> small at the same base.
+        assert_eq!(index.insert(base, entry(64, 2)), Some(entry(3 * MIB, 1)));
+        assert_eq!(large_count(&index), 0);
+        assert_eq!(index.containing(base + 2 * MIB), None);
+        assert_eq!(index.get(base), Some(entry(64, 2)));
+
+        // small -> large.
+        assert_eq!(index.insert(base, entry(MIB + 1, 3)), Some(entry(64, 2)));
+        assert_eq!(large_count(&index), 1);
+        assert_eq!(index.containing(base + MIB), Some((base, entry(MIB + 1, 3))));
+        // large -> large keeps the count.
+        assert_eq!(index.insert(base, entry(2 * MIB, 4)), Some(entry(MIB + 1, 3)));
+        assert_eq!(large_count(&index), 1);
+
+        assert_eq!(index.remove(base), Some(entry(2 * MIB, 4)));
+        assert_eq!(large_count(&index), 0);
+        assert_eq!(index.remove(base), None);
+        assert_eq!(large_count(&index), 0);
+        assert_eq!(index.get(base), None);
+    }
+
+    #[test]
+    fn neighbours_at_the_window_edges() {
+        let index = HeapIndex::new();
+        let r = 40 * MIB;
+        // ends one byte into the window's lowest region, starting two regions down.
+        index.insert(r - MIB - 8, entry(9, 1));
+        // starts on the last byte of the window.
+        index.insert(r + 2 * MIB - 1, entry(8, 2));
+        let (before, after) = index.neighbours(r + 100);
+        assert_eq!(before.map(|o| o.0), Some(r - MIB - 8));
+        assert_eq!(after.map(|o| o.0), Some(r + 2 * MIB - 1));
+
+        // just outside the window on both sides.
+        let index = HeapIndex::new();
+        index.insert(r - MIB - 8, entry(8, 1));
+        index.insert(r + 2 * MIB, entry(8, 2));
+        assert_eq!(index.neighbours(r + 100), (None, None));
+
+        // region 0 has no region below it.
+        let index = HeapIndex::new();
+        index.insert(16, entry(8, 1));
+        assert_eq!(index.neighbours(8), (None, Some((16, entry(8, 1)))));
+        assert_eq!(index.neighbours(16).0, Some((16, entry(8, 1))));
+    }
+
+    #[test]
+    fn matches_a_btree_map() {
+        // one object per 4 MiB slot, so objects of up to 3 MiB never overlap.
+        const SLOTS: usize = 48;
+        const SLOT: usize = 4 * MIB;
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move |bound: usize| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            (state % bound as u64) as usize
+        };
+
+        let index = HeapIndex::new();
+        let mut reference = Reference::default();
+        let mut bases: Vec<Option<usize>> = vec![None; SLOTS];
+        for ticket in 0..4000u64 {
+            let slot = next(SLOTS);
+            let size = match next(4) {
+                0 => 1 + next(64),
+                1 => MIB - 8 + next(16),
+                2 => 2 * MIB + next(MIB),
+                _ => 1 + next(4096),
+            };
+            match bases[slot] {
+                Some(base) if next(2) == 0 => {
+                    assert_eq!(index.remove(base), reference.0.remove(&base));
+                    bases[slot] = None;
+                }
+                Some(base) => {
+                    // same base, possibly another size class.
+                    assert_eq!(index.insert(base, entry(size, ticket)), reference.0.insert(base, entry(size, ticket)));
+                }
+                None => {
+                    // often just below a region boundary.
+                    let offset = if next(2) == 0 { MIB - 1 - next(64) } else { next(MIB) };
+                    let base = slot * SLOT + offset;
+                    assert_eq!(index.insert(base, entry(size, ticket)), reference.0.insert(base, entry(size, ticket)));
+                    bases[slot] = Some(base);
+                }
+            }
+            assert_eq!(large_count(&index), reference.large());
+
+            for _ in 0..8 {
+                let addr = match bases[next(SLOTS)] {
+                    Some(base) => base + next(3 * MIB),
+                    None => next(SLOTS * SLOT),
+                };
+                assert_eq!(index.containing(addr), reference.containing(addr), "containing({:#x})", addr);
+                assert_eq!(index.neighbours(addr), reference.neighbours(addr), "neighbours({:#x})", addr);
+            }
+        }
+
+        let mut all = Vec::new();
+        index.for_each(|base, entry| all.push((base, *entry)));
+        all.sort_unstable();
+        assert_eq!(all, reference.0.into_iter().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,450 @@
+//! minimal json writer used by the runtime reports.
+//! the runtime is linked into instrumented programs, so it avoids pulling in
+//! serde; everything it emits is built through these helpers.
+
+use std::fmt::Write;
+
+/// append `s` to `out` as a quoted json string.
+pub(crate) fn write_str(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// append a json number, or `null` for values json cannot represent.
+pub(crate) fn write_f64(out: &mut String, v: f64) {
+    if v.is_finite() {
+        let _ = write!(out, "{}", v);
+    } else {
+        out.push_str("null");
+    }
+}
+
+/// write a json object into `out`, filling its fields through `f`.
+pub(crate) fn write_object(out: &mut String, f: impl FnOnce(&mut JsonObject)) {
+    out.push('{');
+    f(&mut JsonObject { out, first: true });
+    out.push('}');
+}
+
+/// write a json array into `out`, filling its elements through `f`.
+pub(crate) fn write_array(out: &mut String, f: impl FnOnce(&mut JsonArray)) {
+    out.push('[');
+    f(&mut JsonArray { out, first: true });
+    out.push(']');
+}
+
+pub(crate) struct JsonObject<'a> {
+    out: &'a mut String,
+    first: bool,
+}
+
+impl JsonObject<'_> {
+    fn key(&mut self, key: &str) {
+        if !self.first { self.out.push(','); }
+        self.first = false;
+        write_str(self.out, key);
+        self.out.push(':');
+    }
+
+    pub(crate) fn u64(&mut self, key: &str, v: u64) -> &mut Self {
+        self.key(key);
+        let _ = write!(self.out, "{}", v);
+        self
+    }
+
+    /// write `v`, or `null` when the value is undefined (e.g. a ratio with a zero denominator).
+    pub(crate) fn opt_f64(&mut self, key: &str, v: Option<f64>) -> &mut Self {
+        self.key(key);
+        match v {
+            Some(v) => write_f64(self.out, v),
+            None => self.out.push_str("null"),
+        }
+        self
+    }
+
+    pub(crate) fn bool(&mut self, key: &str, v: bool) -> &mut Self {
+        self.key(key);
+        self.out.push_str(if v { "true" } else { "false" });
+        self
+    }
+
+    pub(crate) fn str(&mut self, key: &str, v: &str) -> &mut Self {
+        self.key(key);
+        write_str(self.out, v);
+        self
+    }
+
+    pub(crate) fn u64_list(&mut self, key: &str, vs: impl IntoIterator<Item = u64>) -> &mut Self {
+        self.array(key, |a| {
+            for v in vs { a.u64(v); }
+        })
+    }
+
+    pub(crate) fn object(&mut self, key: &str, f: impl FnOnce(&mut JsonObject)) -> &mut Self {
+        self.key(key);
+        write_object(self.out, f);
+        self
+    }
+
+    pub(crate) fn array(&mut self, key: &str, f: impl FnOnce(&mut JsonArray)) -> &mut Self {
+        self.key(key);
+        write_array(self.out, f);
+        self
+    }
+}
+
+pub(crate) struct JsonArray<'a> {
+    out: &'a mut String,
+    first: bool,
+}
+
+impl JsonArray<'_> {
+    fn sep(&mut self) {
+        if !self.first { self.out.push(','); }
+        self.first = false;
+    }
+
+    pub(crate) fn u64(&mut self, v: u64) -> &mut Self {
+        self.sep();
+        let _ = write!(self.out, "{}", v);
+        self
+    }
+
+    pub(crate) fn object(&mut self, f: impl FnOnce(&mut JsonObject)) -> &mut Self {
+        self.sep();
+        write_object(self.out, f);
+        self
+    }
+}
+
+/// parsed json value, used to read the static svf dumps.
+/// numbers keep their source text so 64-bit ids survive without f64 rounding.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    pub(crate) fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_u64(&self) -> Option<u64> {
+        match self {
+            JsonValue::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[JsonValue]> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// parse a complete json document. errors carry the byte offset of the problem.
+pub(crate) fn parse(text: &str) -> Result<JsonValue, String> {
+    let mut p = Parser { bytes: text.as_bytes(), pos: 0 };
+    let value = p.value()?;
+    p.skip_ws();
+    if p.pos != p.bytes.len() {
+        return Err(p.error("trailing characters"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, what: &str) -> String {
+        format!("{} at byte {}", what, self.pos)
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.bytes.get(self.pos), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, c: u8) -> Result<(), String> {
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&c) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c as char)))
+        }
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, String> {
+        if self.bytes[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(value)
+        } else {
+            Err(self.error("invalid literal"))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, String> {
+        self.skip_ws();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.object(),
+            Some(b'[') => self.array(),
+            Some(b'"') => self.string().map(JsonValue::String),
+            Some(b't') => self.literal("true", JsonValue::Bool(true)),
+            Some(b'f') => self.literal("false", JsonValue::Bool(false)),
+            Some(b'n') => self.literal("null", JsonValue::Null),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character")),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonValue, String> {
+        self.eat(b'{')?;
+        let mut fields = Vec::new();
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&b'}') {
+            self.pos += 1;
+            return Ok(JsonValue::Object(fields));
+        }
+        loop {
+            self.skip_ws();
+            let key = self.string()?;
+            self.eat(b':')?;
+            fields.push((key, self.value()?));
+            self.skip_ws();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => { self.pos += 1; return Ok(JsonValue::Object(fields)); }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<JsonValue, String> {
+        self.eat(b'[')?;
+        let mut items = Vec::new();
+        self.skip_ws();
+        if self.bytes.get(self.pos) == Some(&b']') {
+            self.pos += 1;
+            return Ok(JsonValue::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_ws();
+            match self.bytes.get(self.pos) {
+                Some(b',') => self.pos += 1,
+                Some(b']') => { self.pos += 1; return Ok(JsonValue::Array(items)); }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    /// `-? (0 | [1-9][0-9]*) (. [0-9]+)? ([eE] [+-]? [0-9]+)?`
+    fn number(&mut self) -> Result<JsonValue, String> {
+        let start = self.pos;
+        if self.bytes.get(self.pos) == Some(&b'-') {
+            self.pos += 1;
+        }
+        match self.bytes.get(self.pos) {
+            Some(b'0') => self.pos += 1,
+            Some(b'1'..=b'9') => {
+                self.digits();
+            }
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.bytes.get(self.pos) == Some(&b'.') {
+            self.pos += 1;
+            if !self.digits() {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.bytes.get(self.pos), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.bytes.get(self.pos), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            if !self.digits() {
+                return Err(self.error("invalid number"));
+            }
+        }
+        // only ascii was consumed.
+        let text = std::str::from_utf8(&self.bytes[start..self.pos]).unwrap();
+        Ok(JsonValue::Number(text.to_string()))
+    }
+
+    /// skip a run of ascii digits, returning whether there was one.
+    fn digits(&mut self) -> bool {
+        let start = self.pos;
+        while matches!(self.bytes.get(self.pos), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos > start
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self.bytes.get(self.pos..self.pos + 4).ok_or_else(|| self.error("truncated escape"))?;
+        if !digits.iter().all(u8::is_ascii_hexdigit) {
+            return Err(self.error("invalid escape"));
+        }
+        // four ascii hex digits.
+        let v = u32::from_str_radix(std::str::from_utf8(digits).unwrap(), 16).unwrap();
+        self.pos += 4;
+        Ok(v)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.eat(b'"')?;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while !matches!(self.bytes.get(self.pos), Some(b'"' | b'\\' | 0..=0x1f) | None) {
+                self.pos += 1;
+            }
+            out.push_str(std::str::from_utf8(&self.bytes[start..self.pos]).map_err(|_| self.error("invalid utf-8"))?);
+            match self.bytes.get(self.pos) {
+                Some(b'"') => { self.pos += 1; return Ok(out); }
+                Some(b'\\') => self.pos += 1,
+                Some(_) => return Err(self.error("control character in string")),
+                None => return Err(self.error("unterminated string")),
+            }
+            let escape = *self.bytes.get(self.pos).ok_or_else(|| self.error("unterminated string"))?;
+            self.pos += 1;
+            match escape {
+                b'"' => out.push('"'),
+                b'\\' => out.push('\\'),
+                b'/' => out.push('/'),
+                b'b' => out.push('\u{8}'),
+                b'f' => out.push('\u{c}'),
+                b'n' => out.push('\n'),
+                b'r' => out.push('\r'),
+                b't' => out.push('\t'),
+                b'u' => {
+                    let mut code = self.hex4()?;
+                    // a high surrogate combines with a following low one; unpaired
+                    // surrogates become U+FFFD.
+                    if (0xd800..0xdc00).contains(&code) && self.bytes[self.pos..].starts_with(b"\\u") {
+                        let pos = self.pos;
+                        self.pos += 2;
+                        let low = self.hex4()?;
+                        if (0xdc00..0xe000).contains(&low) {
+                            code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                        } else {
+                            self.pos = pos;
+                        }
+                    }
+                    out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                }
+                _ => return Err(self.error("invalid escape")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, JsonValue};
+
+    fn string(text: &str) -> String {
+        parse(text).unwrap().as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn numbers_follow_the_json_grammar() {
+        for ok in ["0", "-0", "7", "18446744073709551615", "1.5", "-0.25", "1e9", "1E+9", "2.5e-3"] {
+            assert_eq!(parse(ok), Ok(JsonValue::Number(ok.to_string())), "{}", ok);
+        }
+        assert_eq!(parse("18446744073709551615").unwrap().as_u64(), Some(u64::MAX));
+        for bad in ["--", "-", "+1", "1e+-", "1e", "1.", ".5", "01", "-01", "1.e3", "1..2", "1e5.0", "0x10", "1-2"] {
+            assert!(parse(bad).is_err(), "{} parsed", bad);
+        }
+    }
+
+    #[test]
+    fn strings_unescape() {
+        assert_eq!(string(r#""a\"b\\c\/d""#), "a\"b\\c/d");
+        assert_eq!(string(r#""\b\f\n\r\t""#), "\u{8}\u{c}\n\r\t");
+        assert_eq!(string(r#""\u00e9\u4E2D""#), "\u{e9}\u{4e2d}");
+        // surrogate pairs combine, unpaired halves are replaced.
+        assert_eq!(string(r#""\ud83d\ude00""#), "\u{1f600}");
+        assert_eq!(string(r#""\ud83dx""#), "\u{fffd}x");
+        assert_eq!(string(r#""\ude00""#), "\u{fffd}");
+        assert_eq!(string(r#""\ud83d\u0041""#), "\u{fffd}A");
+        assert_eq!(string(r#""\ud83d\ud83d\ude00""#), "\u{fffd}\u{1f600}");
+        // non-ascii passes through unescaped.
+        assert_eq!(string("\"caf\u{e9}\""), "caf\u{e9}");
+    }
+
+    #[test]
+    fn nested_values_parse() {
+        let value = parse(r#" { "a" : [1, {"b": [true, false, null]}, []], "c": {}, "d": "x" } "#).unwrap();
+        let a = value.get("a").unwrap().as_array().unwrap();
+        assert_eq!(a.len(), 3);
+        assert_eq!(a[0].as_u64(), Some(1));
+        assert_eq!(
+            a[1].get("b"),
+            Some(&JsonValue::Array(vec![JsonValue::Bool(true), JsonValue::Bool(false), JsonValue::Null]))
+        );
+        assert_eq!(a[2], JsonValue::Array(vec![]));
+        assert_eq!(value.get("c"), Some(&JsonValue::Object(vec![])));
+        assert_eq!(value.get("d").and_then(JsonValue::as_str), Some("x"));
+        assert_eq!(value.get("e"), None);
+    }
+
+    #[test]
+    fn malformed_input_is_rejected() {
+        for bad in [
+            "",
+            "{",
+            "[1,]",
+            "[1 2]",
+            "{\"a\" 1}",
+            "{\"a\":1,}",
+            "{1:2}",
+            "\"open",
+            "\"tab\there\"",
+            r#""\x""#,
+            r#""\u12""#,
+            r#""\u+123""#,
+            "tru",
+            "nul",
+            "[] []",
+            "{} x",
+        ] {
+            assert!(parse(bad).is_err(), "{:?} parsed", bad);
+        }
+        assert_eq!(parse("[1,]"), Err(String::from("unexpected character at byte 3")));
+    }
+}
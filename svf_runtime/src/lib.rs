@@ -2,7 +2,12 @@
 //! provides runtime hooks for svf-based analysis:
 //! - alias checking (__svf_check_alias)
 //! - heap verification (__svf_report_alloc, __svf_report_dealloc)
+//! - stack / global object registration (__svf_report_stack_obj, __svf_report_global)
 //! - unsafe heap access counting (__svf_unsafe_heap_access)
+//! - sampled allocation call stacks (`stacks`)
+//! - a tracking global allocator for rust programs (`tracking_alloc`)
+//! - LD_PRELOAD libc allocator interposers (`preload`, cargo feature `preload`)
+//! - end-of-run report (text and json, see `report`)
 
 #![feature(thread_local)]
 
@@ -12,13 +17,26 @@ use std::cell::Cell;
 #[macro_use]
 extern crate lazy_static;
 
+mod access_log;
 pub mod alias;
 pub mod heap;
+pub mod heap_index;
+mod json;
+pub mod objects;
+#[cfg(feature = "preload")]
+pub mod preload;
+pub mod pts_dump;
+pub mod region;
+mod registry;
+pub mod report;
+pub mod shadow;
+pub mod stacks;
+pub mod tracking_alloc;
 pub mod unsafe_heap_access;
 
 thread_local! {
     /// shared reentrancy guard used by heap and alias modules.
-    pub(crate) static IN_CHECKER: Cell<bool> = Cell::new(false);
+    pub(crate) static IN_CHECKER: Cell<bool> = const { Cell::new(false) };
 }
 
 /// raii guard that resets IN_CHECKER when dropped, ensuring the reentrancy
@@ -30,6 +48,31 @@ impl Drop for ReentrancyGuard {
     }
 }
 
+/// run `f` as a runtime hook: skipped while the hooks are disabled on this
+/// thread, with the reentrancy guard held otherwise. for hooks that are not
+/// `extern "C"` entry points, e.g. allocator wrappers.
+pub(crate) fn hook(f: impl FnOnce()) {
+    if IN_CHECKER.with(|c| c.get()) { return; }
+    IN_CHECKER.with(|c| c.set(true));
+    let _guard = ReentrancyGuard;
+    f();
+}
+
+/// run `f` with the runtime hooks disabled on this thread, e.g. while the runtime
+/// itself allocates and may hold `LIVE_HEAP` locks.
+pub(crate) fn without_hooks<R>(f: impl FnOnce() -> R) -> R {
+    let was_checking = IN_CHECKER.with(|c| c.replace(true));
+    let _restore = RestoreChecker(was_checking);
+    f()
+}
+
+struct RestoreChecker(bool);
+impl Drop for RestoreChecker {
+    fn drop(&mut self) {
+        IN_CHECKER.with(|c| c.set(self.0));
+    }
+}
+
 extern "C" {
     pub(crate) fn atexit(cb: extern "C" fn()) -> i32;
 }
@@ -42,16 +85,17 @@ pub(crate) extern "C" fn print_stats_wrapper() {
 
 pub fn init() {
     println!("SVF Runtime Initialized");
+    region::init();
+    shadow::heap_backend();
     // register atexit handler
     REGISTER_ATEXIT.call_once(|| {
         unsafe { atexit(print_stats_wrapper); }
     });
 }
 
+/// emit the end-of-run report, see `report` for the output controls.
 pub fn print_stats() {
-    alias::print_alias_stats();
-    heap::print_heap_stats();
-    unsafe_heap_access::print_unsafe_heap_stats();
+    report::emit();
 }
 
 #[no_mangle]
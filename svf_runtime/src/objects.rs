@@ -0,0 +1,204 @@
+//! stack and global object registration for svf runtime.
+//! contains __svf_report_stack_obj, __svf_release_stack_obj, __svf_report_global
+//! and the LIVE_OBJECTS map, the non-heap counterpart of `heap::LIVE_HEAP`.
+//!
+//! svf points-to sets name stack allocas and globals as well as heap sites. with
+//! these objects registered under their svf node id (`obj_id`), the unsafe access
+//! checker can resolve a pointer to *any* abstract object and validate the
+//! prediction against it, not only heap accesses.
+//!
+//! ## stale stack objects
+//! a frame left without `__svf_release_stack_obj` (longjmp, unwinding) leaves its
+//! objects behind. registering an object drops every registered object that
+//! overlaps it, so stale entries disappear once their memory is reused by a
+//! later frame. like `HeapIndex::neighbours`, the sweep only sees objects that
+//! start within a region above the new object's base.
+//!
+//! ## concurrency
+//! every frame entry and exit registers and releases objects, so `LIVE_OBJECTS`
+//! is a sharded `HeapIndex` rather than one locked map: threads' stacks lie in
+//! different regions and mostly take different shard locks.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::heap_index::HeapIndex;
+use crate::{IN_CHECKER, ReentrancyGuard};
+
+/// kind of abstract object a runtime address resolves to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ObjectKind {
+    Heap = 0,
+    Stack = 1,
+    Global = 2,
+}
+
+impl ObjectKind {
+    pub const ALL: [ObjectKind; 3] = [ObjectKind::Heap, ObjectKind::Stack, ObjectKind::Global];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectKind::Heap => "heap",
+            ObjectKind::Stack => "stack",
+            ObjectKind::Global => "global",
+        }
+    }
+}
+
+/// a registered stack or global object.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RegisteredObject {
+    pub obj_id: u64,
+    pub kind: ObjectKind,
+}
+
+/// registrations so far, indexed by `ObjectKind` (the heap slot stays 0).
+static REGISTERED: [AtomicU64; 3] = [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)];
+
+lazy_static! {
+    /// map address -> (size, obj_id, kind as u64) of registered stack and global
+    /// objects. zero-sized objects are stored with size 1.
+    static ref LIVE_OBJECTS: HeapIndex = HeapIndex::new();
+}
+
+/// the registered stack or global object containing `ptr`, if any.
+pub(crate) fn get_registered_object(ptr: *const u8) -> Option<RegisteredObject> {
+    let (_, (_, obj_id, kind)) = LIVE_OBJECTS.containing(ptr as usize)?;
+    Some(RegisteredObject { obj_id, kind: ObjectKind::ALL[kind as usize] })
+}
+
+/// number of objects registered so far, indexed by `ObjectKind`.
+pub fn registered_counts() -> [u64; 3] {
+    REGISTERED.each_ref().map(|c| c.load(Ordering::Relaxed))
+}
+
+fn register(addr: usize, size: usize, obj_id: u64, kind: ObjectKind) {
+    // a zero-sized object still owns its address, so lookups of it resolve.
+    let size = size.max(1);
+    let end = addr.saturating_add(size);
+    // registered objects never overlap each other: at most one reaches `addr` from
+    // below, the others start inside the new object.
+    if let Some((base, _)) = LIVE_OBJECTS.containing(addr) {
+        LIVE_OBJECTS.remove(base);
+    }
+    while let (_, Some((base, _))) = LIVE_OBJECTS.neighbours(addr) {
+        if base >= end {
+            break;
+        }
+        LIVE_OBJECTS.remove(base);
+    }
+    LIVE_OBJECTS.insert(addr, (size, obj_id, kind as u64));
+    REGISTERED[kind as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// runtime hook: records the stack object `[ptr, ptr + size)` (svf node `obj_id`)
+/// when its frame is entered.
+///
+/// # Safety
+/// `ptr` is only used as an address key and never dereferenced.
+#[no_mangle]
+pub unsafe extern "C" fn __svf_report_stack_obj(ptr: *const u8, size: usize, obj_id: u64) {
+    if ptr.is_null() { return; }
+    if IN_CHECKER.with(|c| c.get()) { return; }
+    IN_CHECKER.with(|c| c.set(true));
+    let _guard = ReentrancyGuard;
+
+    register(ptr as usize, size, obj_id, ObjectKind::Stack);
+}
+
+/// runtime hook: removes the stack object starting at `ptr` when its frame is left.
+/// unknown addresses are ignored.
+///
+/// # Safety
+/// `ptr` is only used as an address key and never dereferenced.
+#[no_mangle]
+pub unsafe extern "C" fn __svf_release_stack_obj(ptr: *const u8) {
+    if ptr.is_null() { return; }
+    if IN_CHECKER.with(|c| c.get()) { return; }
+    IN_CHECKER.with(|c| c.set(true));
+    let _guard = ReentrancyGuard;
+
+    // only the releasing frame's own thread registers objects at its addresses.
+    if let Some((_, _, kind)) = LIVE_OBJECTS.get(ptr as usize) {
+        if kind == ObjectKind::Stack as u64 {
+            LIVE_OBJECTS.remove(ptr as usize);
+        }
+    }
+}
+
+/// runtime hook: records the global object `[ptr, ptr + size)` (svf node `obj_id`),
+/// normally from a module constructor. globals are never released.
+///
+/// # Safety
+/// `ptr` is only used as an address key and never dereferenced.
+#[no_mangle]
+pub unsafe extern "C" fn __svf_report_global(ptr: *const u8, size: usize, obj_id: u64) {
+    if ptr.is_null() { return; }
+    if IN_CHECKER.with(|c| c.get()) { return; }
+    IN_CHECKER.with(|c| c.set(true));
+    let _guard = ReentrancyGuard;
+
+    register(ptr as usize, size, obj_id, ObjectKind::Global);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_registered_object, ObjectKind, __svf_release_stack_obj, __svf_report_global, __svf_report_stack_obj};
+
+    fn resolve(addr: usize) -> Option<(u64, ObjectKind)> {
+        get_registered_object(addr as *const u8).map(|obj| (obj.obj_id, obj.kind))
+    }
+
+    fn stack(addr: usize, size: usize, obj_id: u64) {
+        unsafe { __svf_report_stack_obj(addr as *const u8, size, obj_id) };
+    }
+
+    #[test]
+    fn overlapping_stale_entries_are_dropped() {
+        let a = 0x5000_0000;
+        stack(a, 64, 1);
+        // reaches into `a` from above.
+        stack(a + 32, 64, 2);
+        assert_eq!(resolve(a), None);
+        assert_eq!(resolve(a + 40), Some((2, ObjectKind::Stack)));
+
+        // a frame covering two stale objects and part of a third.
+        stack(a + 0x1000, 16, 3);
+        stack(a + 0x1010, 16, 4);
+        stack(a + 0x1020, 16, 5);
+        stack(a + 0xff8, 0x30, 6);
+        assert_eq!(resolve(a + 0x1000), Some((6, ObjectKind::Stack)));
+        assert_eq!(resolve(a + 0x1018), Some((6, ObjectKind::Stack)));
+        assert_eq!(resolve(a + 0x1024), Some((6, ObjectKind::Stack)));
+        // the rest of the partly covered object is gone too.
+        assert_eq!(resolve(a + 0x102f), None);
+        assert_eq!(resolve(a + 40), Some((2, ObjectKind::Stack)));
+
+        // across a 1 MiB region boundary, in both directions.
+        let edge = 0x5010_0000;
+        stack(edge - 16, 32, 7);
+        assert_eq!(resolve(edge + 8), Some((7, ObjectKind::Stack)));
+        stack(edge + 8, 8, 8);
+        assert_eq!(resolve(edge - 16), None);
+        // starts below the boundary, drops the object starting above it.
+        stack(edge - 4, 16, 9);
+        assert_eq!(resolve(edge + 8), Some((9, ObjectKind::Stack)));
+        assert_eq!(resolve(edge + 12), None);
+    }
+
+    #[test]
+    fn zero_sized_objects_and_release() {
+        let a = 0x5100_0000;
+        stack(a, 0, 1);
+        assert_eq!(resolve(a), Some((1, ObjectKind::Stack)));
+        assert_eq!(resolve(a + 1), None);
+        unsafe {
+            __svf_report_global((a + 0x100) as *const u8, 8, 2);
+            __svf_release_stack_obj(a as *const u8);
+            // globals are never released.
+            __svf_release_stack_obj((a + 0x100) as *const u8);
+        }
+        assert_eq!(resolve(a), None);
+        assert_eq!(resolve(a + 0x104), Some((2, ObjectKind::Global)));
+    }
+}
@@ -0,0 +1,265 @@
+//! LD_PRELOAD interposers for the libc allocator (cargo feature `preload`).
+//!
+//! built with `--features preload`, the cdylib exports `malloc`, `calloc`,
+//! `realloc`, `free` and `posix_memalign`. preloading it records every heap
+//! object of the process in `LIVE_HEAP` under `UNINSTRUMENTED_SITE_ID`, so c
+//! libraries linked into the program, which the instrumentation never sees,
+//! contribute to the heap ground truth:
+//!
+//! ```text
+//! LD_PRELOAD=target/release/libsvf_runtime.so ./program
+//! ```
+//!
+//! ## instrumented objects
+//! instrumented call sites still report their objects through `__svf_report_*`:
+//! - a `__svf_report_alloc` after the interposed `malloc` re-attributes the
+//!   object to the instrumented site (see "re-reported objects" in `heap`).
+//! - `free` and `realloc` only update objects recorded under
+//!   `UNINSTRUMENTED_SITE_ID` or not recorded at all. the others are left to
+//!   `__svf_report_dealloc` / `__svf_report_realloc`, and freeing an unknown
+//!   pointer is never reported as a bad free here.
+//!
+//! ## bootstrap
+//! the real functions are looked up with `dlsym(RTLD_NEXT)`, which may allocate
+//! itself. only the allocations `dlsym` makes on the resolving thread are served
+//! from a small static buffer, and never freed; other threads wait for the
+//! lookup to finish.
+//!
+//! ## realloc
+//! like `free`, `realloc` takes the object out of `LIVE_HEAP` before the real
+//! call, which may free the block, and puts it back if the call fails.
+
+use std::cell::{Cell, UnsafeCell};
+use std::ffi::c_void;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::heap::{self, UNINSTRUMENTED_SITE_ID};
+use crate::hook;
+
+const RTLD_NEXT: *mut c_void = -1isize as *mut c_void;
+const ENOMEM: i32 = 12;
+
+extern "C" {
+    fn dlsym(handle: *mut c_void, symbol: *const u8) -> *mut c_void;
+}
+
+type MallocFn = unsafe extern "C" fn(usize) -> *mut c_void;
+type CallocFn = unsafe extern "C" fn(usize, usize) -> *mut c_void;
+type ReallocFn = unsafe extern "C" fn(*mut c_void, usize) -> *mut c_void;
+type FreeFn = unsafe extern "C" fn(*mut c_void);
+type PosixMemalignFn = unsafe extern "C" fn(*mut *mut c_void, usize, usize) -> i32;
+
+/// interposed symbols, in `REAL` order.
+const SYMBOLS: [&[u8]; 5] = [b"malloc\0", b"calloc\0", b"realloc\0", b"free\0", b"posix_memalign\0"];
+const MALLOC: usize = 0;
+const CALLOC: usize = 1;
+const REALLOC: usize = 2;
+const FREE: usize = 3;
+const POSIX_MEMALIGN: usize = 4;
+
+/// addresses of the real functions, 0 until looked up.
+static REAL: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+/// claimed by the thread running the lookup.
+static RESOLVING: AtomicBool = AtomicBool::new(false);
+static RESOLVED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// set while this thread runs the lookup, so `dlsym`'s own allocations
+    /// are served from the bootstrap buffer.
+    static RESOLVER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// the address of the real `SYMBOLS[index]`. None only inside the lookup itself,
+/// or if the symbol does not exist.
+fn real(index: usize) -> Option<usize> {
+    let f = REAL[index].load(Ordering::Acquire);
+    if f != 0 {
+        return Some(f);
+    }
+    if !RESOLVED.load(Ordering::Acquire) {
+        if RESOLVER.with(Cell::get) {
+            return None;
+        }
+        if RESOLVING.swap(true, Ordering::AcqRel) {
+            // failing here would leak frees and fail reallocs; the lookup is short.
+            while !RESOLVED.load(Ordering::Acquire) {
+                std::thread::yield_now();
+            }
+        } else {
+            RESOLVER.with(|r| r.set(true));
+            for (slot, name) in REAL.iter().zip(SYMBOLS) {
+                slot.store(unsafe { dlsym(RTLD_NEXT, name.as_ptr()) } as usize, Ordering::Release);
+            }
+            RESOLVER.with(|r| r.set(false));
+            RESOLVED.store(true, Ordering::Release);
+        }
+    }
+    Some(REAL[index].load(Ordering::Acquire)).filter(|&f| f != 0)
+}
+
+const BOOTSTRAP_SIZE: usize = 64 << 10;
+/// bytes before each bootstrap block; the last 8 hold its size.
+const HEADER: usize = 16;
+
+#[repr(C, align(4096))]
+struct Bootstrap(UnsafeCell<[u8; BOOTSTRAP_SIZE]>);
+
+// blocks are handed out once, by bumping `BOOTSTRAP_USED`.
+unsafe impl Sync for Bootstrap {}
+
+static BOOTSTRAP: Bootstrap = Bootstrap(UnsafeCell::new([0; BOOTSTRAP_SIZE]));
+static BOOTSTRAP_USED: AtomicUsize = AtomicUsize::new(0);
+
+/// a zeroed block from the bootstrap buffer, or null once it is exhausted.
+fn bootstrap_alloc(size: usize, align: usize) -> *mut c_void {
+    let base = BOOTSTRAP.0.get() as *mut u8;
+    let mut used = BOOTSTRAP_USED.load(Ordering::Relaxed);
+    loop {
+        let start = (used + HEADER).next_multiple_of(align.max(HEADER));
+        let Some(end) = start.checked_add(size).filter(|&end| end <= BOOTSTRAP_SIZE) else {
+            return std::ptr::null_mut();
+        };
+        match BOOTSTRAP_USED.compare_exchange_weak(used, end, Ordering::AcqRel, Ordering::Relaxed) {
+            Ok(_) => unsafe {
+                let block = base.add(start);
+                (block.sub(8) as *mut usize).write(size);
+                return block as *mut c_void;
+            },
+            Err(current) => used = current,
+        }
+    }
+}
+
+fn in_bootstrap(ptr: *mut c_void) -> bool {
+    let base = BOOTSTRAP.0.get() as usize;
+    (base..base + BOOTSTRAP_SIZE).contains(&(ptr as usize))
+}
+
+/// size of the bootstrap block at `ptr`.
+unsafe fn bootstrap_size(ptr: *mut c_void) -> usize {
+    (ptr as *const u8).sub(8).cast::<usize>().read()
+}
+
+/// whether the interposers keep the object starting at `addr` up to date.
+fn owned(addr: usize) -> bool {
+    heap::live_site(addr).is_none_or(|site_id| site_id == UNINSTRUMENTED_SITE_ID)
+}
+
+fn record_alloc(ptr: *mut c_void, size: usize) {
+    if !ptr.is_null() {
+        hook(|| heap::record_alloc(ptr as usize, size, UNINSTRUMENTED_SITE_ID));
+    }
+}
+
+/// interposed `malloc`.
+///
+/// # Safety
+/// same contract as libc `malloc`.
+#[no_mangle]
+pub unsafe extern "C" fn malloc(size: usize) -> *mut c_void {
+    let Some(f) = real(MALLOC) else { return bootstrap_alloc(size, HEADER) };
+    let ptr = std::mem::transmute::<usize, MallocFn>(f)(size);
+    record_alloc(ptr, size);
+    ptr
+}
+
+/// interposed `calloc`.
+///
+/// # Safety
+/// same contract as libc `calloc`.
+#[no_mangle]
+pub unsafe extern "C" fn calloc(count: usize, size: usize) -> *mut c_void {
+    let Some(f) = real(CALLOC) else {
+        return count.checked_mul(size).map_or(std::ptr::null_mut(), |total| bootstrap_alloc(total, HEADER));
+    };
+    let ptr = std::mem::transmute::<usize, CallocFn>(f)(count, size);
+    record_alloc(ptr, count.saturating_mul(size));
+    ptr
+}
+
+/// interposed `realloc`.
+///
+/// # Safety
+/// same contract as libc `realloc`.
+#[no_mangle]
+pub unsafe extern "C" fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void {
+    if ptr.is_null() {
+        return malloc(size);
+    }
+    if in_bootstrap(ptr) {
+        let moved = malloc(size);
+        if !moved.is_null() {
+            std::ptr::copy_nonoverlapping(ptr as *const u8, moved as *mut u8, size.min(bootstrap_size(ptr)));
+        }
+        return moved;
+    }
+    let Some(f) = real(REALLOC) else { return std::ptr::null_mut() };
+    if size == 0 {
+        // glibc frees the block on a zero-sized realloc: record first, as in `free`.
+        hook(|| {
+            if owned(ptr as usize) {
+                heap::record_dealloc(ptr as usize);
+            }
+        });
+        let new_ptr = std::mem::transmute::<usize, ReallocFn>(f)(ptr, size);
+        record_alloc(new_ptr, size);
+        return new_ptr;
+    }
+    // take the object out before the block can be freed and handed to another
+    // thread; `Some(None)` is an owned pointer that was never recorded.
+    let mut taken = None;
+    hook(|| {
+        if owned(ptr as usize) {
+            taken = Some(heap::begin_realloc(ptr as usize));
+        }
+    });
+    let new_ptr = std::mem::transmute::<usize, ReallocFn>(f)(ptr, size);
+    hook(|| match taken {
+        // site 0 keeps `UNINSTRUMENTED_SITE_ID`; a failed realloc puts the object back.
+        Some(Some(entry)) => heap::end_realloc(ptr as usize, entry, new_ptr as usize, size, 0),
+        Some(None) if !new_ptr.is_null() => heap::record_alloc(new_ptr as usize, size, UNINSTRUMENTED_SITE_ID),
+        _ => {}
+    });
+    new_ptr
+}
+
+/// interposed `free`.
+///
+/// # Safety
+/// same contract as libc `free`.
+#[no_mangle]
+pub unsafe extern "C" fn free(ptr: *mut c_void) {
+    if ptr.is_null() || in_bootstrap(ptr) {
+        return;
+    }
+    // record first: once freed, another thread may get the address back.
+    hook(|| {
+        if owned(ptr as usize) {
+            heap::record_dealloc(ptr as usize);
+        }
+    });
+    if let Some(f) = real(FREE) {
+        std::mem::transmute::<usize, FreeFn>(f)(ptr);
+    }
+}
+
+/// interposed `posix_memalign`.
+///
+/// # Safety
+/// same contract as libc `posix_memalign`.
+#[no_mangle]
+pub unsafe extern "C" fn posix_memalign(memptr: *mut *mut c_void, align: usize, size: usize) -> i32 {
+    let Some(f) = real(POSIX_MEMALIGN) else {
+        let ptr = bootstrap_alloc(size, align);
+        if ptr.is_null() {
+            return ENOMEM;
+        }
+        *memptr = ptr;
+        return 0;
+    };
+    let result = std::mem::transmute::<usize, PosixMemalignFn>(f)(memptr, align, size);
+    if result == 0 {
+        record_alloc(*memptr, size);
+    }
+    result
+}
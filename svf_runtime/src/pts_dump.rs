@@ -0,0 +1,124 @@
+//! static svf points-to dumps (`svf_pts_to_<module>_N.json`) as seen by the runtime.
+//!
+//! the runtime only needs the allocation site metadata (`allocation_sites` and
+//! `abstract_heap_objects`) to annotate its reports with `alloc_fn` and
+//! `source_loc`. runtime site ids are svf node ids, so sites are keyed by `node_id`.
+//!
+//! dumps are listed in `SVF_PTS_DUMP` as `:`-separated paths; a directory entry
+//! loads every `svf_pts_to_*.json` file in it. they are loaded once, on first use
+//! (normally while building the exit report). when several dumps describe the
+//! same node id, the first one loaded wins.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::json::{self, JsonObject, JsonValue};
+
+/// env var listing the pts dump files or directories to load.
+pub const PTS_DUMP_ENV: &str = "SVF_PTS_DUMP";
+
+#[derive(Clone, Debug)]
+pub struct SourceLoc {
+    pub file: String,
+    pub line: u64,
+    pub col: u64,
+}
+
+/// static metadata of one allocation site.
+#[derive(Clone, Debug)]
+pub struct SiteInfo {
+    pub alloc_fn: String,
+    pub source_loc: Option<SourceLoc>,
+    /// `summary.module` of the dump the site came from.
+    pub module: String,
+}
+
+impl SiteInfo {
+    pub(crate) fn write_json(&self, o: &mut JsonObject) {
+        o.str("alloc_fn", &self.alloc_fn).str("module", &self.module);
+        if let Some(loc) = &self.source_loc {
+            o.object("source_loc", |l| {
+                l.str("file", &loc.file).u64("line", loc.line).u64("col", loc.col);
+            });
+        }
+    }
+
+    /// `alloc_fn at file:line:col`, for the text reports.
+    pub fn describe(&self) -> String {
+        match &self.source_loc {
+            Some(loc) => format!("{} at {}:{}:{}", self.alloc_fn, loc.file, loc.line, loc.col),
+            None => self.alloc_fn.clone(),
+        }
+    }
+}
+
+static SITES: OnceLock<HashMap<u64, SiteInfo>> = OnceLock::new();
+
+/// static metadata for a runtime site id, if a loaded dump describes it.
+pub fn site_info(site_id: u64) -> Option<&'static SiteInfo> {
+    SITES.get_or_init(load_from_env).get(&site_id)
+}
+
+fn load_from_env() -> HashMap<u64, SiteInfo> {
+    let mut sites = HashMap::new();
+    let Some(paths) = std::env::var_os(PTS_DUMP_ENV) else { return sites };
+    for path in std::env::split_paths(&paths) {
+        for file in dump_files(&path) {
+            if let Err(e) = load_file(&file, &mut sites) {
+                eprintln!("[svf_runtime] skipping pts dump {}: {}", file.display(), e);
+            }
+        }
+    }
+    sites
+}
+
+fn dump_files(path: &Path) -> Vec<PathBuf> {
+    if !path.is_dir() {
+        return vec![path.to_path_buf()];
+    }
+    let mut files: Vec<PathBuf> = match std::fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| {
+                let name = p.file_name().and_then(|n| n.to_str()).unwrap_or("");
+                name.starts_with("svf_pts_to_") && name.ends_with(".json")
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+fn load_file(path: &Path, sites: &mut HashMap<u64, SiteInfo>) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let dump = json::parse(&text)?;
+    let module = dump
+        .get("summary")
+        .and_then(|s| s.get("module"))
+        .and_then(JsonValue::as_str)
+        .unwrap_or("")
+        .to_string();
+
+    for section in ["allocation_sites", "abstract_heap_objects"] {
+        let Some(entries) = dump.get(section).and_then(JsonValue::as_array) else { continue };
+        for entry in entries {
+            let Some(node_id) = entry.get("node_id").and_then(JsonValue::as_u64) else { continue };
+            sites.entry(node_id).or_insert_with(|| SiteInfo {
+                alloc_fn: entry.get("alloc_fn").and_then(JsonValue::as_str).unwrap_or("").to_string(),
+                source_loc: entry.get("source_loc").and_then(parse_source_loc),
+                module: module.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn parse_source_loc(v: &JsonValue) -> Option<SourceLoc> {
+    Some(SourceLoc {
+        file: v.get("file")?.as_str()?.to_string(),
+        line: v.get("line").and_then(JsonValue::as_u64).unwrap_or(0),
+        col: v.get("col").and_then(JsonValue::as_u64).unwrap_or(0),
+    })
+}
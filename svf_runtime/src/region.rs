@@ -0,0 +1,395 @@
+//! memory region classifier for pointers that miss `LIVE_HEAP`.
+//!
+//! the unsafe access checker uses it to break false positives and true
+//! negatives down by where the pointer actually pointed:
+//! - `tls`: the current thread's static tls blocks, from their lowest module
+//!   block (`dl_iterate_phdr`) up to the thread pointer (x86_64 glibc, tls
+//!   variant ii; other targets never classify as tls).
+//! - `stack`: the main stack mapping, extended down to its `RLIMIT_STACK`, and
+//!   the stack of any other thread that has classified an address, from
+//!   `pthread_getattr_np` (64-bit glibc).
+//! - `global`: the executable's non-code mappings (`.rodata`, `.data`) and the
+//!   anonymous mapping right after them (`.bss`).
+//! - `mmap`: any other anonymous mapping.
+//! - `unknown`: everything else, e.g. shared library data or untracked `[heap]`.
+//!
+//! `/proc/self/maps` is read once, into a snapshot: by `init`, or else by the
+//! first classification, with hooks disabled. an address no snapshot mapping
+//! covers, e.g. one mmapped later, classifies as `unknown`. the main thread
+//! takes its stack from the snapshot, since `pthread_getattr_np` reads the maps
+//! for it; other threads compute their bounds once, on their first
+//! classification, and publish their stack in `THREAD_STACKS` until they exit.
+
+use std::cell::Cell;
+use std::io::{self, Write};
+use std::sync::{Once, RwLock};
+
+use crate::json::JsonObject;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Region {
+    Stack = 0,
+    Global = 1,
+    Tls = 2,
+    Mmap = 3,
+    Unknown = 4,
+}
+
+impl Region {
+    pub const ALL: [Region; 5] = [Region::Stack, Region::Global, Region::Tls, Region::Mmap, Region::Unknown];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Region::Stack => "stack",
+            Region::Global => "global",
+            Region::Tls => "tls",
+            Region::Mmap => "mmap",
+            Region::Unknown => "unknown",
+        }
+    }
+}
+
+/// write per-region counts, indexed in `Region::ALL` order, as json fields.
+pub(crate) fn write_counts_json(counts: &[usize; 5], o: &mut JsonObject) {
+    for region in Region::ALL {
+        o.u64(region.as_str(), counts[region as usize] as u64);
+    }
+}
+
+/// `stack N, global N, ...`, for the text reports.
+pub(crate) fn write_counts_text(counts: &[usize; 5], out: &mut dyn Write) -> io::Result<()> {
+    for (i, region) in Region::ALL.iter().enumerate() {
+        let sep = if i > 0 { ", " } else { "" };
+        write!(out, "{}{} {}", sep, region.as_str(), counts[*region as usize])?;
+    }
+    writeln!(out)
+}
+
+/// opaque `pthread_attr_t`, large enough for glibc on 64-bit targets (56 bytes
+/// on x86_64, 64 on aarch64).
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+#[repr(C, align(8))]
+struct PthreadAttr([u8; 64]);
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+extern "C" {
+    fn pthread_self() -> usize;
+    fn pthread_getattr_np(thread: usize, attr: *mut PthreadAttr) -> i32;
+    fn pthread_attr_getstack(attr: *const PthreadAttr, addr: *mut *mut u8, size: *mut usize) -> i32;
+    fn pthread_attr_destroy(attr: *mut PthreadAttr) -> i32;
+    fn getrlimit(resource: i32, rlim: *mut [u64; 2]) -> i32;
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+const RLIMIT_STACK: i32 = 3;
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+extern "C" {
+    fn dl_iterate_phdr(
+        callback: unsafe extern "C" fn(info: *const DlPhdrInfo, size: usize, data: *mut u8) -> i32,
+        data: *mut u8,
+    ) -> i32;
+}
+
+/// leading fields of glibc's `struct dl_phdr_info`.
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+#[repr(C)]
+struct DlPhdrInfo {
+    addr: usize,
+    name: *const u8,
+    phdr: *const u8,
+    phnum: u16,
+    adds: u64,
+    subs: u64,
+    tls_modid: usize,
+    tls_data: *const u8,
+}
+
+// `dlpi_tls_data` ends glibc's x86_64 layout at byte 64.
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+const _: () = assert!(std::mem::size_of::<DlPhdrInfo>() == 64);
+
+/// `dl_iterate_phdr` callback: lower `*data` to the current thread's tls block
+/// of this module, if it lies below the thread pointer (i.e. is static tls).
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+unsafe extern "C" fn lowest_tls_block(info: *const DlPhdrInfo, size: usize, data: *mut u8) -> i32 {
+    let lowest = &mut *(data as *mut (usize, usize));
+    if size >= std::mem::size_of::<DlPhdrInfo>() {
+        let block = (*info).tls_data as usize;
+        if block != 0 && block < lowest.1 {
+            lowest.0 = lowest.0.min(block);
+        }
+    }
+    0
+}
+
+/// address ranges private to the current thread.
+#[derive(Clone, Copy)]
+struct ThreadBounds {
+    stack: (usize, usize),
+    tls: (usize, usize),
+}
+
+/// a thread's entry in `THREAD_STACKS`, removed when the thread exits.
+struct PublishedStack((usize, usize));
+
+impl Drop for PublishedStack {
+    fn drop(&mut self) {
+        crate::without_hooks(|| {
+            if let Ok(mut stacks) = THREAD_STACKS.write() {
+                if let Some(i) = stacks.iter().position(|s| *s == self.0) {
+                    stacks.swap_remove(i);
+                }
+            }
+        });
+    }
+}
+
+thread_local! {
+    static THREAD_BOUNDS: Cell<Option<ThreadBounds>> = const { Cell::new(None) };
+    static PUBLISHED_STACK: PublishedStack = publish_stack();
+}
+
+lazy_static! {
+    /// stacks of the live threads that computed their bounds.
+    static ref THREAD_STACKS: RwLock<Vec<(usize, usize)>> = RwLock::new(Vec::new());
+}
+
+fn publish_stack() -> PublishedStack {
+    let stack = thread_bounds().stack;
+    crate::without_hooks(|| {
+        if let Ok(mut stacks) = THREAD_STACKS.write() {
+            stacks.push(stack);
+        }
+    });
+    PublishedStack(stack)
+}
+
+fn thread_bounds() -> ThreadBounds {
+    if let Some(bounds) = THREAD_BOUNDS.with(Cell::get) {
+        return bounds;
+    }
+    // the main thread runs on the snapshot's stack mapping.
+    let here = 0u8;
+    let stack = match lookup_maps(&here as *const u8 as usize) {
+        Some(m) if m.region == Region::Stack => (m.start, m.end),
+        _ => current_stack(),
+    };
+    let bounds = ThreadBounds { stack, tls: current_static_tls() };
+    THREAD_BOUNDS.with(|b| b.set(Some(bounds)));
+    // once per thread: the first access creates the entry.
+    let _ = PUBLISHED_STACK.try_with(|_| ());
+    bounds
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+fn current_stack() -> (usize, usize) {
+    let mut stack = (0, 0);
+    unsafe {
+        let mut attr = PthreadAttr([0; 64]);
+        if pthread_getattr_np(pthread_self(), &mut attr) == 0 {
+            let mut addr = std::ptr::null_mut();
+            let mut size = 0;
+            if pthread_attr_getstack(&attr, &mut addr, &mut size) == 0 {
+                stack = (addr as usize, addr as usize + size);
+            }
+            pthread_attr_destroy(&mut attr);
+        }
+    }
+    stack
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64")))]
+fn current_stack() -> (usize, usize) {
+    (0, 0)
+}
+
+#[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu"))]
+fn current_static_tls() -> (usize, usize) {
+    // the static tls blocks end at the thread pointer, which is `pthread_self()`.
+    let thread = unsafe { pthread_self() };
+    let mut lowest = (thread, thread);
+    unsafe { dl_iterate_phdr(lowest_tls_block, &mut lowest as *mut (usize, usize) as *mut u8) };
+    lowest
+}
+
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu")))]
+fn current_static_tls() -> (usize, usize) {
+    (0, 0)
+}
+
+/// whether `addr` is on the stack of another live thread.
+fn on_thread_stack(addr: usize) -> bool {
+    // a thread is registering or exiting; it is not worth waiting for.
+    let Ok(stacks) = THREAD_STACKS.try_read() else { return false };
+    stacks.iter().any(|&(start, end)| start <= addr && addr < end)
+}
+
+#[derive(Clone, Copy)]
+struct Mapping {
+    start: usize,
+    end: usize,
+    region: Region,
+}
+
+lazy_static! {
+    /// `/proc/self/maps` snapshot, sorted by start address.
+    static ref MAPS: RwLock<Vec<Mapping>> = RwLock::new(Vec::new());
+}
+
+static SNAPSHOT: Once = Once::new();
+
+/// capture the maps snapshot, unless it already was.
+pub(crate) fn init() {
+    SNAPSHOT.call_once(capture_maps);
+}
+
+/// (re)read `/proc/self/maps` into the snapshot used by `classify`.
+pub(crate) fn capture_maps() {
+    let mappings = crate::without_hooks(read_maps);
+    if let Ok(mut maps) = MAPS.write() {
+        *maps = mappings;
+    }
+}
+
+fn read_maps() -> Vec<Mapping> {
+    let exe = std::fs::read_link("/proc/self/exe").ok();
+    let text = std::fs::read_to_string("/proc/self/maps").unwrap_or_default();
+    let mut mappings: Vec<Mapping> = Vec::new();
+    let mut after_exe = false;
+    for line in text.lines() {
+        // start-end perms offset dev inode [path]
+        let mut fields = line.split_whitespace();
+        let (Some(range), Some(perms)) = (fields.next(), fields.next()) else { continue };
+        let path = fields.nth(3).unwrap_or("");
+        let Some((start, end)) = range.split_once('-') else { continue };
+        let (Ok(start), Ok(end)) = (usize::from_str_radix(start, 16), usize::from_str_radix(end, 16)) else { continue };
+
+        let is_exe = exe.as_deref().is_some_and(|exe| !path.is_empty() && exe.as_os_str() == path);
+        let region = if is_exe {
+            if perms.contains('x') { Region::Unknown } else { Region::Global }
+        } else if path.is_empty() {
+            // the bss directly follows the executable's last file mapping.
+            let is_bss = after_exe && mappings.last().is_some_and(|m| m.end == start);
+            if is_bss { Region::Global } else { Region::Mmap }
+        } else if path == "[stack]" {
+            // the kernel grows the main stack down to its rlimit.
+            let floor = mappings.last().map_or(0, |m| m.end);
+            mappings.push(Mapping { start: end.saturating_sub(stack_limit()).max(floor).min(start), end, region: Region::Stack });
+            after_exe = false;
+            continue;
+        } else {
+            Region::Unknown
+        };
+        after_exe = is_exe;
+        mappings.push(Mapping { start, end, region });
+    }
+    // the kernel lists mappings in address order.
+    mappings
+}
+
+/// the soft `RLIMIT_STACK`, `usize::MAX` when unlimited.
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+fn stack_limit() -> usize {
+    let mut limit = [0u64; 2];
+    if unsafe { getrlimit(RLIMIT_STACK, &mut limit) } != 0 {
+        return 0;
+    }
+    usize::try_from(limit[0]).unwrap_or(usize::MAX)
+}
+
+#[cfg(not(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64")))]
+fn stack_limit() -> usize {
+    0
+}
+
+/// the snapshot mapping covering `addr`.
+fn lookup_maps(addr: usize) -> Option<Mapping> {
+    let maps = MAPS.read().ok()?;
+    let idx = maps.partition_point(|m| m.start <= addr);
+    let m = maps.get(idx.checked_sub(1)?)?;
+    (addr < m.end).then_some(*m)
+}
+
+/// classify a pointer that is not in a live heap object.
+pub(crate) fn classify(ptr: *const u8) -> Region {
+    init();
+    let addr = ptr as usize;
+    let bounds = thread_bounds();
+    // tls first: glibc places it at the top of a thread's stack block.
+    if bounds.tls.0 <= addr && addr < bounds.tls.1 {
+        return Region::Tls;
+    }
+    if bounds.stack.0 <= addr && addr < bounds.stack.1 {
+        return Region::Stack;
+    }
+    if on_thread_stack(addr) {
+        return Region::Stack;
+    }
+    lookup_maps(addr).map_or(Region::Unknown, |m| m.region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{capture_maps, classify, init, stack_limit, Region, MAPS};
+    use std::cell::Cell;
+    use std::sync::mpsc;
+
+    static GLOBAL: [u64; 4] = [1, 2, 3, 4];
+
+    thread_local! {
+        static LOCAL: Cell<u64> = const { Cell::new(0) };
+    }
+
+    fn addr<T>(value: &T) -> *const u8 {
+        value as *const T as *const u8
+    }
+
+    #[test]
+    fn classifies_stack_global_tls_and_heap() {
+        // take the one-time snapshot first, so no later classification retakes it.
+        init();
+        // mapped before the snapshot; large enough for its own mapping.
+        let heap = vec![0u8; 4 << 20];
+        capture_maps();
+
+        let local = 0u64;
+        assert_eq!(classify(addr(&local)), Region::Stack);
+        assert_eq!(classify(addr(&GLOBAL)), Region::Global);
+        let tls = LOCAL.with(addr);
+        if cfg!(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu")) {
+            assert_eq!(classify(tls), Region::Tls);
+        }
+        assert_eq!(classify(heap.as_ptr()), Region::Mmap);
+        // mapped after the snapshot.
+        let later = vec![0u8; 64 << 20];
+        assert_eq!(classify(later.as_ptr()), Region::Unknown);
+    }
+
+    #[test]
+    fn main_stack_extends_to_its_rlimit() {
+        init();
+        let maps = MAPS.read().unwrap();
+        let Some(stack) = maps.iter().find(|m| m.region == Region::Stack) else { return };
+        // the kernel maps far less of it up front.
+        assert!(stack.end - stack.start >= stack_limit().min(1 << 20));
+    }
+
+    #[test]
+    fn other_threads_stacks_are_stack() {
+        let (tx, rx) = mpsc::channel();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        let worker = std::thread::spawn(move || {
+            let local = 0u64;
+            assert_eq!(classify(addr(&local)), Region::Stack);
+            tx.send(addr(&local) as usize).unwrap();
+            done_rx.recv().unwrap();
+        });
+        let theirs = rx.recv().unwrap() as *const u8;
+        assert_eq!(classify(theirs), Region::Stack);
+        done_tx.send(()).unwrap();
+        worker.join().unwrap();
+        assert_ne!(classify(theirs), Region::Stack);
+    }
+}
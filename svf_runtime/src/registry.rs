@@ -0,0 +1,96 @@
+//! registry of per-thread statistics for svf runtime.
+//!
+//! hooks update thread-local stats without contention, but thread-local
+//! destructors only run at thread exit: when the `atexit` report fires, the main
+//! thread and any still-running workers have not been dropped yet. each thread
+//! therefore registers its stats slot here, so a report can aggregate
+//! - the `retired` totals of every thread that already exited, plus
+//! - the current contents of every live thread's slot.
+//!
+//! a thread's slot is folded into `retired` and unregistered under the same lock
+//! when the thread exits, so no update is counted twice or lost.
+
+use std::sync::{Arc, Mutex};
+
+/// statistics that can be combined across threads.
+pub(crate) trait Mergeable: Default {
+    fn merge_from(&mut self, other: &Self);
+}
+
+struct Slots<T> {
+    live: Vec<Arc<Mutex<T>>>,
+    retired: T,
+}
+
+pub(crate) struct ThreadRegistry<T> {
+    slots: Mutex<Slots<T>>,
+}
+
+impl<T: Mergeable> ThreadRegistry<T> {
+    pub(crate) fn new() -> Self {
+        Self { slots: Mutex::new(Slots { live: Vec::new(), retired: T::default() }) }
+    }
+
+    fn register(&self) -> Arc<Mutex<T>> {
+        let slot = Arc::new(Mutex::new(T::default()));
+        if let Ok(mut slots) = self.slots.lock() {
+            slots.live.push(Arc::clone(&slot));
+        }
+        slot
+    }
+
+    fn retire(&self, slot: &Arc<Mutex<T>>) {
+        if let Ok(mut slots) = self.slots.lock() {
+            slots.live.retain(|s| !Arc::ptr_eq(s, slot));
+            if let Ok(local) = slot.lock() {
+                slots.retired.merge_from(&local);
+            }
+        }
+    }
+
+    /// run `f` on the totals of exited threads, for updates made after the
+    /// caller's own slot was retired (during thread-local teardown).
+    pub(crate) fn with_retired<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.slots.lock().ok().map(|mut slots| f(&mut slots.retired))
+    }
+
+    /// totals over exited threads and the current state of all live threads.
+    pub(crate) fn aggregate(&self) -> T {
+        let mut total = T::default();
+        if let Ok(slots) = self.slots.lock() {
+            total.merge_from(&slots.retired);
+            for slot in slots.live.iter() {
+                if let Ok(local) = slot.lock() {
+                    total.merge_from(&local);
+                }
+            }
+        }
+        total
+    }
+}
+
+/// a thread's handle to its registered slot, meant to be stored in a `thread_local!`.
+/// the slot is retired into the registry when the thread-local is destroyed.
+pub(crate) struct ThreadSlot<T: Mergeable + 'static> {
+    registry: &'static ThreadRegistry<T>,
+    slot: Arc<Mutex<T>>,
+}
+
+impl<T: Mergeable + 'static> ThreadSlot<T> {
+    pub(crate) fn new(registry: &'static ThreadRegistry<T>) -> Self {
+        Self { registry, slot: registry.register() }
+    }
+
+    /// run `f` on this thread's stats. the lock is only contended while a report
+    /// is being aggregated.
+    pub(crate) fn with<R>(&self, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        self.slot.lock().ok().map(|mut s| f(&mut s))
+    }
+}
+
+impl<T: Mergeable + 'static> Drop for ThreadSlot<T> {
+    fn drop(&mut self) {
+        // merging may allocate; keep our own hooks out of it.
+        crate::without_hooks(|| self.registry.retire(&self.slot));
+    }
+}
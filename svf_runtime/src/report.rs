@@ -0,0 +1,113 @@
+//! end-of-run report for svf runtime.
+//!
+//! all statistics are gathered into a single [`Report`] data model, which has two
+//! renderers:
+//! - json: a schema-versioned document written to the path in `SVF_REPORT_PATH`.
+//!   this is the format scripts should consume.
+//! - text: the human-readable tables printed to stdout. enabled by default,
+//!   disabled with `SVF_REPORT_TEXT=0`.
+//!
+//! allocation sites are annotated with `alloc_fn` / `source_loc` from the static
+//! dumps listed in `SVF_PTS_DUMP` (see `pts_dump`).
+//!
+//! bump [`SCHEMA_VERSION`] whenever a field is renamed or removed; adding fields
+//! does not require a bump.
+
+use std::fs::File;
+use std::io::{self, Write};
+
+use crate::alias::{self, AliasStats};
+use crate::heap::{self, SiteLeak, SiteStats};
+use crate::json;
+use crate::shadow::{self, HeapBackendStats};
+use crate::stacks::{self, AllocStack};
+use crate::unsafe_heap_access::{self, UnsafeHeapStats};
+
+/// version of the json report layout.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// env var naming the file the json report is written to. unset = no json report.
+pub const REPORT_PATH_ENV: &str = "SVF_REPORT_PATH";
+/// env var controlling the stdout text report. `0`, `false` or `off` disables it.
+pub const REPORT_TEXT_ENV: &str = "SVF_REPORT_TEXT";
+
+/// everything the runtime knows at the time of collection.
+#[derive(Clone, Debug)]
+pub struct Report {
+    pub alias: AliasStats,
+    pub unsafe_heap: UnsafeHeapStats,
+    /// per-site allocation statistics, sorted by site id.
+    pub sites: Vec<(u64, SiteStats)>,
+    /// objects never deallocated, grouped by site, largest first.
+    pub leaks: Vec<SiteLeak>,
+    /// invalid deallocations, in `BadFree::ALL` order.
+    pub bad_frees: [u64; 3],
+    pub heap_backend: HeapBackendStats,
+    /// sampled allocation stacks, largest first. empty unless `SVF_STACK_SAMPLE` is set.
+    pub stacks: Vec<AllocStack>,
+}
+
+impl Report {
+    /// collect a report. hooks are disabled meanwhile, so allocations made while
+    /// collecting never re-enter `LIVE_HEAP`.
+    pub fn collect() -> Self {
+        crate::without_hooks(|| Self {
+            alias: alias::alias_stats(),
+            unsafe_heap: unsafe_heap_access::unsafe_heap_stats(),
+            sites: heap::site_stats(),
+            leaks: heap::leak_summary(),
+            bad_frees: heap::bad_free_counts(),
+            heap_backend: shadow::heap_backend_stats(),
+            stacks: stacks::alloc_stacks(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        json::write_object(&mut out, |o| {
+            o.str("schema", "svf_runtime_report")
+                .u64("schema_version", SCHEMA_VERSION)
+                .object("alias", |a| self.alias.write_json(a))
+                .object("unsafe_heap", |u| self.unsafe_heap.write_json(u))
+                .array("sites", |a| heap::write_site_stats_json(&self.sites, a))
+                .array("leaks", |a| heap::write_leaks_json(&self.leaks, a))
+                .object("bad_frees", |b| heap::write_bad_frees_json(&self.bad_frees, b))
+                .object("heap_backend", |h| self.heap_backend.write_json(h))
+                .array("stacks", |a| stacks::write_stacks_json(&self.stacks, a));
+        });
+        out.push('\n');
+        out
+    }
+
+    pub fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        self.alias.write_text(out)?;
+        self.unsafe_heap.write_text(out)?;
+        heap::write_leaks_text(&self.leaks, out)?;
+        stacks::write_stacks_text(&self.stacks, out)?;
+        heap::write_bad_frees_text(&self.bad_frees, out)
+    }
+}
+
+fn text_enabled() -> bool {
+    match std::env::var(REPORT_TEXT_ENV) {
+        Ok(v) => !matches!(v.trim().to_ascii_lowercase().as_str(), "0" | "false" | "off"),
+        Err(_) => true,
+    }
+}
+
+/// collect a report and emit it through the renderers selected by the environment.
+pub fn emit() {
+    // rendering allocates too (and may load the pts dumps), keep hooks off throughout.
+    crate::without_hooks(|| {
+        let report = Report::collect();
+        if text_enabled() {
+            let _ = report.write_text(&mut io::stdout().lock());
+        }
+        if let Some(path) = std::env::var_os(REPORT_PATH_ENV) {
+            let written = File::create(&path).and_then(|mut f| f.write_all(report.to_json().as_bytes()));
+            if let Err(e) = written {
+                eprintln!("[svf_runtime] failed to write report to {}: {}", path.to_string_lossy(), e);
+            }
+        }
+    });
+}
@@ -0,0 +1,389 @@
+//! optional shadow-memory backend for heap membership lookups.
+//!
+//! every unsafe access asks `LIVE_HEAP` which object contains the pointer, a
+//! btree walk under a shard lock. with `SVF_HEAP_BACKEND=shadow` the runtime also
+//! keeps a shadow map from each 16-byte granule of application memory to the
+//! slot of the object covering it, so a lookup is a directory load, a granule
+//! load and a read of the slot. `LIVE_HEAP` stays the source of truth (frees,
+//! neighbours, leak summaries); the shadow only answers membership.
+//!
+//! ## layout
+//! - a static directory with one entry per 4 GiB of address space, pointing to a
+//!   leaf of one `u32` per granule. leaves are reserved with
+//!   `mmap(MAP_NORESERVE)` on first use, so only the shadow pages of memory the
+//!   heap actually touched get committed.
+//! - a slot table, reserved the same way, holding (base, size, site_id, ticket)
+//!   of up to `MAX_SLOTS` live objects. slot 0 marks an empty granule.
+//!
+//! ## fallback
+//! some lookups still go to `LIVE_HEAP`:
+//! - a granule shared by two objects (one ending and one starting inside it,
+//!   which needs an object that is not 16-byte aligned) is marked `CONFLICT`.
+//!   conflicts are never cleared, freeing either object leaves the granule marked.
+//! - objects larger than `MAX_SHADOWED_SIZE`, objects beyond the slot table and
+//!   objects whose leaf could not be reserved are not shadowed at all. while any
+//!   of those are live, every miss falls back.
+//!
+//! a lookup racing with the free of the object it hits may see the slot being
+//! reused. slots are written under the slot state lock and guarded by a
+//! sequence count, so a lookup either reads one object's fields or falls back;
+//! the slot is only returned when it contains the looked up address.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{fence, AtomicPtr, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+
+use crate::heap_index::{Entry, Object};
+use crate::json::JsonObject;
+
+/// env var selecting the heap lookup backend: `index` (default) or `shadow`.
+pub const HEAP_BACKEND_ENV: &str = "SVF_HEAP_BACKEND";
+
+/// structure answering heap membership lookups.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HeapBackend {
+    /// the sharded interval index alone, see `heap_index`.
+    Index,
+    /// granule shadow memory in front of the index.
+    Shadow,
+}
+
+impl HeapBackend {
+    pub const ALL: [HeapBackend; 2] = [HeapBackend::Index, HeapBackend::Shadow];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeapBackend::Index => "index",
+            HeapBackend::Shadow => "shadow",
+        }
+    }
+}
+
+/// the backend chosen by `SVF_HEAP_BACKEND`, fixed on first use.
+pub fn heap_backend() -> HeapBackend {
+    static BACKEND: OnceLock<HeapBackend> = OnceLock::new();
+    *BACKEND.get_or_init(|| {
+        let Ok(value) = std::env::var(HEAP_BACKEND_ENV) else { return HeapBackend::Index };
+        let value = value.trim().to_ascii_lowercase();
+        HeapBackend::ALL.into_iter().find(|b| b.as_str() == value).unwrap_or_else(|| {
+            eprintln!("[svf_runtime] unknown {}={}, using index", HEAP_BACKEND_ENV, value);
+            HeapBackend::Index
+        })
+    })
+}
+
+pub(crate) fn enabled() -> bool {
+    heap_backend() == HeapBackend::Shadow
+}
+
+const GRANULE_SHIFT: u32 = 4;
+const GRANULE: usize = 1 << GRANULE_SHIFT;
+/// application bytes covered by one leaf.
+const LEAF_SHIFT: u32 = 32;
+const LEAF_GRANULES: usize = 1 << (LEAF_SHIFT - GRANULE_SHIFT);
+/// leaves covering a 48-bit address space.
+const DIRECTORY_LEN: usize = 1 << (48 - LEAF_SHIFT);
+/// largest object marked in the shadow; larger ones always fall back.
+const MAX_SHADOWED_SIZE: usize = 16 << 20;
+/// live objects the slot table holds.
+const MAX_SLOTS: usize = 1 << 22;
+
+const EMPTY: u32 = 0;
+const CONFLICT: u32 = u32::MAX;
+
+static DIRECTORY: [AtomicPtr<AtomicU32>; DIRECTORY_LEN] =
+    [const { AtomicPtr::new(std::ptr::null_mut()) }; DIRECTORY_LEN];
+
+const PROT_READ: i32 = 0x1;
+const PROT_WRITE: i32 = 0x2;
+const MAP_PRIVATE: i32 = 0x02;
+const MAP_ANONYMOUS: i32 = 0x20;
+const MAP_NORESERVE: i32 = 0x4000;
+const MAP_FAILED: usize = !0;
+
+extern "C" {
+    fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut u8;
+    fn munmap(addr: *mut u8, len: usize) -> i32;
+}
+
+/// reserve `len` zeroed bytes, committed page by page as they are touched.
+fn reserve(len: usize) -> Option<*mut u8> {
+    let flags = MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE;
+    let mem = unsafe { mmap(std::ptr::null_mut(), len, PROT_READ | PROT_WRITE, flags, -1, 0) };
+    (mem as usize != MAP_FAILED).then_some(mem)
+}
+
+/// a live object in the slot table. all-zero bytes are a valid (empty) slot.
+#[repr(C)]
+struct Slot {
+    /// odd while the fields are being rewritten.
+    seq: AtomicU64,
+    base: AtomicU64,
+    size: AtomicU64,
+    site_id: AtomicU64,
+    ticket: AtomicU64,
+}
+
+/// slot allocation, only touched by allocs and frees.
+struct SlotState {
+    /// slots never handed out start here.
+    next: usize,
+    free: Vec<u32>,
+    by_base: HashMap<usize, u32>,
+}
+
+impl SlotState {
+    fn take(&mut self) -> Option<u32> {
+        self.free.pop().or_else(|| {
+            (self.next < MAX_SLOTS).then(|| {
+                self.next += 1;
+                (self.next - 1) as u32
+            })
+        })
+    }
+}
+
+/// answer of a shadow lookup.
+pub(crate) enum Lookup {
+    Hit(Object),
+    Miss,
+    /// the shadow cannot tell, ask `LIVE_HEAP`.
+    Unknown,
+}
+
+pub(crate) struct Shadow {
+    /// `MAX_SLOTS` slots, or null if they could not be reserved.
+    slots: *const Slot,
+    state: Mutex<SlotState>,
+    /// live objects that are not in the shadow.
+    unshadowed: AtomicUsize,
+    fallback_lookups: AtomicU64,
+}
+
+// `slots` is only accessed through atomics.
+unsafe impl Send for Shadow {}
+unsafe impl Sync for Shadow {}
+
+lazy_static! {
+    pub(crate) static ref SHADOW: Shadow = Shadow::new();
+}
+
+/// the leaf covering `addr`, reserving it if `create` is set.
+fn leaf(addr: usize, create: bool) -> Option<*const AtomicU32> {
+    let entry = DIRECTORY.get(addr >> LEAF_SHIFT)?;
+    let leaf = entry.load(Ordering::Acquire);
+    if !leaf.is_null() {
+        return Some(leaf);
+    }
+    if !create {
+        return None;
+    }
+    let len = LEAF_GRANULES * size_of::<AtomicU32>();
+    let fresh = reserve(len)? as *mut AtomicU32;
+    match entry.compare_exchange(std::ptr::null_mut(), fresh, Ordering::AcqRel, Ordering::Acquire) {
+        Ok(_) => Some(fresh),
+        Err(winner) => {
+            unsafe { munmap(fresh as *mut u8, len) };
+            Some(winner)
+        }
+    }
+}
+
+/// the shadow granule of `addr`, if its leaf exists.
+fn granule(addr: usize) -> Option<&'static AtomicU32> {
+    let leaf = leaf(addr, false)?;
+    Some(unsafe { &*leaf.add((addr >> GRANULE_SHIFT) & (LEAF_GRANULES - 1)) })
+}
+
+/// granule-aligned starts of the granules overlapping `[base, end)`.
+fn granules(base: usize, end: usize) -> impl Iterator<Item = usize> {
+    (base & !(GRANULE - 1)..end).step_by(GRANULE)
+}
+
+impl Shadow {
+    fn new() -> Self {
+        let slots = reserve(MAX_SLOTS * size_of::<Slot>()).map_or(std::ptr::null(), |mem| mem as *const Slot);
+        Self {
+            slots,
+            // slot 0 is `EMPTY`.
+            state: Mutex::new(SlotState { next: 1, free: Vec::new(), by_base: HashMap::new() }),
+            unshadowed: AtomicUsize::new(0),
+            fallback_lookups: AtomicU64::new(0),
+        }
+    }
+
+    fn slot(&self, slot: u32) -> &Slot {
+        unsafe { &*self.slots.add(slot as usize) }
+    }
+
+    /// rewrite `slot`. callers hold `state`, so there is one writer at a time.
+    fn write_slot(&self, slot: u32, base: usize, (size, site_id, ticket): Entry) {
+        let s = self.slot(slot);
+        s.seq.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::Release);
+        s.base.store(base as u64, Ordering::Relaxed);
+        s.size.store(size as u64, Ordering::Relaxed);
+        s.site_id.store(site_id, Ordering::Relaxed);
+        s.ticket.store(ticket, Ordering::Relaxed);
+        s.seq.fetch_add(1, Ordering::Release);
+    }
+
+    /// the object in `slot`, `None` if a writer got in the way.
+    fn read_slot(&self, slot: u32) -> Option<Object> {
+        let s = self.slot(slot);
+        let seq = s.seq.load(Ordering::Acquire);
+        if seq & 1 != 0 {
+            return None;
+        }
+        let base = s.base.load(Ordering::Relaxed) as usize;
+        let entry = (
+            s.size.load(Ordering::Relaxed) as usize,
+            s.site_id.load(Ordering::Relaxed),
+            s.ticket.load(Ordering::Relaxed),
+        );
+        fence(Ordering::Acquire);
+        (s.seq.load(Ordering::Relaxed) == seq).then_some((base, entry))
+    }
+
+    /// record the object at `base`, which must not be in the shadow yet.
+    pub(crate) fn insert(&self, base: usize, entry: Entry) {
+        let size = entry.0;
+        if size == 0 {
+            return;
+        }
+        let end = base.saturating_add(size);
+        let shadowable = !self.slots.is_null()
+            && size <= MAX_SHADOWED_SIZE
+            && leaf(base, true).is_some()
+            && leaf(end - 1, true).is_some();
+        let mut state = self.state.lock().unwrap();
+        let Some(slot) = (if shadowable { state.take() } else { None }) else {
+            self.unshadowed.fetch_add(1, Ordering::Relaxed);
+            return;
+        };
+        state.by_base.insert(base, slot);
+        self.write_slot(slot, base, entry);
+        drop(state);
+
+        for start in granules(base, end) {
+            let Some(g) = granule(start) else { continue };
+            if start >= base && start + GRANULE <= end {
+                g.store(slot, Ordering::Release);
+            } else {
+                let _ = g.fetch_update(Ordering::AcqRel, Ordering::Acquire, |cur| {
+                    Some(if cur == EMPTY || cur == slot { slot } else { CONFLICT })
+                });
+            }
+        }
+    }
+
+    /// forget the object of `size` bytes at `base`.
+    pub(crate) fn remove(&self, base: usize, size: usize) {
+        if size == 0 {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let Some(slot) = state.by_base.remove(&base) else {
+            self.unshadowed.fetch_sub(1, Ordering::Relaxed);
+            return;
+        };
+        // only clear the granules still pointing at this object, a shared one may
+        // have been marked `CONFLICT` since.
+        for start in granules(base, base.saturating_add(size)) {
+            if let Some(g) = granule(start) {
+                let _ = g.compare_exchange(slot, EMPTY, Ordering::AcqRel, Ordering::Relaxed);
+            }
+        }
+        state.free.push(slot);
+    }
+
+    /// the live object containing `addr`, as far as the shadow knows.
+    pub(crate) fn lookup(&self, addr: usize) -> Lookup {
+        let slot = granule(addr).map_or(EMPTY, |g| g.load(Ordering::Acquire));
+        let mut torn = false;
+        if slot != EMPTY && slot != CONFLICT {
+            match self.read_slot(slot) {
+                Some((base, entry)) if addr.wrapping_sub(base) < entry.0 => return Lookup::Hit((base, entry)),
+                Some(_) => {}
+                None => torn = true,
+            }
+        }
+        if torn || slot == CONFLICT || self.unshadowed.load(Ordering::Relaxed) > 0 {
+            self.fallback_lookups.fetch_add(1, Ordering::Relaxed);
+            return Lookup::Unknown;
+        }
+        Lookup::Miss
+    }
+}
+
+/// which backend served the heap lookups, and how often the shadow fell back.
+#[derive(Clone, Copy, Debug)]
+pub struct HeapBackendStats {
+    pub backend: HeapBackend,
+    /// live objects the shadow does not cover.
+    pub unshadowed_objects: u64,
+    /// shadow lookups answered by `LIVE_HEAP`.
+    pub fallback_lookups: u64,
+}
+
+pub fn heap_backend_stats() -> HeapBackendStats {
+    let backend = heap_backend();
+    let (unshadowed_objects, fallback_lookups) = match backend {
+        HeapBackend::Index => (0, 0),
+        HeapBackend::Shadow => (
+            SHADOW.unshadowed.load(Ordering::Relaxed) as u64,
+            SHADOW.fallback_lookups.load(Ordering::Relaxed),
+        ),
+    };
+    HeapBackendStats { backend, unshadowed_objects, fallback_lookups }
+}
+
+impl HeapBackendStats {
+    pub(crate) fn write_json(&self, o: &mut JsonObject) {
+        o.str("backend", self.backend.as_str())
+            .u64("unshadowed_objects", self.unshadowed_objects)
+            .u64("fallback_lookups", self.fallback_lookups);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Lookup, Shadow};
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn lookups_never_mix_two_objects_of_a_reused_slot() {
+        let shadow = Arc::new(Shadow::new());
+        let x = 0x6100_0000usize;
+        // both objects contain `x + 40` and take turns in the same slot.
+        let objects = [(x, (64, 1, 101)), (x + 32, (64, 2, 102))];
+        let done = Arc::new(AtomicBool::new(false));
+
+        let readers: Vec<_> = (0..2)
+            .map(|_| {
+                let (shadow, done) = (Arc::clone(&shadow), Arc::clone(&done));
+                std::thread::spawn(move || {
+                    while !done.load(Ordering::Relaxed) {
+                        if let Lookup::Hit(hit) = shadow.lookup(x + 40) {
+                            assert!(objects.contains(&hit), "torn read {:?}", hit);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for round in 0..200_000 {
+            let (base, entry) = objects[round % 2];
+            shadow.insert(base, entry);
+            shadow.remove(base, entry.0);
+        }
+        done.store(true, Ordering::Relaxed);
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        shadow.insert(x, (64, 1, 101));
+        assert!(matches!(shadow.lookup(x + 63), Lookup::Hit((b, (64, 1, 101))) if b == x));
+        assert!(matches!(shadow.lookup(x + 64), Lookup::Miss));
+    }
+}
@@ -0,0 +1,267 @@
+//! sampled allocation call stacks.
+//!
+//! one svf site id often covers many calling contexts: after inlining, every
+//! `Vec` / `Box` allocation shares the site of `alloc::alloc`, as the
+//! `source_loc` of such sites in the pts dumps shows. with `SVF_STACK_SAMPLE=N`
+//! every Nth recorded allocation also captures its call stack:
+//! - stacks are walked with `_Unwind_Backtrace` (up to `MAX_FRAMES` return
+//!   addresses, no frame pointers needed) and deduplicated into a stack table,
+//!   which stores each distinct (site id, stack) pair once under a `stack_id`;
+//! - the ticket of a sampled live object maps to its stack, so FN events carry
+//!   a `stack_id` naming the object's real calling context.
+//!
+//! frames are only symbolized (with `dladdr`) when the report is built. `dladdr`
+//! only knows exported symbols, so most frames of an executable come out as a
+//! module offset for `addr2line`. leading frames with a symbol of the runtime
+//! itself are dropped, and rust symbols are left mangled.
+
+use std::collections::HashMap;
+use std::ffi::{c_char, c_void, CStr};
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::json::JsonArray;
+
+/// env var setting the stack sampling period: capture every Nth allocation.
+/// unset or `0` disables capture.
+pub const STACK_SAMPLE_ENV: &str = "SVF_STACK_SAMPLE";
+
+/// return addresses captured per stack.
+const MAX_FRAMES: usize = 32;
+
+#[repr(C)]
+struct UnwindContext {
+    _private: [u8; 0],
+}
+
+const URC_NO_REASON: i32 = 0;
+const URC_END_OF_STACK: i32 = 5;
+
+/// glibc's `Dl_info`.
+#[repr(C)]
+struct DlInfo {
+    fname: *const c_char,
+    fbase: usize,
+    sname: *const c_char,
+    saddr: usize,
+}
+
+extern "C" {
+    fn _Unwind_Backtrace(
+        trace: unsafe extern "C" fn(ctx: *mut UnwindContext, arg: *mut c_void) -> i32,
+        arg: *mut c_void,
+    ) -> i32;
+    fn _Unwind_GetIP(ctx: *mut UnwindContext) -> usize;
+    fn dladdr(addr: *const c_void, info: *mut DlInfo) -> i32;
+}
+
+unsafe extern "C" fn visit_frame(ctx: *mut UnwindContext, arg: *mut c_void) -> i32 {
+    let visit = &mut *(arg as *mut &mut dyn FnMut(usize) -> bool);
+    if visit(_Unwind_GetIP(ctx)) { URC_NO_REASON } else { URC_END_OF_STACK }
+}
+
+/// call `visit` with the return address of each frame of the current stack,
+/// innermost first, until it returns false. never allocates.
+pub(crate) fn walk(mut visit: impl FnMut(usize) -> bool) {
+    let mut visit: &mut dyn FnMut(usize) -> bool = &mut visit;
+    unsafe { _Unwind_Backtrace(visit_frame, &mut visit as *mut &mut dyn FnMut(usize) -> bool as *mut c_void) };
+}
+
+/// sampled allocations of one (site id, stack) pair.
+#[derive(Clone)]
+struct StackEntry {
+    site_id: u64,
+    frames: Box<[usize]>,
+    allocs: u64,
+    bytes: u64,
+}
+
+#[derive(Default)]
+struct StackTable {
+    ids: HashMap<(u64, Box<[usize]>), u32>,
+    /// indexed by stack id.
+    stacks: Vec<StackEntry>,
+    /// stack of each sampled live object.
+    by_ticket: HashMap<u64, u32>,
+}
+
+lazy_static! {
+    static ref STACKS: Mutex<StackTable> = Mutex::new(StackTable::default());
+}
+
+/// allocations seen while sampling, to pick every Nth.
+static ALLOCS_SEEN: AtomicU64 = AtomicU64::new(0);
+
+/// the sampling period from `SVF_STACK_SAMPLE`, 0 when disabled.
+fn sample_period() -> u64 {
+    static PERIOD: OnceLock<u64> = OnceLock::new();
+    *PERIOD.get_or_init(|| match std::env::var(STACK_SAMPLE_ENV) {
+        Ok(v) => v.trim().parse().unwrap_or_else(|_| {
+            eprintln!("[svf_runtime] invalid {}={}, stack capture disabled", STACK_SAMPLE_ENV, v);
+            0
+        }),
+        Err(_) => 0,
+    })
+}
+
+/// capture the current stack for the new object `ticket` if it is sampled.
+/// called from the allocation hooks, with hooks disabled.
+pub(crate) fn record_alloc(ticket: u64, site_id: u64, size: usize) {
+    let period = sample_period();
+    if period == 0 || !ALLOCS_SEEN.fetch_add(1, Ordering::Relaxed).is_multiple_of(period) {
+        return;
+    }
+    let mut frames = [0usize; MAX_FRAMES];
+    let mut len = 0;
+    walk(|ip| {
+        frames[len] = ip;
+        len += 1;
+        len < MAX_FRAMES
+    });
+
+    let mut table = STACKS.lock().unwrap();
+    let key = (site_id, Box::from(&frames[..len]));
+    let next_id = table.stacks.len() as u32;
+    let stack_id = *table.ids.entry(key).or_insert(next_id);
+    if stack_id == next_id {
+        table.stacks.push(StackEntry { site_id, frames: Box::from(&frames[..len]), allocs: 0, bytes: 0 });
+    }
+    let entry = &mut table.stacks[stack_id as usize];
+    entry.allocs += 1;
+    entry.bytes += size as u64;
+    table.by_ticket.insert(ticket, stack_id);
+}
+
+/// forget the stack of the freed object `ticket`.
+pub(crate) fn forget(ticket: u64) {
+    if sample_period() != 0 {
+        STACKS.lock().unwrap().by_ticket.remove(&ticket);
+    }
+}
+
+/// the stack captured for the live object `ticket`, if it was sampled.
+pub(crate) fn ticket_stack(ticket: u64) -> Option<u32> {
+    if sample_period() == 0 {
+        return None;
+    }
+    STACKS.lock().ok()?.by_ticket.get(&ticket).copied()
+}
+
+/// a symbolized return address.
+#[derive(Clone, Debug)]
+pub struct StackFrame {
+    pub ip: usize,
+    /// path of the module containing `ip`, and `ip`'s offset in it.
+    pub module: Option<(String, usize)>,
+    /// the nearest exported symbol at or below `ip`, and `ip`'s offset from it.
+    pub symbol: Option<(String, usize)>,
+}
+
+impl StackFrame {
+    fn symbolize(ip: usize) -> Self {
+        let mut info = DlInfo { fname: std::ptr::null(), fbase: 0, sname: std::ptr::null(), saddr: 0 };
+        // a return address points past the call, which may be in the next function.
+        if unsafe { dladdr(ip.saturating_sub(1) as *const c_void, &mut info) } == 0 {
+            return Self { ip, module: None, symbol: None };
+        }
+        let name = |s: *const c_char| unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned();
+        Self {
+            ip,
+            module: (!info.fname.is_null()).then(|| (name(info.fname), ip - info.fbase)),
+            symbol: (!info.sname.is_null()).then(|| (name(info.sname), ip - info.saddr)),
+        }
+    }
+
+    /// whether the frame belongs to the runtime's own hooks.
+    fn in_runtime(&self) -> bool {
+        self.symbol.as_ref().is_some_and(|(s, _)| s.starts_with("__svf_") || s.contains("svf_runtime"))
+    }
+
+    /// `symbol+0x1f (module+0x1234)`, for the text report.
+    fn describe(&self) -> String {
+        let module = match &self.module {
+            Some((path, offset)) => format!("{}+{:#x}", path.rsplit('/').next().unwrap_or(path), offset),
+            None => format!("{:#x}", self.ip),
+        };
+        match &self.symbol {
+            Some((symbol, offset)) => format!("{}+{:#x} ({})", symbol, offset, module),
+            None => module,
+        }
+    }
+}
+
+/// sampled allocations sharing a site id and call stack.
+#[derive(Clone, Debug)]
+pub struct AllocStack {
+    pub stack_id: u32,
+    pub site_id: u64,
+    /// sampled allocations with this stack and their bytes.
+    pub allocs: u64,
+    pub bytes: u64,
+    /// innermost first, without the runtime's own frames.
+    pub frames: Vec<StackFrame>,
+}
+
+/// the symbolized stack table, largest sampled bytes first.
+pub fn alloc_stacks() -> Vec<AllocStack> {
+    let entries = crate::without_hooks(|| STACKS.lock().unwrap().stacks.clone());
+    let mut stacks: Vec<AllocStack> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(stack_id, s)| AllocStack {
+            stack_id: stack_id as u32,
+            site_id: s.site_id,
+            allocs: s.allocs,
+            bytes: s.bytes,
+            frames: s.frames.iter().map(|&ip| StackFrame::symbolize(ip)).skip_while(StackFrame::in_runtime).collect(),
+        })
+        .collect();
+    stacks.sort_unstable_by(|a, b| b.bytes.cmp(&a.bytes).then(a.stack_id.cmp(&b.stack_id)));
+    stacks
+}
+
+/// stacks shown in the text report, and frames shown per stack; the json
+/// report lists everything.
+const TEXT_STACKS: usize = 10;
+const TEXT_FRAMES: usize = 6;
+
+pub(crate) fn write_stacks_json(stacks: &[AllocStack], a: &mut JsonArray) {
+    for stack in stacks {
+        a.object(|o| {
+            o.u64("stack_id", stack.stack_id as u64)
+                .u64("site_id", stack.site_id)
+                .u64("allocs", stack.allocs)
+                .u64("bytes", stack.bytes)
+                .array("frames", |f| {
+                    for frame in &stack.frames {
+                        f.object(|o| {
+                            o.str("ip", &format!("{:#x}", frame.ip));
+                            if let Some((module, offset)) = &frame.module {
+                                o.str("module", module).u64("module_offset", *offset as u64);
+                            }
+                            if let Some((symbol, offset)) = &frame.symbol {
+                                o.str("symbol", symbol).u64("symbol_offset", *offset as u64);
+                            }
+                        });
+                    }
+                });
+        });
+    }
+}
+
+/// the largest sampled stacks; prints nothing when sampling is off.
+pub fn write_stacks_text(stacks: &[AllocStack], out: &mut dyn Write) -> io::Result<()> {
+    if stacks.is_empty() { return Ok(()); }
+    writeln!(out, "\n=== SVF Allocation Stacks (1 in {} allocations) ===", sample_period())?;
+    for stack in stacks.iter().take(TEXT_STACKS) {
+        writeln!(out, "  stack {} (site {}): {} allocs, {} bytes", stack.stack_id, stack.site_id, stack.allocs, stack.bytes)?;
+        for frame in stack.frames.iter().take(TEXT_FRAMES) {
+            writeln!(out, "    {}", frame.describe())?;
+        }
+    }
+    if stacks.len() > TEXT_STACKS {
+        writeln!(out, "  ... {} more stacks in the json report", stacks.len() - TEXT_STACKS)?;
+    }
+    writeln!(out, "==============================\n")
+}
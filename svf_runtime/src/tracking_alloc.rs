@@ -0,0 +1,120 @@
+//! `#[global_allocator]` wrapper reporting every rust heap allocation.
+//!
+//! `__svf_report_alloc` only sees the allocations the instrumentation pass
+//! rewrote with a site id. a rust program built without the lto plugin, or one
+//! whose allocations go through code the pass never saw, can install
+//! [`SvfTrackingAllocator`] instead:
+//!
+//! ```no_run
+//! use svf_runtime::tracking_alloc::SvfTrackingAllocator;
+//!
+//! #[global_allocator]
+//! static ALLOC: SvfTrackingAllocator = SvfTrackingAllocator::system();
+//! # fn main() {}
+//! ```
+//!
+//! every alloc, dealloc and realloc then reaches `LIVE_HEAP` through the same
+//! hooks, so the unsafe access ground truth covers the whole rust heap.
+//!
+//! ## site ids
+//! an allocation's site id is a hash of the return addresses of its first
+//! `SITE_FRAMES` frames, taken with `_Unwind_Backtrace` (no frame pointers
+//! needed), with [`TRACKED_SITE_TAG`] set so it never collides with the
+//! static site ids. the same call path always yields the same id within a run,
+//! but addresses (and so ids) change between runs under aslr. a realloc keeps the
+//! object's site.
+//!
+//! allocations made by the runtime itself (hooks disabled) are passed through
+//! untracked. freeing such a block later is not a bad free, and a realloc of one
+//! starts tracking it under the realloc's call path.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use crate::{heap, hook, stacks};
+
+/// set in every site id assigned by [`SvfTrackingAllocator`].
+pub const TRACKED_SITE_TAG: u64 = 1 << 63;
+
+/// return addresses hashed into a site id. rust allocations pass through several
+/// `alloc` / `RawVec` frames before reaching the caller that matters.
+const SITE_FRAMES: usize = 12;
+
+/// global allocator forwarding to `A` and reporting to the svf runtime.
+pub struct SvfTrackingAllocator<A: GlobalAlloc = System> {
+    inner: A,
+}
+
+impl SvfTrackingAllocator<System> {
+    /// track the system allocator.
+    pub const fn system() -> Self {
+        Self::new(System)
+    }
+}
+
+impl<A: GlobalAlloc> SvfTrackingAllocator<A> {
+    pub const fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+/// site id of the current call path, see the module docs.
+#[inline(never)]
+fn caller_site() -> u64 {
+    // fnv-1a over the return addresses.
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    let mut frames = 0;
+    stacks::walk(|ip| {
+        for byte in ip.to_le_bytes() {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3);
+        }
+        frames += 1;
+        frames < SITE_FRAMES
+    });
+    hash | TRACKED_SITE_TAG
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for SvfTrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc(layout);
+        if !ptr.is_null() {
+            hook(|| heap::record_alloc(ptr as usize, layout.size(), caller_site()));
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.inner.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            hook(|| heap::record_alloc(ptr as usize, layout.size(), caller_site()));
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // record first: once freed, another thread may get the address back. an
+        // untracked block is one the runtime allocated, not a bad free.
+        hook(|| {
+            heap::record_dealloc(ptr as usize);
+        });
+        self.inner.dealloc(ptr, layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // take the object out first, as in `dealloc`: a moved block is freed inside
+        // `inner.realloc`, and another thread may get its address back right away.
+        let mut taken = None;
+        hook(|| taken = heap::begin_realloc(ptr as usize));
+        let new_ptr = self.inner.realloc(ptr, layout, new_size);
+        hook(|| match taken {
+            // site 0 keeps the object's site; a failed realloc puts the object back.
+            Some(entry) => heap::end_realloc(ptr as usize, entry, new_ptr as usize, new_size, 0),
+            None if !new_ptr.is_null() => heap::record_alloc(new_ptr as usize, new_size, caller_site()),
+            None => {}
+        });
+        new_ptr
+    }
+}
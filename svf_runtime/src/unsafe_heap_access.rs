@@ -15,43 +15,115 @@
 //!   AND at runtime the pointer did NOT access any live heap object. this is correct.
 //!
 //! ## how stats are calculated
-//! - `ANALYZED_SITES`: count of unique svf abstract heap object node ids seen via
+//! - `analyzed_sites`: count of unique svf abstract heap object node ids seen via
 //!   `__svf_analyze_heap_obj`. this is the total number of allocation sites svf's
 //!   andersen analysis linked to at least one unsafe-region pointer.
 //! - `ACCESS_TP/FP/FN/TN`: incremented once per instrumented load/store in sese regions.
 //!   determined by matching `(svf_has_targets, runtime_is_heap)`.
-//! - `MATCHED_SITE_IDS`: unique allocation site ids where svf's analysis matched the
+//! - `matched_site_ids`: unique allocation site ids where svf's analysis matched the
 //!   runtime heap object's site_id for a given pointer.
-//! - `MISSED_SITE_IDS`: unique allocation site ids where the runtime found a heap object
+//! - `missed_site_ids`: unique allocation site ids where the runtime found a heap object
 //!   but svf either had no targets or had different targets for that pointer.
-//! - `FP_SITE_IDS`: unique allocation site ids that svf associated with a pointer,
+//! - `fp_site_ids`: unique allocation site ids that svf associated with a pointer,
 //!   but at runtime the pointer was not accessing any heap object.
 //!
 //! ## hook call order (per instrumented load/store)
 //! 1. `__svf_analyze_heap_obj(ptr, site_id)` is called once per svf target — populates
 //!    the thread-local `CURRENT_ANALYSIS` array with the analyzed site ids.
 //! 2. `__svf_check_heap_access(ptr, is_load, access_id)` is called once per access — classifies
-//!    the access as TP/FP/FN/TN by checking the pointer against `LIVE_HEAP`.
+//!    the access as TP/FP/FN/TN by checking the pointer against `LIVE_HEAP`. when the
+//!    access width is known, `__svf_check_heap_access_sized(ptr, len, is_load, access_id)`
+//!    is called instead.
 //! 3. false negatives emit a single-line JSON record keyed by `access_id` so logs
-//!    can be joined against the static `unsafe_accesses` dump.
+//!    can be joined with the pts dumps through the lto plugin's access map
+//!    (`svf_access_ids_<module>.json`; `svf-triage` in the `svf_pts` crate does
+//!    this offline).
+//!
+//! ## per-site confusion
+//! the per-site table breaks the matrix down by allocation site:
+//! - `tp` / `fn`: TP / FN accesses whose runtime object belongs to the site.
+//! - `fp_predictions`: how often the site was predicted for an access that hit no
+//!   predicted site, i.e. an FP access or a `site_mismatch` FN.
+//! - `access_ids` / `tickets`: distinct access ids and heap objects (tickets)
+//!   that touched the site at runtime.
+//!
+//! the report ranks sites by `fn + fp_predictions` and joins them with the pts
+//! dump's site metadata. the counters are recorded through `access_log` like
+//! every other set, so concurrent accesses are never dropped.
+//!
+//! ## per-access table
+//! the per-access table keeps one row per executed `access_id`: execution count, its
+//! TP/FP/FN/TN counts, the union of runtime site ids it touched and the largest
+//! (uncapped) predicted set seen. rows are recorded through `access_log`, so
+//! concurrent accesses are never dropped, and dumped in the json report keyed
+//! by `access_id` alone. the pts dumps' `unsafe_ptrs` do not record access ids;
+//! the lto plugin's access map gives the instruction of each id, see
+//! `svf_pts::access_map`.
+//!
+//! ## all-object validation
+//! the TP/FP/FN/TN matrix only knows heap objects. independently of it, every
+//! access is resolved to an abstract object: the `LIVE_HEAP` object's site, or
+//! else a stack / global object registered in `objects`. the prediction is then
+//! a `match` (the object's id is predicted), a `mismatch` (other ids are
+//! predicted) or an `empty_prediction`, counted per object kind. accesses that
+//! resolve to no object are `unresolved`.
+//!
+//! ## non-heap regions
+//! FP and TN accesses are further counted by the memory region the pointer fell
+//! into (stack, global, tls, mmap, unknown; see `region`), which tells whether
+//! svf confuses heap with stack or with globals.
+//!
+//! ## use-after-free
+//! an access that misses `LIVE_HEAP` but falls into an object in the heap
+//! module's quarantine of recently freed objects is a use-after-free. it is
+//! counted and emitted as a `{"event":"svf_uaf",...}` record *in addition to*
+//! its TP/FP/FN/TN classification, which still treats the pointer as not heap.
+//! the lookup never waits for the quarantine: a check that finds it locked by a
+//! concurrent free is skipped and counted as `checks_skipped`, and an object is
+//! only found when few freed bases lie between it and the address, so the
+//! count is a lower bound.
+//!
+//! ## out-of-bounds
+//! `__svf_check_heap_access_sized` also receives the access width `len` and
+//! checks `[ptr, ptr + len)` against `LIVE_HEAP`:
+//! - **partial**: the access overlaps a live object but does not fit in it, i.e.
+//!   it starts inside the object and runs past its end, or starts before the
+//!   object and runs into it.
+//! - **full**: the access misses every live object and starts less than `len`
+//!   bytes past the end of the nearest object below it. accesses further away
+//!   cannot be attributed to an object and are not reported.
+//!
+//! out-of-bounds accesses are counted and emitted as `{"event":"svf_oob",...}`
+//! records keyed by `access_id`, again in addition to the TP/FP/FN/TN
+//! classification, which only looks at `ptr` itself.
+//!
+//! ## collection
+//! counters are atomics; sets and tables are built from per-thread event buffers
+//! (see `access_log`), so no update is lost to lock contention. the few events
+//! that cannot be recorded at all are reported as `dropped_events`.
 //!
 //! IMPORTANT: these hooks are called for EVERY load/store in sese regions,
 //! including loads/stores inside this module and the runtime itself.
 //! all operations must be non-allocating and non-blocking to prevent
 //! stack overflow and deadlocks.
 
-use std::sync::Mutex;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::collections::BTreeSet;
+use std::io::{self, Write};
+use std::ptr;
 
 use crate::{IN_CHECKER, ReentrancyGuard};
+use crate::access_log::{self, AccessSets, Event};
+use crate::heap::LiveObject;
+use crate::json::{JsonArray, JsonObject};
+use crate::objects::ObjectKind;
+use crate::pts_dump;
+use crate::region;
 
 // heap access counters: count how many loads/stores actually targeted heap objects.
 // these only increment when the runtime confirms the pointer is in LIVE_HEAP.
 static HEAP_LOAD_COUNT: AtomicUsize = AtomicUsize::new(0);
 static HEAP_STORE_COUNT: AtomicUsize = AtomicUsize::new(0);
-// count of unique svf abstract heap object node ids (allocation sites svf analyzed).
-static ANALYZED_SITES: AtomicUsize = AtomicUsize::new(0);
 
 // per-access confusion matrix counters.
 // each instrumented sese load/store increments exactly one of these.
@@ -63,27 +135,18 @@ static ACCESS_TP: AtomicUsize = AtomicUsize::new(0);
 static ACCESS_FP: AtomicUsize = AtomicUsize::new(0);
 static ACCESS_FN: AtomicUsize = AtomicUsize::new(0);
 static ACCESS_TN: AtomicUsize = AtomicUsize::new(0);
+// FP / TN accesses by the region the pointer fell into, indexed by `Region`.
+static FP_BY_REGION: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+static TN_BY_REGION: [AtomicUsize; 5] = [const { AtomicUsize::new(0) }; 5];
+// all-object validation, indexed by `[ObjectKind][ObjectOutcome]`.
+static OBJECT_OUTCOMES: [[AtomicUsize; 3]; 3] = [const { [const { AtomicUsize::new(0) }; 3] }; 3];
+// accesses that resolved to no heap, stack or global object.
+static UNRESOLVED_ACCESSES: AtomicUsize = AtomicUsize::new(0);
+// accesses into quarantined (recently freed) heap objects.
+static ACCESS_UAF: AtomicUsize = AtomicUsize::new(0);
+// out-of-bounds sized accesses, indexed by `OobKind`.
+static ACCESS_OOB: [AtomicUsize; 2] = [const { AtomicUsize::new(0) }; 2];
 
-lazy_static! {
-    /// global set of all unique allocation site ids that svf analyzed across the program.
-    static ref GLOBAL_ANALYZED_SITE_IDS: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
-    
-    /// set of globally unique allocation tickets that were actually touched by unsafe pointers.
-    /// uses monotonic ticket ids (not addresses) to avoid double-counting after free/reuse.
-    static ref ACTUALLY_TOUCHED_TICKETS: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
-
-    /// tickets where svf correctly identified the allocation site (true positives).
-    static ref MATCHED_TOUCHED_TICKETS: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
-
-    /// unique site_ids where svf's analysis matched the runtime object (true positive sites).
-    static ref MATCHED_SITE_IDS: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
-
-    /// unique site_ids touched at runtime but missed by svf (false negative sites).
-    static ref MISSED_SITE_IDS: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
-
-    /// unique site_ids svf associated with a pointer that was NOT on heap (false positive sites).
-    static ref FP_SITE_IDS: Mutex<BTreeSet<u64>> = Mutex::new(BTreeSet::new());
-}
 
 // thread-local array of svf analysis results for the *current* instruction.
 // populated by `__svf_analyze_heap_obj` and consumed/cleared by `__svf_check_heap_access`.
@@ -106,117 +169,488 @@ static mut CURRENT_ANALYSIS_LEN: usize = 0;
 #[thread_local]
 static mut CURRENT_ANALYSIS_TRUE_LEN: usize = 0;
 
-/// print unsafe heap access statistics.
-/// called via atexit handler registered in `__svf_analyze_heap_obj`.
-pub fn print_unsafe_heap_stats() {
-    let heap_loads = HEAP_LOAD_COUNT.load(Ordering::Relaxed);
-    let heap_stores = HEAP_STORE_COUNT.load(Ordering::Relaxed);
-    let analyzed_objs = ANALYZED_SITES.load(Ordering::Relaxed);
-    
-    // tally actual memory allocated by the svf-analyzed site ids.
-    let mut analyzed_mem = 0;
-    if let Ok(analyzed) = GLOBAL_ANALYZED_SITE_IDS.try_lock() {
-        for &site_id in analyzed.iter() {
-            analyzed_mem += crate::heap::get_site_alloc_bytes(site_id);
+/// view of the site ids recorded for the current access.
+unsafe fn current_analysis() -> &'static [u64] {
+    match &*ptr::addr_of!(CURRENT_ANALYSIS) {
+        Some(analysis) => &analysis[..CURRENT_ANALYSIS_LEN],
+        None => &[],
+    }
+}
+
+/// number of worst sites listed in the text report.
+const TEXT_WORST_SITES: usize = 20;
+
+/// per-site confusion counters as reported.
+#[derive(Clone, Copy, Debug)]
+pub struct SiteConfusion {
+    pub site_id: u64,
+    pub tp: u64,
+    pub fn_: u64,
+    pub fp_predictions: u64,
+    /// distinct access ids that touched the site at runtime.
+    pub access_ids: u64,
+    /// distinct heap objects of the site touched at runtime.
+    pub tickets: u64,
+}
+
+impl SiteConfusion {
+    /// TP / (TP + FN) over this site's accesses, in percent.
+    pub fn recall(&self) -> Option<f64> {
+        let denom = self.tp + self.fn_;
+        (denom > 0).then(|| self.tp as f64 / denom as f64 * 100.0)
+    }
+
+    /// wrong classifications attributed to the site, used for ranking.
+    pub fn errors(&self) -> u64 {
+        self.fn_ + self.fp_predictions
+    }
+}
+
+/// classification of one access in the confusion matrix.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum AccessClass {
+    Tp = 0,
+    Fp = 1,
+    Fn = 2,
+    Tn = 3,
+}
+
+impl AccessClass {
+    pub const ALL: [AccessClass; 4] = [AccessClass::Tp, AccessClass::Fp, AccessClass::Fn, AccessClass::Tn];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccessClass::Tp => "tp",
+            AccessClass::Fp => "fp",
+            AccessClass::Fn => "fn",
+            AccessClass::Tn => "tn",
         }
     }
+}
 
-    let actual_touched_count = if let Ok(touched) = ACTUALLY_TOUCHED_TICKETS.try_lock() {
-        touched.len()
-    } else {
-        0
-    };
-    let matched_count = if let Ok(matched) = MATCHED_TOUCHED_TICKETS.try_lock() {
-        matched.len()
-    } else {
-        0
-    };
-    
-    let matched_sites = if let Ok(m) = MATCHED_SITE_IDS.try_lock() { m.len() } else { 0 };
-    let missed_sites = if let Ok(m) = MISSED_SITE_IDS.try_lock() { m.len() } else { 0 };
-
-    let mut never_accessed_fp_sites = BTreeSet::new();
-    if let (Ok(analyzed), Ok(matched)) = (GLOBAL_ANALYZED_SITE_IDS.try_lock(), MATCHED_SITE_IDS.try_lock()) {
-        for &id in analyzed.iter() {
-            if !matched.contains(&id) {
-                never_accessed_fp_sites.insert(id);
-            }
+/// how a sized access misses the live object it is reported against.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum OobKind {
+    /// overlaps the object but does not fit in it.
+    Partial = 0,
+    /// lies entirely beyond the end of the object.
+    Full = 1,
+}
+
+impl OobKind {
+    pub const ALL: [OobKind; 2] = [OobKind::Partial, OobKind::Full];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OobKind::Partial => "partial",
+            OobKind::Full => "full",
         }
     }
+}
+
+/// per-access row as reported.
+#[derive(Clone, Debug)]
+pub struct AccessSummary {
+    pub access_id: u64,
+    /// counts indexed by `AccessClass`.
+    pub classes: [u64; 4],
+    /// runtime allocation sites the access touched.
+    pub site_ids: Vec<u64>,
+    /// largest predicted set size, including ids past `CURRENT_ANALYSIS_CAP`.
+    pub max_predicted: usize,
+}
+
+impl AccessSummary {
+    pub fn executions(&self) -> u64 {
+        self.classes.iter().sum()
+    }
+}
+
+/// outcome of validating a prediction against the abstract object an access resolved to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ObjectOutcome {
+    Match = 0,
+    Mismatch = 1,
+    EmptyPrediction = 2,
+}
+
+impl ObjectOutcome {
+    pub const ALL: [ObjectOutcome; 3] = [ObjectOutcome::Match, ObjectOutcome::Mismatch, ObjectOutcome::EmptyPrediction];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ObjectOutcome::Match => "match",
+            ObjectOutcome::Mismatch => "mismatch",
+            ObjectOutcome::EmptyPrediction => "empty_prediction",
+        }
+    }
+}
+
+/// snapshot of the unsafe heap access statistics, shared by the text and json renderers.
+#[derive(Clone, Debug, Default)]
+pub struct UnsafeHeapStats {
+    // per-access confusion matrix
+    pub tp: usize,
+    pub fp: usize,
+    pub fn_: usize,
+    pub tn: usize,
+    /// FP / TN accesses by the region the pointer fell into, in `Region::ALL` order.
+    pub fp_by_region: [usize; 5],
+    pub tn_by_region: [usize; 5],
+    // svf static analysis overview
+    pub analyzed_sites: usize,
+    pub analyzed_bytes: u64,
+    // runtime heap object tracking
+    pub heap_loads: usize,
+    pub heap_stores: usize,
+    pub touched_objects: usize,
+    pub matched_objects: usize,
+    pub matched_site_ids: Vec<u64>,
+    pub missed_site_ids: Vec<u64>,
+    pub fp_site_ids: Vec<u64>,
+    /// svf-analyzed sites never matched by an unsafe access at runtime.
+    pub never_accessed_site_ids: Vec<u64>,
+    /// runtime objects allocated at `never_accessed_site_ids`.
+    pub never_accessed_objects: u64,
+    /// per-site confusion counters, worst (most errors) first.
+    pub sites: Vec<SiteConfusion>,
+    /// per-access rows, sorted by access id.
+    pub accesses: Vec<AccessSummary>,
+    // all-object validation, indexed by `[ObjectKind][ObjectOutcome]`
+    pub object_outcomes: [[usize; 3]; 3],
+    pub unresolved_accesses: usize,
+    /// stack / global registrations, indexed by `ObjectKind`.
+    pub registered_objects: [u64; 3],
+    // use-after-free, reported separately from the confusion matrix
+    pub uaf_accesses: usize,
+    pub uaf_site_ids: Vec<u64>,
+    /// quarantine lookups skipped because a free held the quarantine.
+    pub uaf_checks_skipped: u64,
+    // out-of-bounds sized accesses, also outside the confusion matrix
+    pub oob_partial: usize,
+    pub oob_full: usize,
+    pub oob_access_ids: Vec<u64>,
+    /// set / table updates that could not be recorded (thread exiting).
+    pub dropped_events: u64,
+}
+
+impl UnsafeHeapStats {
+    pub fn total(&self) -> usize {
+        self.tp + self.fp + self.fn_ + self.tn
+    }
+
+    /// TP / (TP + FP), in percent.
+    pub fn precision(&self) -> Option<f64> {
+        let denom = self.tp + self.fp;
+        (denom > 0).then(|| self.tp as f64 / denom as f64 * 100.0)
+    }
+
+    /// TP / (TP + FN), in percent.
+    pub fn recall(&self) -> Option<f64> {
+        let denom = self.tp + self.fn_;
+        (denom > 0).then(|| self.tp as f64 / denom as f64 * 100.0)
+    }
 
-    let mut never_accessed_fp_objs = 0;
-    for &id in never_accessed_fp_sites.iter() {
-        never_accessed_fp_objs += crate::heap::get_site_alloc_count(id);
+    /// touched heap objects whose site svf never matched.
+    pub fn missed_objects(&self) -> usize {
+        self.touched_objects.saturating_sub(self.matched_objects)
     }
 
-    let tp = ACCESS_TP.load(Ordering::Relaxed);
-    let fp = ACCESS_FP.load(Ordering::Relaxed);
-    let fn_ = ACCESS_FN.load(Ordering::Relaxed);
-    let tn = ACCESS_TN.load(Ordering::Relaxed);
-    let total = tp + fp + fn_ + tn;
-
-    println!("\n=== SVF Unsafe Heap Access Analysis ===");
-
-    // section 1: per-access confusion matrix
-    // classifies each instrumented sese load/store based on whether svf identified
-    // heap targets and whether the pointer actually accessed heap at runtime.
-    println!("--- Per-Access Confusion Matrix ---");
-    println!("(each instrumented load/store in SESE unsafe regions is classified once)");
-    println!("Total instrumented SESE accesses: {}", total);
-    println!("  True Positive  (SVF identified heap target, runtime IS  heap): {}", tp);
-    println!("  False Positive (SVF identified heap target, runtime NOT heap): {}", fp);
-    println!("  False Negative (SVF found no heap target,   runtime IS  heap): {}", fn_);
-    println!("  True Negative  (SVF found no heap target,   runtime NOT heap): {}", tn);
-    if total > 0 {
-        let precision = if tp + fp > 0 { tp as f64 / (tp + fp) as f64 * 100.0 } else { 0.0 };
-        let recall = if tp + fn_ > 0 { tp as f64 / (tp + fn_) as f64 * 100.0 } else { 0.0 };
-        println!("  Precision (TP / (TP + FP)): {:.2}%", precision);
-        println!("  Recall    (TP / (TP + FN)): {:.2}%", recall);
+    pub(crate) fn write_json(&self, o: &mut JsonObject) {
+        o.object("confusion", |c| {
+            c.u64("total", self.total() as u64)
+                .u64("tp", self.tp as u64)
+                .u64("fp", self.fp as u64)
+                .u64("fn", self.fn_ as u64)
+                .u64("tn", self.tn as u64)
+                .opt_f64("precision", self.precision())
+                .opt_f64("recall", self.recall());
+        });
+        o.object("regions", |r| {
+            r.object("fp", |f| region::write_counts_json(&self.fp_by_region, f))
+                .object("tn", |t| region::write_counts_json(&self.tn_by_region, t));
+        });
+        o.object("static", |st| {
+            st.u64("analyzed_sites", self.analyzed_sites as u64)
+                .u64("analyzed_bytes", self.analyzed_bytes);
+        });
+        o.object("runtime", |r| {
+            r.u64("heap_loads", self.heap_loads as u64)
+                .u64("heap_stores", self.heap_stores as u64)
+                .u64("touched_objects", self.touched_objects as u64)
+                .u64("matched_objects", self.matched_objects as u64)
+                .u64("missed_objects", self.missed_objects() as u64)
+                .u64("never_accessed_objects", self.never_accessed_objects);
+        });
+        o.u64_list("matched_site_ids", self.matched_site_ids.iter().copied())
+            .u64_list("missed_site_ids", self.missed_site_ids.iter().copied())
+            .u64_list("fp_site_ids", self.fp_site_ids.iter().copied())
+            .u64_list("never_accessed_site_ids", self.never_accessed_site_ids.iter().copied());
+        o.array("sites", |a| write_site_confusion_json(&self.sites, a));
+        o.array("accesses", |a| write_access_table_json(&self.accesses, a));
+        o.object("objects", |ob| {
+            ob.object("registered", |r| {
+                r.u64("stack", self.registered_objects[ObjectKind::Stack as usize])
+                    .u64("global", self.registered_objects[ObjectKind::Global as usize]);
+            });
+            for kind in ObjectKind::ALL {
+                ob.object(kind.as_str(), |k| {
+                    for outcome in ObjectOutcome::ALL {
+                        k.u64(outcome.as_str(), self.object_outcomes[kind as usize][outcome as usize] as u64);
+                    }
+                });
+            }
+            ob.u64("unresolved", self.unresolved_accesses as u64);
+        });
+        o.object("use_after_free", |u| {
+            u.u64("accesses", self.uaf_accesses as u64)
+                .u64_list("site_ids", self.uaf_site_ids.iter().copied())
+                .u64("checks_skipped", self.uaf_checks_skipped);
+        });
+        o.object("out_of_bounds", |b| {
+            b.u64("partial", self.oob_partial as u64)
+                .u64("full", self.oob_full as u64)
+                .u64_list("access_ids", self.oob_access_ids.iter().copied());
+        });
+        o.u64("dropped_events", self.dropped_events);
     }
-    if fp > 0 {
-        if let Ok(fps) = FP_SITE_IDS.try_lock() {
-            print!("  FP site IDs (SVF static analysis claimed pointer targets these sites, but actually not): ");
-            for &id in fps.iter() { print!("{} ", id); }
-            println!();
+
+    /// human-readable rendering, the format previously printed by `print_unsafe_heap_stats`.
+    pub fn write_text(&self, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "\n=== SVF Unsafe Heap Access Analysis ===")?;
+
+        // section 1: per-access confusion matrix
+        // classifies each instrumented sese load/store based on whether svf identified
+        // heap targets and whether the pointer actually accessed heap at runtime.
+        writeln!(out, "--- Per-Access Confusion Matrix ---")?;
+        writeln!(out, "(each instrumented load/store in SESE unsafe regions is classified once)")?;
+        writeln!(out, "Total instrumented SESE accesses: {}", self.total())?;
+        writeln!(out, "  True Positive  (SVF identified heap target, runtime IS  heap): {}", self.tp)?;
+        writeln!(out, "  False Positive (SVF identified heap target, runtime NOT heap): {}", self.fp)?;
+        writeln!(out, "  False Negative (SVF found no heap target,   runtime IS  heap): {}", self.fn_)?;
+        writeln!(out, "  True Negative  (SVF found no heap target,   runtime NOT heap): {}", self.tn)?;
+        if self.total() > 0 {
+            writeln!(out, "  Precision (TP / (TP + FP)): {:.2}%", self.precision().unwrap_or(0.0))?;
+            writeln!(out, "  Recall    (TP / (TP + FN)): {:.2}%", self.recall().unwrap_or(0.0))?;
+        }
+        if self.fp > 0 {
+            write!(out, "  FP accesses by region: ")?;
+            region::write_counts_text(&self.fp_by_region, out)?;
+        }
+        if self.tn > 0 {
+            write!(out, "  TN accesses by region: ")?;
+            region::write_counts_text(&self.tn_by_region, out)?;
+        }
+        if self.fp > 0 {
+            write!(out, "  FP site IDs (SVF static analysis claimed pointer targets these sites, but actually not): ")?;
+            write_id_list(out, &self.fp_site_ids)?;
+        }
+
+        // section 2: svf static analysis overview
+        // how many unique allocation sites svf's andersen analysis linked to unsafe pointers.
+        writeln!(out, "--- SVF Static Analysis ---")?;
+        writeln!(out, "Unique allocation sites SVF identified as aliased by unsafe ptrs: {}", self.analyzed_sites)?;
+        writeln!(out, "Total memory allocated by SVF-identified sites at runtime: {} bytes", self.analyzed_bytes)?;
+
+        // section 3: runtime heap object tracking (ground truth)
+        // tracks unique heap objects (by monotonic ticket id) to avoid reuse confusion.
+        // a heap object is "touched" if any instrumented unsafe pointer accessed it.
+        writeln!(out, "--- Runtime Heap Object Tracking (Ground Truth) ---")?;
+        writeln!(out, "Unsafe heap loads observed at runtime: {}", self.heap_loads)?;
+        writeln!(out, "Unsafe heap stores observed at runtime: {}", self.heap_stores)?;
+        writeln!(out, "Historically touched unique heap objects: {}", self.touched_objects)?;
+        writeln!(out, "  -> True Positive objects (SVF correctly identified): {} [from {} unique sites]", self.matched_objects, self.matched_site_ids.len())?;
+        write!(out, "     Matched site IDs: ")?;
+        write_id_list(out, &self.matched_site_ids)?;
+        writeln!(out, "  -> False Negative objects (SVF missed): {} [from {} unique sites]", self.missed_objects(), self.missed_site_ids.len())?;
+        write!(out, "     Missed site IDs: ")?;
+        write_id_list(out, &self.missed_site_ids)?;
+        if !self.never_accessed_site_ids.is_empty() {
+            writeln!(out, "  -> False Positive objects (SVF identified but NEVER accessed by unsafe ptr): {} [from {} unique sites]", self.never_accessed_objects, self.never_accessed_site_ids.len())?;
+            write!(out, "     FP site IDs: ")?;
+            write_id_list(out, &self.never_accessed_site_ids)?;
+        }
+        // true negative objects: pointers that svf correctly did not associate with heap,
+        // and at runtime they indeed did not access heap. reported as access count above.
+        writeln!(out, "  -> True Negative accesses (no heap target, confirmed not heap): {}", self.tn)?;
+        if !self.accesses.is_empty() {
+            let imprecise = self.accesses.iter().filter(|a| a.classes[AccessClass::Fp as usize] + a.classes[AccessClass::Fn as usize] > 0).count();
+            writeln!(out, "Distinct access ids executed: {} ({} with FP or FN accesses; per-id table in the json report)", self.accesses.len(), imprecise)?;
+        }
+        if !self.sites.is_empty() {
+            writeln!(out, "--- Worst Allocation Sites (FN accesses + FP predictions) ---")?;
+            for site in self.sites.iter().take(TEXT_WORST_SITES) {
+                write!(out, "  site {}: {} TP, {} FN, {} FP predictions, {} access ids, {} objects", site.site_id, site.tp, site.fn_, site.fp_predictions, site.access_ids, site.tickets)?;
+                match pts_dump::site_info(site.site_id) {
+                    Some(info) => writeln!(out, " -- {}", info.describe())?,
+                    None => writeln!(out)?,
+                }
+            }
+            if self.sites.len() > TEXT_WORST_SITES {
+                writeln!(out, "  ... {} more sites in the json report", self.sites.len() - TEXT_WORST_SITES)?;
+            }
+        }
+        if self.registered_objects.iter().any(|&n| n > 0) {
+            writeln!(out, "--- All-Object Validation (heap, stack and global objects) ---")?;
+            writeln!(out, "Registered objects: {} stack, {} global", self.registered_objects[ObjectKind::Stack as usize], self.registered_objects[ObjectKind::Global as usize])?;
+            for kind in ObjectKind::ALL {
+                let [matched, mismatched, empty] = self.object_outcomes[kind as usize];
+                writeln!(out, "  {:<6} accesses: {} match, {} mismatch, {} empty prediction", kind.as_str(), matched, mismatched, empty)?;
+            }
+            writeln!(out, "  Unresolved accesses (no known object): {}", self.unresolved_accesses)?;
+        }
+        if self.uaf_accesses > 0 {
+            writeln!(out, "Use-after-free accesses (hit a recently freed object): {} [from {} unique sites]", self.uaf_accesses, self.uaf_site_ids.len())?;
+            write!(out, "     UAF site IDs: ")?;
+            write_id_list(out, &self.uaf_site_ids)?;
         }
+        if self.uaf_checks_skipped > 0 {
+            writeln!(out, "Use-after-free checks skipped (quarantine busy): {}", self.uaf_checks_skipped)?;
+        }
+        if self.oob_partial + self.oob_full > 0 {
+            writeln!(out, "Out-of-bounds accesses: {} partial, {} full [from {} unique access ids]", self.oob_partial, self.oob_full, self.oob_access_ids.len())?;
+            write!(out, "     OOB access IDs: ")?;
+            write_id_list(out, &self.oob_access_ids)?;
+        }
+        if self.dropped_events > 0 {
+            writeln!(out, "WARNING: {} events could not be recorded; sets and tables may be incomplete", self.dropped_events)?;
+        }
+        writeln!(out, "======================================\n")
     }
+}
+
+fn write_id_list(out: &mut dyn Write, ids: &[u64]) -> io::Result<()> {
+    for id in ids { write!(out, "{} ", id)?; }
+    writeln!(out)
+}
 
-    // section 2: svf static analysis overview
-    // how many unique allocation sites svf's andersen analysis linked to unsafe pointers.
-    println!("--- SVF Static Analysis ---");
-    println!("Unique allocation sites SVF identified as aliased by unsafe ptrs: {}", analyzed_objs);
-    println!("Total memory allocated by SVF-identified sites at runtime: {} bytes", analyzed_mem);
-
-    // section 3: runtime heap object tracking (ground truth)
-    // tracks unique heap objects (by monotonic ticket id) to avoid reuse confusion.
-    // a heap object is "touched" if any instrumented unsafe pointer accessed it.
-    println!("--- Runtime Heap Object Tracking (Ground Truth) ---");
-    println!("Unsafe heap loads observed at runtime: {}", heap_loads);
-    println!("Unsafe heap stores observed at runtime: {}", heap_stores);
-    println!("Historically touched unique heap objects: {}", actual_touched_count);
-    println!("  -> True Positive objects (SVF correctly identified): {} [from {} unique sites]", matched_count, matched_sites);
-    if let Ok(matched) = MATCHED_SITE_IDS.try_lock() {
-        print!("     Matched site IDs: ");
-        for &id in matched.iter() { print!("{} ", id); }
-        println!();
+fn write_site_confusion_json(sites: &[SiteConfusion], a: &mut JsonArray) {
+    for site in sites {
+        a.object(|o| {
+            o.u64("site_id", site.site_id)
+                .u64("tp", site.tp)
+                .u64("fn", site.fn_)
+                .u64("fp_predictions", site.fp_predictions)
+                .u64("access_ids", site.access_ids)
+                .u64("tickets", site.tickets)
+                .opt_f64("recall", site.recall());
+            if let Some(info) = pts_dump::site_info(site.site_id) {
+                info.write_json(o);
+            }
+        });
     }
-    let fn_objs = if actual_touched_count >= matched_count { actual_touched_count - matched_count } else { 0 };
-    println!("  -> False Negative objects (SVF missed): {} [from {} unique sites]", fn_objs, missed_sites);
-    if let Ok(missed) = MISSED_SITE_IDS.try_lock() {
-        print!("     Missed site IDs: ");
-        for &id in missed.iter() { print!("{} ", id); }
-        println!();
+}
+
+fn write_access_table_json(accesses: &[AccessSummary], a: &mut JsonArray) {
+    for access in accesses {
+        a.object(|o| {
+            o.u64("access_id", access.access_id).u64("executions", access.executions());
+            for class in AccessClass::ALL {
+                o.u64(class.as_str(), access.classes[class as usize]);
+            }
+            o.u64_list("site_ids", access.site_ids.iter().copied())
+                .u64("max_predicted", access.max_predicted as u64);
+        });
     }
-    if !never_accessed_fp_sites.is_empty() {
-        println!("  -> False Positive objects (SVF identified but NEVER accessed by unsafe ptr): {} [from {} unique sites]", never_accessed_fp_objs, never_accessed_fp_sites.len());
-        print!("     FP site IDs: ");
-        for &id in never_accessed_fp_sites.iter() { print!("{} ", id); }
-        println!();
+}
+
+/// per-access rows, sorted by access id.
+fn access_table(log: &AccessSets) -> Vec<AccessSummary> {
+    let mut accesses: Vec<AccessSummary> = log
+        .accesses
+        .iter()
+        .map(|(&access_id, acc)| AccessSummary {
+            access_id,
+            classes: acc.classes,
+            site_ids: acc.site_ids.iter().copied().collect(),
+            max_predicted: acc.max_predicted,
+        })
+        .collect();
+    accesses.sort_unstable_by_key(|a| a.access_id);
+    accesses
+}
+
+/// per-site confusion counters, worst first.
+fn site_confusion(log: &AccessSets) -> Vec<SiteConfusion> {
+    let mut sites: Vec<SiteConfusion> = log
+        .sites
+        .iter()
+        .map(|(&site_id, acc)| SiteConfusion {
+            site_id,
+            tp: acc.tp,
+            fn_: acc.fn_,
+            fp_predictions: acc.fp_predictions,
+            access_ids: acc.access_ids.len() as u64,
+            tickets: acc.tickets.len() as u64,
+        })
+        .collect();
+    sites.sort_unstable_by_key(|s| (std::cmp::Reverse(s.errors()), s.site_id));
+    sites
+}
+
+fn ids(set: &BTreeSet<u64>) -> Vec<u64> {
+    set.iter().copied().collect()
+}
+
+/// collect the unsafe heap access statistics.
+pub fn unsafe_heap_stats() -> UnsafeHeapStats {
+    let log = crate::without_hooks(access_log::aggregate);
+
+    // tally actual memory allocated by the svf-analyzed site ids.
+    let analyzed = ids(&log.analyzed_site_ids);
+    let analyzed_bytes = analyzed.iter().map(|&id| crate::heap::get_site_alloc_bytes(id)).sum();
+    let matched_site_ids = ids(&log.matched_site_ids);
+
+    let never_accessed_site_ids: Vec<u64> = analyzed
+        .iter()
+        .copied()
+        .filter(|id| matched_site_ids.binary_search(id).is_err())
+        .collect();
+    let never_accessed_objects = never_accessed_site_ids
+        .iter()
+        .map(|&id| crate::heap::get_site_alloc_count(id))
+        .sum();
+
+    UnsafeHeapStats {
+        tp: ACCESS_TP.load(Ordering::Relaxed),
+        fp: ACCESS_FP.load(Ordering::Relaxed),
+        fn_: ACCESS_FN.load(Ordering::Relaxed),
+        tn: ACCESS_TN.load(Ordering::Relaxed),
+        fp_by_region: FP_BY_REGION.each_ref().map(|c| c.load(Ordering::Relaxed)),
+        tn_by_region: TN_BY_REGION.each_ref().map(|c| c.load(Ordering::Relaxed)),
+        analyzed_sites: analyzed.len(),
+        analyzed_bytes,
+        heap_loads: HEAP_LOAD_COUNT.load(Ordering::Relaxed),
+        heap_stores: HEAP_STORE_COUNT.load(Ordering::Relaxed),
+        touched_objects: log.touched_tickets.len(),
+        matched_objects: log.matched_tickets.len(),
+        matched_site_ids,
+        missed_site_ids: ids(&log.missed_site_ids),
+        fp_site_ids: ids(&log.fp_site_ids),
+        never_accessed_site_ids,
+        never_accessed_objects,
+        sites: site_confusion(&log),
+        accesses: access_table(&log),
+        object_outcomes: OBJECT_OUTCOMES.each_ref().map(|k| k.each_ref().map(|c| c.load(Ordering::Relaxed))),
+        unresolved_accesses: UNRESOLVED_ACCESSES.load(Ordering::Relaxed),
+        registered_objects: crate::objects::registered_counts(),
+        uaf_accesses: ACCESS_UAF.load(Ordering::Relaxed),
+        uaf_site_ids: ids(&log.uaf_site_ids),
+        uaf_checks_skipped: crate::heap::quarantine_skipped(),
+        oob_partial: ACCESS_OOB[OobKind::Partial as usize].load(Ordering::Relaxed),
+        oob_full: ACCESS_OOB[OobKind::Full as usize].load(Ordering::Relaxed),
+        oob_access_ids: ids(&log.oob_access_ids),
+        dropped_events: access_log::dropped_events(),
     }
-    // true negative objects: pointers that svf correctly did not associate with heap,
-    // and at runtime they indeed did not access heap. reported as access count above.
-    println!("  -> True Negative accesses (no heap target, confirmed not heap): {}", tn);
-    println!("======================================\n");
+}
+
+/// print unsafe heap access statistics.
+/// called via atexit handler registered in `__svf_analyze_heap_obj`.
+pub fn print_unsafe_heap_stats() {
+    let _ = unsafe_heap_stats().write_text(&mut io::stdout().lock());
 }
 
 fn print_fn_event_json(
@@ -229,7 +663,7 @@ fn print_fn_event_json(
     analyzed_len: usize,
 ) {
     print!(
-        "{{\"event\":\"svf_fn\",\"kind\":\"{}\",\"access_id\":{},\"ptr\":\"{:p}\",\"is_load\":{},\"heap_ticket\":{},\"runtime_site_id\":{},\"predicted_site_ids\":[",
+        "{{\"event\":\"svf_fn\",\"kind\":\"{}\",\"access_id\":{},\"ptr\":\"{:p}\",\"is_load\":{},\"heap_ticket\":{},\"runtime_site_id\":{},",
         kind,
         access_id,
         ptr,
@@ -237,14 +671,16 @@ fn print_fn_event_json(
         heap_ticket,
         runtime_site_id,
     );
+    if let Some(stack_id) = crate::stacks::ticket_stack(heap_ticket) {
+        print!("\"stack_id\":{},", stack_id);
+    }
+    print!("\"predicted_site_ids\":[");
 
-    if let Some(analysis) = unsafe { CURRENT_ANALYSIS } {
-        for i in 0..analyzed_len {
-            if i > 0 {
-                print!(",");
-            }
-            print!("{}", analysis[i]);
+    for (i, site_id) in unsafe { current_analysis() }.iter().take(analyzed_len).enumerate() {
+        if i > 0 {
+            print!(",");
         }
+        print!("{}", site_id);
     }
 
     let true_len = unsafe { CURRENT_ANALYSIS_TRUE_LEN };
@@ -254,19 +690,99 @@ fn print_fn_event_json(
     );
 }
 
+/// count a false prediction for every site svf predicted for the current access.
+unsafe fn record_false_predictions(fp_access: bool) {
+    for &site_id in current_analysis() {
+        if site_id > 0 {
+            access_log::record(Event::FalsePrediction { site_id, fp_access });
+        }
+    }
+}
+
+/// validate the current prediction against the abstract object the access resolved to.
+unsafe fn record_object_outcome(resolved: Option<(ObjectKind, u64)>) {
+    let Some((kind, obj_id)) = resolved else {
+        UNRESOLVED_ACCESSES.fetch_add(1, Ordering::Relaxed);
+        return;
+    };
+    let outcome = if CURRENT_ANALYSIS_LEN == 0 {
+        ObjectOutcome::EmptyPrediction
+    } else if current_analysis().contains(&obj_id) {
+        ObjectOutcome::Match
+    } else {
+        ObjectOutcome::Mismatch
+    };
+    OBJECT_OUTCOMES[kind as usize][outcome as usize].fetch_add(1, Ordering::Relaxed);
+}
+
+/// count and emit an access into a quarantined (recently freed) heap object.
+fn check_use_after_free(ptr: *const u8, is_load: bool, access_id: u64) {
+    let Some(freed) = crate::heap::get_quarantined_object(ptr) else { return };
+    ACCESS_UAF.fetch_add(1, Ordering::Relaxed);
+    access_log::record(Event::UseAfterFree { site_id: freed.site_id });
+    println!(
+        "{{\"event\":\"svf_uaf\",\"access_id\":{},\"ptr\":\"{:p}\",\"is_load\":{},\"heap_ticket\":{},\"runtime_site_id\":{},\"object_base\":\"{:#x}\",\"object_size\":{}}}",
+        access_id, ptr, is_load, freed.ticket, freed.site_id, freed.base, freed.size,
+    );
+}
+
+/// count and emit a sized access `[ptr, ptr + len)` that does not fit in a live object.
+/// `object` is the live object containing `ptr`, if any.
+fn check_out_of_bounds(ptr: *const u8, len: usize, object: Option<LiveObject>, is_load: bool, access_id: u64) {
+    let start = ptr as usize;
+    let end = start.saturating_add(len);
+    let (kind, obj) = match object {
+        Some(obj) if end > obj.base + obj.size => (OobKind::Partial, obj),
+        Some(_) => return,
+        // `ptr` is in no live object, so `below` (if any) ends at or before it.
+        None => match crate::heap::get_live_heap_neighbours(ptr) {
+            (_, Some(above)) if above.base < end => (OobKind::Partial, above),
+            (Some(below), _) if start < (below.base + below.size).saturating_add(len) => (OobKind::Full, below),
+            _ => return,
+        },
+    };
+    ACCESS_OOB[kind as usize].fetch_add(1, Ordering::Relaxed);
+    access_log::record(Event::OutOfBounds { access_id });
+    println!(
+        "{{\"event\":\"svf_oob\",\"kind\":\"{}\",\"access_id\":{},\"ptr\":\"{:p}\",\"len\":{},\"is_load\":{},\"heap_ticket\":{},\"runtime_site_id\":{},\"object_base\":\"{:#x}\",\"object_size\":{}}}",
+        kind.as_str(), access_id, ptr, len, is_load, obj.ticket, obj.site_id, obj.base, obj.size,
+    );
+}
+
 /// runtime hook: called once per instrumented load/store to cross-check svf analysis
 /// against runtime heap state. classifies each access as TP/FP/FN/TN.
 ///
 /// ## classification logic:
 /// - has_svf_targets = CURRENT_ANALYSIS_LEN > 0 (svf identified heap allocation targets)
-/// - is_heap = get_live_heap_ticket(ptr) returns Some (pointer is in LIVE_HEAP)
+/// - is_heap = get_live_heap_object(ptr) returns Some (pointer is in LIVE_HEAP)
 /// - (true, Some)  => TP: svf correctly identified a heap target
 /// - (true, None)  => FP: svf said heap but pointer is actually stack/global
 /// - (false, Some) => FN: svf missed this heap access entirely
 /// - (false, None) => TN: svf correctly had no heap targets for a non-heap pointer
+///
+/// # Safety
+/// `ptr` is only compared against `LIVE_HEAP` and never dereferenced.
 #[no_mangle]
 #[inline(never)]
 pub unsafe extern "C" fn __svf_check_heap_access(ptr: *const u8, is_load: bool, access_id: u64) {
+    check_heap_access(ptr, 0, is_load, access_id);
+}
+
+/// runtime hook: `__svf_check_heap_access` for an access of `len` bytes.
+/// classifies the access exactly like the unsized hook, and additionally
+/// reports it if `[ptr, ptr + len)` runs out of bounds of a live heap object.
+/// a `len` of 0 skips the bounds check.
+///
+/// # Safety
+/// `ptr` is only compared against `LIVE_HEAP` and never dereferenced.
+#[no_mangle]
+#[inline(never)]
+pub unsafe extern "C" fn __svf_check_heap_access_sized(ptr: *const u8, len: usize, is_load: bool, access_id: u64) {
+    check_heap_access(ptr, len, is_load, access_id);
+}
+
+/// shared body of the access hooks; `len` is 0 when the access width is unknown.
+unsafe fn check_heap_access(ptr: *const u8, len: usize, is_load: bool, access_id: u64) {
     if ptr.is_null() { return; }
     if IN_CHECKER.with(|c| c.get()) { return; }
     IN_CHECKER.with(|c| c.set(true));
@@ -274,43 +790,36 @@ pub unsafe extern "C" fn __svf_check_heap_access(ptr: *const u8, is_load: bool,
 
     let a_len = CURRENT_ANALYSIS_LEN;
     let svf_has_targets = a_len > 0;
-    let heap_hit = crate::heap::get_live_heap_ticket(ptr);
+    let object = crate::heap::get_live_heap_object(ptr);
+    let heap_hit = object.map(|obj| (obj.ticket, obj.site_id));
+    if heap_hit.is_none() {
+        check_use_after_free(ptr, is_load, access_id);
+    }
+    let resolved = match heap_hit {
+        Some((_, site_id)) => Some((ObjectKind::Heap, site_id)),
+        None => crate::objects::get_registered_object(ptr).map(|obj| (obj.kind, obj.obj_id)),
+    };
+    record_object_outcome(resolved);
+    if len > 0 {
+        check_out_of_bounds(ptr, len, object, is_load, access_id);
+    }
 
-    match (svf_has_targets, heap_hit) {
+    let class = match (svf_has_targets, heap_hit) {
         // svf identified heap target(s) AND pointer is on heap
         (true, Some((ticket, site_id))) => {
             if is_load { HEAP_LOAD_COUNT.fetch_add(1, Ordering::Relaxed); }
             else { HEAP_STORE_COUNT.fetch_add(1, Ordering::Relaxed); }
 
-            if let Ok(mut touched) = ACTUALLY_TOUCHED_TICKETS.try_lock() {
-                touched.insert(ticket);
-            }
-
             // check if *this specific* site_id was among svf's analysis results
-            let mut matched_current = false;
-            for i in 0..a_len {
-                if let Some(analysis) = CURRENT_ANALYSIS {
-                    if analysis[i] == site_id {
-                        matched_current = true;
-                        break;
-                    }
-                }
-            }
+            let matched_current = current_analysis().contains(&site_id);
             if matched_current {
                 ACCESS_TP.fetch_add(1, Ordering::Relaxed);
-                if let Ok(mut matched) = MATCHED_TOUCHED_TICKETS.try_lock() {
-                    matched.insert(ticket);
-                }
-                if let Ok(mut sites) = MATCHED_SITE_IDS.try_lock() {
-                    sites.insert(site_id);
-                }
+                AccessClass::Tp
             } else {
                 // svf identified *some* heap target, but not THIS specific site
                 // so it is actually a False Negative for this specific access!
                 ACCESS_FN.fetch_add(1, Ordering::Relaxed);
-                if let Ok(mut sites) = MISSED_SITE_IDS.try_lock() {
-                    sites.insert(site_id);
-                }
+                record_false_predictions(false);
                 // Tag truncation-suspect events distinctly so post-fix
                 // saturation can be separated from clean mismatches.  With
                 // CURRENT_ANALYSIS_CAP raised to 1024 this should be rare;
@@ -322,21 +831,16 @@ pub unsafe extern "C" fn __svf_check_heap_access(ptr: *const u8, is_load: bool,
                     "site_mismatch"
                 };
                 print_fn_event_json(kind, access_id, ptr, is_load, ticket, site_id, a_len);
+                AccessClass::Fn
             }
         }
         // FALSE POSITIVE: svf identified heap target(s) BUT pointer is NOT on heap
         (true, None) => {
             ACCESS_FP.fetch_add(1, Ordering::Relaxed);
+            FP_BY_REGION[region::classify(ptr) as usize].fetch_add(1, Ordering::Relaxed);
             // record which site_ids were incorrectly associated
-            if let Ok(mut fps) = FP_SITE_IDS.try_lock() {
-                for i in 0..a_len {
-                    if let Some(analysis) = CURRENT_ANALYSIS {
-                        if analysis[i] > 0 {
-                            fps.insert(analysis[i]);
-                        }
-                    }
-                }
-            }
+            record_false_predictions(true);
+            AccessClass::Fp
         }
         // FALSE NEGATIVE: svf identified 0 heap targets BUT pointer IS on heap
         (false, Some((ticket, site_id))) => {
@@ -344,19 +848,17 @@ pub unsafe extern "C" fn __svf_check_heap_access(ptr: *const u8, is_load: bool,
             if is_load { HEAP_LOAD_COUNT.fetch_add(1, Ordering::Relaxed); }
             else { HEAP_STORE_COUNT.fetch_add(1, Ordering::Relaxed); }
 
-            if let Ok(mut touched) = ACTUALLY_TOUCHED_TICKETS.try_lock() {
-                touched.insert(ticket);
-            }
-            if let Ok(mut sites) = MISSED_SITE_IDS.try_lock() {
-                sites.insert(site_id);
-            }
             print_fn_event_json("empty_prediction", access_id, ptr, is_load, ticket, site_id, a_len);
+            AccessClass::Fn
         }
         // TRUE NEGATIVE: svf identified 0 heap targets AND pointer is NOT on heap
         (false, None) => {
             ACCESS_TN.fetch_add(1, Ordering::Relaxed);
+            TN_BY_REGION[region::classify(ptr) as usize].fetch_add(1, Ordering::Relaxed);
+            AccessClass::Tn
         }
-    }
+    };
+    access_log::record(Event::Access { access_id, class, hit: heap_hit, predicted: CURRENT_ANALYSIS_TRUE_LEN });
 
     // unconditionally clear analysis results for next instruction
     CURRENT_ANALYSIS_LEN = 0;
@@ -367,6 +869,9 @@ pub unsafe extern "C" fn __svf_check_heap_access(ptr: *const u8, is_load: bool,
 /// runtime hook: called for EACH allocation site svf identified as aliasing a given pointer.
 /// populates the thread-local CURRENT_ANALYSIS array so that `__svf_check_heap_access`
 /// can cross-check against runtime heap state.
+///
+/// # Safety
+/// `ptr` is never dereferenced; only its nullness is checked.
 #[no_mangle]
 #[inline(never)]
 pub unsafe extern "C" fn __svf_analyze_heap_obj(ptr: *const u8, site_id: u64) {
@@ -381,25 +886,18 @@ pub unsafe extern "C" fn __svf_analyze_heap_obj(ptr: *const u8, site_id: u64) {
     });
 
     if site_id > 0 {
-        if CURRENT_ANALYSIS.is_none() {
-            CURRENT_ANALYSIS = Some([0; CURRENT_ANALYSIS_CAP]);
-        }
+        let current = &mut *ptr::addr_of_mut!(CURRENT_ANALYSIS);
+        let analysis = current.get_or_insert([0; CURRENT_ANALYSIS_CAP]);
         // Increment the true count first — it tracks ALL analyze calls,
         // including any past the CAP, so the classifier can detect
         // truncation-suspect events.
         CURRENT_ANALYSIS_TRUE_LEN += 1;
         if CURRENT_ANALYSIS_LEN < CURRENT_ANALYSIS_CAP {
-            if let Some(ref mut analysis) = CURRENT_ANALYSIS {
-                analysis[CURRENT_ANALYSIS_LEN] = site_id;
-                CURRENT_ANALYSIS_LEN += 1;
-            }
+            analysis[CURRENT_ANALYSIS_LEN] = site_id;
+            CURRENT_ANALYSIS_LEN += 1;
         }
 
-        if let Ok(mut analyzed) = GLOBAL_ANALYZED_SITE_IDS.try_lock() {
-            if analyzed.insert(site_id) {
-                ANALYZED_SITES.fetch_add(1, Ordering::Relaxed);
-            }
-        }
+        access_log::record(Event::Analyzed { site_id });
     }
 
     // IN_CHECKER is reset by ReentrancyGuard drop
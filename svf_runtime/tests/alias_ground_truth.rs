@@ -0,0 +1,57 @@
+//! each execution of an alias check is judged under the exact, same-object and
+//! overlap notions; sized checks decide overlap, unsized ones leave it undecided.
+
+use svf_runtime::alias::{alias_stats, Confusion, GroundTruth, __svf_check_alias, __svf_check_alias_sized};
+use svf_runtime::heap::__svf_report_alloc;
+
+const PREDICT_ALIAS: u32 = 1 << 31;
+
+/// true alias, true disjoint, false alias, false disjoint, undecided.
+fn counts(m: &Confusion) -> [usize; 5] {
+    [m.true_alias, m.true_disjoint, m.false_alias, m.false_disjoint, m.undecided]
+}
+
+#[test]
+fn sized_checks_fill_every_ground_truth_matrix() {
+    // two adjacent heap objects; the addresses are never dereferenced.
+    let (a, b) = (0x7100_0000usize, 0x7100_0040usize);
+    unsafe {
+        __svf_report_alloc(a as *mut u8, 64, 7);
+        __svf_report_alloc(b as *mut u8, 64, 8);
+
+        // inside one object, overlapping ranges.
+        __svf_check_alias_sized(a, 8, a + 4, 8, 1 | PREDICT_ALIAS);
+        // inside one object, disjoint ranges.
+        __svf_check_alias_sized(a, 4, a + 8, 4, 2);
+        // equal addresses alias under every notion.
+        __svf_check_alias_sized(a, 4, a, 4, 3);
+        // not on the heap: same-object is undecided.
+        __svf_check_alias_sized(0x1000, 8, 0x2000, 8, 4 | PREDICT_ALIAS);
+        // unsized: overlap is undecided.
+        __svf_check_alias(a, a + 4, 5 | PREDICT_ALIAS);
+        // a range crossing into the neighbouring object.
+        __svf_check_alias_sized(a + 0x30, 32, b, 8, 6 | PREDICT_ALIAS);
+    }
+
+    let stats = alias_stats();
+    let matrix = |truth: GroundTruth| counts(&stats.ground_truth[truth as usize]);
+    assert_eq!(matrix(GroundTruth::Exact), [0, 1, 4, 1, 0]);
+    assert_eq!(matrix(GroundTruth::SameObject), [2, 0, 1, 2, 1]);
+    assert_eq!(matrix(GroundTruth::Overlap), [2, 1, 1, 1, 1]);
+    assert_eq!(
+        [stats.true_alias, stats.true_disjoint, stats.false_alias, stats.false_disjoint],
+        [0, 1, 4, 1]
+    );
+
+    let check = |id: u64| stats.checks.iter().find(|(c, _)| *c == id).unwrap().1;
+    let aliased = |id: u64| {
+        let c = check(id);
+        [c.aliased, c.aliased_same_object, c.aliased_overlap]
+    };
+    assert_eq!(aliased(1), [0, 1, 1]);
+    assert_eq!(aliased(2), [0, 1, 0]);
+    assert_eq!(aliased(3), [1, 1, 1]);
+    assert_eq!(aliased(4), [0, 0, 0]);
+    assert_eq!(aliased(5), [0, 1, 0]);
+    assert_eq!(aliased(6), [0, 0, 1]);
+}
@@ -0,0 +1,46 @@
+//! every prediction byte of `__svf_check_alias_v2` decodes to its `Prediction`
+//! and lands in the matching counters; unknown bytes count as no info.
+
+use svf_runtime::alias::{alias_stats, Prediction, __svf_check_alias_v2};
+
+#[test]
+fn v2_predictions_decode_and_count() {
+    // the lto plugin writes these bytes, see `predictionByte`.
+    for (byte, prediction) in Prediction::ALL.iter().enumerate() {
+        assert_eq!(*prediction as u8, byte as u8);
+        assert_eq!(Prediction::from_u8(byte as u8), Some(*prediction));
+    }
+    assert_eq!(Prediction::from_u8(4), None);
+    assert_eq!(Prediction::from_u8(0xff), None);
+
+    let (a, b) = (0x1000usize, 0x2000usize);
+    let wide = 1u64 << 40;
+    unsafe {
+        __svf_check_alias_v2(a, a, 1, 0);
+        __svf_check_alias_v2(a, b, 2, 1);
+        __svf_check_alias_v2(a, a, wide, 2);
+        __svf_check_alias_v2(a, b, wide, 2);
+        __svf_check_alias_v2(a, b, 4, 3);
+        __svf_check_alias_v2(a, a, 5, 0xff);
+    }
+
+    let stats = alias_stats();
+    assert_eq!((stats.total, stats.no_info), (6, 1));
+    assert_eq!(
+        [stats.true_alias, stats.true_disjoint, stats.false_alias, stats.false_disjoint],
+        [1, 0, 3, 1]
+    );
+    let by_prediction: Vec<(usize, usize)> = stats.by_prediction.iter().map(|c| (c.executions, c.aliased)).collect();
+    assert_eq!(by_prediction, [(1, 1), (1, 0), (2, 1), (1, 0)]);
+    assert_eq!(stats.must_alias_violations, 1);
+
+    let check = |id: u64| stats.checks.iter().find(|(c, _)| *c == id).map(|(_, c)| *c);
+    let must = check(wide).unwrap();
+    assert_eq!((must.hits, must.aliased, must.must_alias_violations), (2, 1, 1));
+    assert_eq!(must.prediction, Prediction::MustAlias);
+    assert_eq!(check(1).unwrap().prediction, Prediction::NoAlias);
+    assert_eq!(check(2).unwrap().prediction, Prediction::MayAlias);
+    assert_eq!(check(4).unwrap().prediction, Prediction::PartialAlias);
+    // no info is counted, but never enters the per-check table.
+    assert!(check(5).is_none());
+}
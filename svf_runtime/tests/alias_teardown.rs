@@ -0,0 +1,35 @@
+//! a check made while a thread's thread-locals are torn down, after its stats
+//! slot was retired, is still counted instead of aborting the process.
+
+use std::thread;
+
+use svf_runtime::alias::{alias_stats, __svf_check_alias};
+
+struct CheckOnDrop;
+
+impl Drop for CheckOnDrop {
+    fn drop(&mut self) {
+        unsafe { __svf_check_alias(0x1000, 0x1000, 7) };
+    }
+}
+
+thread_local! {
+    static CHECK_ON_DROP: CheckOnDrop = const { CheckOnDrop };
+}
+
+#[test]
+fn checks_during_thread_teardown_are_counted() {
+    thread::spawn(|| {
+        // registered before the stats slot, so destroyed after it.
+        CHECK_ON_DROP.with(|_| {});
+        unsafe { __svf_check_alias(0x1000, 0x2000, 7) };
+    })
+    .join()
+    .unwrap();
+
+    let stats = alias_stats();
+    assert_eq!(stats.total, 2);
+    assert_eq!(stats.true_disjoint + stats.true_alias, 1);
+    let (id, check) = &stats.checks[0];
+    assert_eq!((*id, check.hits, check.aliased), (7, 2, 1));
+}
@@ -0,0 +1,72 @@
+//! alias statistics must include checks from the main thread and from threads
+//! that are still running when the report is collected, not only exited ones.
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use svf_runtime::alias::{alias_stats, CheckVerdict, __svf_check_alias};
+
+const PREDICT_ALIAS: u32 = 1 << 31;
+
+/// one true alias, one false alias and one true disjoint execution.
+fn run_checks(check_id: u32) {
+    let (a, b) = (0x1000usize, 0x2000usize);
+    unsafe {
+        __svf_check_alias(a, a, check_id | PREDICT_ALIAS);
+        __svf_check_alias(a, b, check_id | PREDICT_ALIAS);
+        __svf_check_alias(a, b, check_id + 1);
+    }
+}
+
+#[test]
+fn stats_cover_main_live_and_exited_threads() {
+    const LIVE: u32 = 4;
+    const EXITED: u32 = 3;
+
+    run_checks(0);
+
+    for t in 0..EXITED {
+        thread::spawn(move || run_checks(100 + 2 * t)).join().unwrap();
+    }
+
+    let checked = Arc::new(Barrier::new(LIVE as usize + 1));
+    let release = Arc::new(Barrier::new(LIVE as usize + 1));
+    let live: Vec<_> = (0..LIVE)
+        .map(|t| {
+            let (checked, release) = (Arc::clone(&checked), Arc::clone(&release));
+            thread::spawn(move || {
+                run_checks(200 + 2 * t);
+                // a false disjoint execution on a distinct check id
+                unsafe { __svf_check_alias(0x3000, 0x3000, 300 + t) };
+                checked.wait();
+                release.wait();
+            })
+        })
+        .collect();
+    checked.wait();
+
+    let threads = (1 + EXITED + LIVE) as usize;
+    let expect = |stats: &svf_runtime::alias::AliasStats| {
+        assert_eq!(stats.total, threads * 3 + LIVE as usize);
+        assert_eq!(stats.true_alias, threads);
+        assert_eq!(stats.false_alias, threads);
+        assert_eq!(stats.true_disjoint, threads);
+        assert_eq!(stats.false_disjoint, LIVE as usize);
+        assert_eq!(stats.checks.len(), threads * 2 + LIVE as usize);
+        let main_check = stats.checks.iter().find(|(id, _)| *id == 0).unwrap().1;
+        assert_eq!(main_check.hits, 2);
+        assert_eq!(main_check.aliased, 1);
+        assert_eq!(main_check.verdict(), CheckVerdict::TrueAlias);
+        assert_eq!(stats.verdict_counts(), [threads, threads, 0, LIVE as usize]);
+    };
+
+    // live workers have not exited yet, the main thread never will before atexit.
+    expect(&alias_stats());
+
+    release.wait();
+    for handle in live {
+        handle.join().unwrap();
+    }
+    // retiring the workers must not count their checks twice.
+    expect(&alias_stats());
+}
@@ -0,0 +1,39 @@
+//! with `SVF_STACK_SAMPLE=1` every allocation of a site is attributed to its
+//! calling context: one stack per distinct call path, deduplicated.
+
+// the preload interposers see allocations before the test can enable sampling.
+#![cfg(not(feature = "preload"))]
+
+use svf_runtime::heap::__svf_report_alloc;
+use svf_runtime::stacks::{alloc_stacks, STACK_SAMPLE_ENV};
+
+const SITE: u64 = 77;
+
+#[inline(never)]
+fn allocate_from_a(addr: usize) {
+    unsafe { __svf_report_alloc(addr as *mut u8, 16, SITE) };
+    std::hint::black_box(());
+}
+
+#[inline(never)]
+fn allocate_from_b(addr: usize) {
+    unsafe { __svf_report_alloc(addr as *mut u8, 32, SITE) };
+    std::hint::black_box(());
+}
+
+#[test]
+fn stacks_are_deduplicated_per_call_path() {
+    std::env::set_var(STACK_SAMPLE_ENV, "1");
+    let base = 0x6000_0000_0000usize;
+    for i in 0..3 {
+        allocate_from_a(base + i * 64);
+    }
+    allocate_from_b(base + 0x1000);
+
+    let stacks: Vec<_> = alloc_stacks().into_iter().filter(|s| s.site_id == SITE).collect();
+    let mut counts: Vec<_> = stacks.iter().map(|s| (s.allocs, s.bytes)).collect();
+    counts.sort_unstable();
+    assert_eq!(counts, vec![(1, 32), (3, 48)]);
+    assert!(stacks.iter().all(|s| !s.frames.is_empty()));
+    assert_ne!(stacks[0].stack_id, stacks[1].stack_id);
+}
@@ -0,0 +1,53 @@
+//! frees and reallocs that match no live object base are classified as never
+//! allocated, double free or interior pointer, and counted on the object's site.
+
+use svf_runtime::heap::{
+    bad_free_counts, leak_summary, site_stats, BadFree, SiteStats, __svf_report_alloc, __svf_report_dealloc,
+    __svf_report_realloc,
+};
+
+fn stats(site_id: u64) -> SiteStats {
+    site_stats().iter().find(|(id, _)| *id == site_id).map(|(_, s)| *s).unwrap_or_default()
+}
+
+fn count(kind: BadFree) -> u64 {
+    bad_free_counts()[kind as usize]
+}
+
+#[test]
+fn bad_frees_are_classified_per_site() {
+    let (a, b) = (0x7300_0000usize, 0x7300_1000usize);
+    unsafe {
+        __svf_report_alloc(a as *mut u8, 64, 21);
+        __svf_report_alloc(b as *mut u8, 64, 22);
+
+        __svf_report_dealloc(a as *mut u8);
+        assert_eq!(bad_free_counts(), [0, 0, 0]);
+
+        __svf_report_dealloc(a as *mut u8);
+        assert_eq!(count(BadFree::DoubleFree), 1);
+
+        __svf_report_dealloc((b + 8) as *mut u8);
+        assert_eq!(count(BadFree::InteriorPointer), 1);
+
+        __svf_report_dealloc(0x7300_9000 as *mut u8);
+        assert_eq!(count(BadFree::NeverAllocated), 1);
+    }
+    // an interior free leaves the object live.
+    assert!(leak_summary().iter().any(|l| l.site_id == 22));
+
+    unsafe {
+        // reallocating the freed object is a double free; the result is a new object.
+        __svf_report_realloc(a as *mut u8, 0x7300_2000 as *mut u8, 32, 23);
+        // reallocating from the middle of a live object.
+        __svf_report_realloc((b + 16) as *mut u8, 0x7300_3000 as *mut u8, 32, 23);
+        // an untracked pointer may come from an uninstrumented allocation.
+        __svf_report_realloc(0x7300_a000 as *mut u8, 0x7300_4000 as *mut u8, 32, 23);
+    }
+    assert_eq!(bad_free_counts(), [1, 2, 2]);
+    assert_eq!(leak_summary().iter().find(|l| l.site_id == 23).map(|l| l.objects), Some(3));
+
+    let (freed, live) = (stats(21), stats(22));
+    assert_eq!((freed.double_frees, freed.interior_frees, freed.free_count), (2, 0, 1));
+    assert_eq!((live.double_frees, live.interior_frees, live.free_count), (0, 2, 0));
+}
@@ -0,0 +1,50 @@
+//! a realloc keeps the object's ticket, moved or not; site 0 keeps its site and a
+//! new site moves the object's bytes between the sites.
+
+use svf_runtime::heap::{leak_summary, site_stats, SiteStats, __svf_report_alloc, __svf_report_realloc};
+
+fn live(site_id: u64) -> Option<(u64, u64, u64)> {
+    leak_summary().iter().find(|l| l.site_id == site_id).map(|l| (l.objects, l.bytes, l.oldest_ticket))
+}
+
+fn stats(site_id: u64) -> SiteStats {
+    site_stats().iter().find(|(id, _)| *id == site_id).map(|(_, s)| *s).unwrap_or_default()
+}
+
+#[test]
+fn realloc_keeps_ticket_and_site() {
+    let (a, b, c) = (0x7200_0000usize, 0x7200_1000usize, 0x7200_2000usize);
+    unsafe { __svf_report_alloc(a as *mut u8, 32, 11) };
+    let (_, _, ticket) = live(11).unwrap();
+
+    // in place, site 0: same site, same ticket, new size.
+    unsafe { __svf_report_realloc(a as *mut u8, a as *mut u8, 64, 0) };
+    assert_eq!(live(11), Some((1, 64, ticket)));
+
+    // moved, site 0: still the same object.
+    unsafe { __svf_report_realloc(a as *mut u8, b as *mut u8, 48, 0) };
+    assert_eq!(live(11), Some((1, 48, ticket)));
+
+    // moved to a new site: the old site frees it, the new one allocates it.
+    unsafe { __svf_report_realloc(b as *mut u8, c as *mut u8, 16, 12) };
+    assert_eq!(live(11), None);
+    assert_eq!(live(12), Some((1, 16, ticket)));
+
+    let old = stats(11);
+    assert_eq!((old.alloc_count, old.alloc_bytes, old.free_count, old.free_bytes), (1, 32, 1, 48));
+    assert_eq!((old.realloc_count, old.realloc_grow_bytes, old.realloc_shrink_bytes), (2, 32, 16));
+    let new = stats(12);
+    assert_eq!((new.alloc_count, new.alloc_bytes, new.free_count), (1, 16, 0));
+    assert_eq!((new.realloc_count, new.realloc_grow_bytes, new.realloc_shrink_bytes), (1, 0, 32));
+
+    // an untracked pointer (or null) becomes a fresh object with a new ticket.
+    unsafe { __svf_report_realloc(0x7200_9000 as *mut u8, 0x7200_3000 as *mut u8, 8, 13) };
+    unsafe { __svf_report_realloc(std::ptr::null_mut(), 0x7200_4000 as *mut u8, 8, 13) };
+    let (objects, bytes, fresh) = live(13).unwrap();
+    assert_eq!((objects, bytes), (2, 16));
+    assert!(fresh > ticket);
+
+    // a failed realloc leaves the object alone.
+    unsafe { __svf_report_realloc(c as *mut u8, std::ptr::null_mut(), 1024, 14) };
+    assert_eq!(live(12), Some((1, 16, ticket)));
+}
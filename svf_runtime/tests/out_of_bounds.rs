@@ -0,0 +1,34 @@
+//! sized accesses are reported as partial when they overlap a live object without
+//! fitting in it, and as full when they land just past its end.
+
+use svf_runtime::heap::__svf_report_alloc;
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, OobKind, __svf_check_heap_access_sized};
+
+#[test]
+fn sized_accesses_are_bounds_checked() {
+    assert_eq!(OobKind::ALL.map(|k| k.as_str()), ["partial", "full"]);
+
+    let (a, b) = (0x7500_0000usize, 0x7500_1000usize);
+    unsafe {
+        __svf_report_alloc(a as *mut u8, 64, 41);
+        __svf_report_alloc(b as *mut u8, 64, 42);
+
+        // in bounds, up to the last byte.
+        __svf_check_heap_access_sized(a as *const u8, 64, true, 1);
+        __svf_check_heap_access_sized((a + 56) as *const u8, 8, false, 1);
+        // runs past the end of `a`.
+        __svf_check_heap_access_sized((a + 60) as *const u8, 8, true, 2);
+        // starts before `b` and runs into it.
+        __svf_check_heap_access_sized((b - 4) as *const u8, 8, false, 3);
+        // entirely past the end of `a`, within one access width.
+        __svf_check_heap_access_sized((a + 64) as *const u8, 8, true, 4);
+        // too far from any object to blame one.
+        __svf_check_heap_access_sized((a + 0x200) as *const u8, 8, true, 5);
+        // unknown width: no bounds check.
+        __svf_check_heap_access_sized((a + 60) as *const u8, 0, true, 6);
+    }
+
+    let stats = unsafe_heap_stats();
+    assert_eq!((stats.oob_partial, stats.oob_full), (2, 1));
+    assert_eq!(stats.oob_access_ids, [2, 3, 4]);
+}
@@ -0,0 +1,63 @@
+//! with the `preload` feature the test binary's own libc allocations go through
+//! the interposers: uninstrumented objects must be tracked, and instrumented
+//! ones left to the `__svf_report_*` hooks.
+
+#![cfg(feature = "preload")]
+
+use std::ffi::c_void;
+
+use svf_runtime::heap::{bad_free_counts, __svf_report_alloc, __svf_report_dealloc, UNINSTRUMENTED_SITE_ID};
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, __svf_analyze_heap_obj, __svf_check_heap_access};
+
+extern "C" {
+    fn malloc(size: usize) -> *mut c_void;
+    fn calloc(count: usize, size: usize) -> *mut c_void;
+    fn realloc(ptr: *mut c_void, size: usize) -> *mut c_void;
+    fn free(ptr: *mut c_void);
+    fn posix_memalign(memptr: *mut *mut c_void, align: usize, size: usize) -> i32;
+}
+
+/// the site the access checker resolves `addr` to.
+fn resolve(addr: *mut c_void, access_id: u64) -> Option<u64> {
+    unsafe {
+        __svf_analyze_heap_obj(addr as *const u8, 1);
+        __svf_check_heap_access(addr as *const u8, true, access_id);
+    }
+    let stats = unsafe_heap_stats();
+    stats.accesses.iter().find(|a| a.access_id == access_id)?.site_ids.first().copied()
+}
+
+#[test]
+fn libc_allocations_are_tracked() {
+    unsafe {
+        let small = malloc(100);
+        assert_eq!(resolve(small.byte_add(99), 1), Some(UNINSTRUMENTED_SITE_ID));
+        let zeroed = calloc(10, 30);
+        assert_eq!(resolve(zeroed.byte_add(299), 2), Some(UNINSTRUMENTED_SITE_ID));
+        let mut aligned = std::ptr::null_mut();
+        assert_eq!(posix_memalign(&mut aligned, 4096, 5000), 0);
+        assert_eq!(resolve(aligned.byte_add(4999), 3), Some(UNINSTRUMENTED_SITE_ID));
+
+        let grown = realloc(small, 1 << 20);
+        assert_eq!(resolve(grown.byte_add((1 << 20) - 1), 4), Some(UNINSTRUMENTED_SITE_ID));
+        // a failed realloc keeps the object, a zero-sized one frees it.
+        assert!(realloc(grown, usize::MAX / 2).is_null());
+        assert_eq!(resolve(grown.byte_add((1 << 20) - 1), 40), Some(UNINSTRUMENTED_SITE_ID));
+        let tiny = malloc(8);
+        assert!(realloc(tiny, 0).is_null());
+        assert_eq!(resolve(tiny, 41), None);
+        free(grown);
+        free(zeroed);
+        free(aligned);
+        assert_eq!(resolve(aligned, 5), None);
+
+        // an instrumented call site re-attributes the object and owns its free.
+        let instrumented = malloc(64);
+        __svf_report_alloc(instrumented as *mut u8, 64, 42);
+        assert_eq!(resolve(instrumented, 6), Some(42));
+        __svf_report_dealloc(instrumented as *mut u8);
+        free(instrumented);
+        assert_eq!(resolve(instrumented, 7), None);
+    }
+    assert_eq!(bad_free_counts(), [0; 3]);
+}
@@ -0,0 +1,42 @@
+//! the region breakdown works through the hooks alone: the first classification
+//! takes the maps snapshot, without `svf_runtime::init`.
+
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, __svf_analyze_heap_obj, __svf_check_heap_access};
+
+static GLOBAL: [u64; 4] = [1, 2, 3, 4];
+
+extern "C" {
+    /// the environment strings sit at the top of the main thread's stack.
+    static environ: *const *const u8;
+    fn mmap(addr: *mut u8, len: usize, prot: i32, flags: i32, fd: i32, offset: i64) -> *mut u8;
+}
+
+/// `PROT_READ | PROT_WRITE`, `MAP_PRIVATE | MAP_ANONYMOUS`.
+const PROT_RW: i32 = 3;
+const MAP_ANON_PRIVATE: i32 = 0x22;
+
+fn addr<T>(value: &T) -> *const u8 {
+    value as *const T as *const u8
+}
+
+#[test]
+fn regions_are_classified_without_init() {
+    // mapped directly, so the preload interposers do not see it as heap.
+    let mapped = unsafe { mmap(std::ptr::null_mut(), 1 << 20, PROT_RW, MAP_ANON_PRIVATE, -1, 0) };
+    let local = 0u64;
+    unsafe {
+        // TN: nothing predicted.
+        __svf_check_heap_access(addr(&GLOBAL), true, 1);
+        __svf_check_heap_access(addr(&local), true, 2);
+        __svf_check_heap_access(*environ, true, 3);
+        __svf_check_heap_access(mapped, true, 4);
+        // FP: a heap site predicted for a global.
+        __svf_analyze_heap_obj(addr(&GLOBAL), 5);
+        __svf_check_heap_access(addr(&GLOBAL), false, 5);
+    }
+
+    let stats = unsafe_heap_stats();
+    // stack, global, tls, mmap, unknown
+    assert_eq!(stats.tn_by_region, [2, 1, 0, 1, 0]);
+    assert_eq!(stats.fp_by_region, [0, 1, 0, 0, 0]);
+}
@@ -0,0 +1,76 @@
+//! the shadow backend must resolve heap pointers exactly like `LIVE_HEAP`,
+//! including the objects it hands back to it: granules shared by unaligned
+//! objects and objects too large to shadow.
+
+// the backend is fixed by the first allocation, which the preload interposers
+// see long before the test can select it.
+#![cfg(not(feature = "preload"))]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use svf_runtime::heap::{__svf_report_alloc, __svf_report_dealloc, __svf_report_realloc};
+use svf_runtime::shadow::{heap_backend_stats, HeapBackend, HEAP_BACKEND_ENV};
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, __svf_analyze_heap_obj, __svf_check_heap_access};
+
+/// the allocation site of the live object containing `addr`, as the access
+/// checker sees it. addresses are only used as keys, never dereferenced.
+fn resolve(addr: usize) -> Option<u64> {
+    static NEXT_ACCESS: AtomicU64 = AtomicU64::new(1);
+    let access_id = NEXT_ACCESS.fetch_add(1, Ordering::Relaxed);
+    unsafe {
+        // predict a site no object has, so heap hits are FNs that record their site.
+        __svf_analyze_heap_obj(addr as *const u8, u64::MAX);
+        __svf_check_heap_access(addr as *const u8, true, access_id);
+    }
+    let stats = unsafe_heap_stats();
+    let row = stats.accesses.iter().find(|a| a.access_id == access_id).unwrap();
+    row.site_ids.first().copied()
+}
+
+#[test]
+fn shadow_lookups_match_live_heap() {
+    std::env::set_var(HEAP_BACKEND_ENV, "shadow");
+    assert_eq!(heap_backend_stats().backend, HeapBackend::Shadow);
+
+    let a = 0x7000_0000_0000usize;
+    // `b` starts inside the last granule of `a`.
+    let b = a + 100;
+    let large = 0x7100_0000_0000usize;
+    unsafe {
+        __svf_report_alloc(a as *mut u8, 100, 1);
+        __svf_report_alloc(b as *mut u8, 50, 2);
+        __svf_report_alloc(large as *mut u8, 32 << 20, 3);
+    }
+
+    assert_eq!(resolve(a - 1), None);
+    assert_eq!(resolve(a), Some(1));
+    assert_eq!(resolve(a + 99), Some(1));
+    assert_eq!(resolve(b), Some(2));
+    assert_eq!(resolve(b + 49), Some(2));
+    assert_eq!(resolve(b + 50), None);
+    assert_eq!(resolve(large + (20 << 20)), Some(3));
+    assert_eq!(heap_backend_stats().unshadowed_objects, 1);
+
+    unsafe {
+        __svf_report_dealloc(a as *mut u8);
+        __svf_report_dealloc(large as *mut u8);
+    }
+    assert_eq!(heap_backend_stats().unshadowed_objects, 0);
+    assert_eq!(resolve(a + 10), None);
+    assert_eq!(resolve(a + 99), None);
+    assert_eq!(resolve(b), Some(2));
+    assert_eq!(resolve(large + (20 << 20)), None);
+
+    // a moved realloc keeps the site and leaves nothing behind.
+    let moved = 0x7000_1000_0000usize;
+    unsafe { __svf_report_realloc(b as *mut u8, moved as *mut u8, 4096, 0) };
+    assert_eq!(resolve(b), None);
+    assert_eq!(resolve(moved + 4095), Some(2));
+    assert_eq!(resolve(moved + 4096), None);
+    // the same base reported twice replaces the first object.
+    unsafe { __svf_report_alloc(moved as *mut u8, 16, 4) };
+    assert_eq!(resolve(moved), Some(4));
+    assert_eq!(resolve(moved + 32), None);
+
+    assert!(heap_backend_stats().fallback_lookups > 0);
+}
@@ -0,0 +1,35 @@
+//! every classified access lands in its runtime site's row, and every site
+//! predicted for an access that hit none of them counts a false prediction.
+
+use svf_runtime::heap::__svf_report_alloc;
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, __svf_analyze_heap_obj, __svf_check_heap_access};
+
+#[test]
+fn accesses_are_counted_per_site() {
+    let (a, b) = (0x7600_0000usize, 0x7600_1000usize);
+    let not_heap = 0u64;
+    let not_heap = &not_heap as *const u64 as *const u8;
+    unsafe {
+        __svf_report_alloc(a as *mut u8, 64, 51);
+        __svf_report_alloc(b as *mut u8, 64, 52);
+
+        // TP.
+        __svf_analyze_heap_obj(a as *const u8, 51);
+        __svf_check_heap_access(a as *const u8, true, 1);
+        // FN, site mismatch: 52 was predicted instead.
+        __svf_analyze_heap_obj(a as *const u8, 52);
+        __svf_check_heap_access((a + 8) as *const u8, true, 2);
+        // FN, empty prediction.
+        __svf_check_heap_access(b as *const u8, false, 3);
+        // FP.
+        __svf_analyze_heap_obj(not_heap, 51);
+        __svf_check_heap_access(not_heap, true, 4);
+    }
+
+    let stats = unsafe_heap_stats();
+    let rows: Vec<_> = stats.sites.iter().map(|s| (s.site_id, s.tp, s.fn_, s.fp_predictions, s.access_ids, s.tickets)).collect();
+    assert_eq!(rows, [(51, 1, 1, 1, 2, 1), (52, 0, 1, 1, 1, 1)]);
+    assert_eq!(stats.sites[0].recall(), Some(50.0));
+    assert_eq!(stats.sites[1].recall(), Some(0.0));
+    assert_eq!(stats.fp_site_ids, [51]);
+}
@@ -0,0 +1,52 @@
+//! rust allocations made through `SvfTrackingAllocator` must reach `LIVE_HEAP`
+//! under a stable, tagged site id per call path.
+
+use svf_runtime::heap::{bad_free_counts, site_stats};
+use svf_runtime::tracking_alloc::{SvfTrackingAllocator, TRACKED_SITE_TAG};
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, __svf_analyze_heap_obj, __svf_check_heap_access};
+
+#[global_allocator]
+static ALLOC: SvfTrackingAllocator = SvfTrackingAllocator::system();
+
+const BLOCK: usize = 12345;
+const BLOCKS: u64 = 16;
+
+#[inline(never)]
+fn allocate_block() -> Box<[u8]> {
+    vec![7u8; BLOCK].into_boxed_slice()
+}
+
+/// the site the access checker resolves `ptr` to.
+fn resolve(ptr: *const u8, access_id: u64) -> Option<u64> {
+    unsafe {
+        __svf_analyze_heap_obj(ptr, 1);
+        __svf_check_heap_access(ptr, true, access_id);
+    }
+    let stats = unsafe_heap_stats();
+    stats.accesses.iter().find(|a| a.access_id == access_id)?.site_ids.first().copied()
+}
+
+#[test]
+fn rust_allocations_are_tracked() {
+    let blocks: Vec<Box<[u8]>> = (0..BLOCKS).map(|_| allocate_block()).collect();
+
+    let site = resolve(blocks[0].as_ptr(), 1).expect("block not in LIVE_HEAP");
+    assert_ne!(site & TRACKED_SITE_TAG, 0);
+    for (i, block) in blocks.iter().enumerate() {
+        assert_eq!(resolve(block[BLOCK - 1..].as_ptr(), 2 + i as u64), Some(site));
+    }
+
+    drop(blocks);
+    let stats = site_stats().into_iter().find(|&(id, _)| id == site).unwrap().1;
+    assert_eq!((stats.alloc_count, stats.alloc_bytes), (BLOCKS, BLOCKS * BLOCK as u64));
+    assert_eq!((stats.free_count, stats.free_bytes), (BLOCKS, BLOCKS * BLOCK as u64));
+
+    // a grown vec stays one object of its first site.
+    let mut grown: Vec<u64> = Vec::with_capacity(4);
+    let grown_site = resolve(grown.as_ptr() as *const u8, 100).unwrap();
+    grown.extend(0..100_000);
+    assert_eq!(resolve(grown[99_999..].as_ptr() as *const u8, 101), Some(grown_site));
+
+    // blocks the runtime allocated untracked are freed by the test, silently.
+    assert_eq!(bad_free_counts(), [0; 3]);
+}
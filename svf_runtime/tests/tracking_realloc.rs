@@ -0,0 +1,70 @@
+//! a tracked realloc takes the object out of `LIVE_HEAP` before the block can be
+//! freed and reused, and puts it back when the realloc fails.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use svf_runtime::heap::{leak_summary, UNINSTRUMENTED_SITE_ID};
+use svf_runtime::tracking_alloc::{SvfTrackingAllocator, TRACKED_SITE_TAG};
+
+/// (objects, bytes, oldest ticket) over the live objects of tracked sites, leaving
+/// out the test's own libc allocations when built with `preload`.
+fn live() -> (u64, u64, u64) {
+    let tracked = |site_id: u64| site_id & TRACKED_SITE_TAG != 0 && site_id != UNINSTRUMENTED_SITE_ID;
+    let leaks: Vec<_> = leak_summary().into_iter().filter(|l| tracked(l.site_id)).collect();
+    let objects = leaks.iter().map(|l| l.objects).sum();
+    let bytes = leaks.iter().map(|l| l.bytes).sum();
+    (objects, bytes, leaks.iter().map(|l| l.oldest_ticket).min().unwrap_or(0))
+}
+
+/// the system allocator, failing reallocs on request and recording how many
+/// objects were live while it reallocated.
+#[derive(Default)]
+struct Inner {
+    fail: AtomicBool,
+    live_during_realloc: AtomicU64,
+}
+
+unsafe impl GlobalAlloc for Inner {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        self.live_during_realloc.store(live().0, Ordering::Relaxed);
+        if self.fail.load(Ordering::Relaxed) {
+            return std::ptr::null_mut();
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[test]
+fn realloc_takes_the_object_out_first() {
+    let alloc = SvfTrackingAllocator::new(Inner::default());
+    let layout = Layout::from_size_align(64, 8).unwrap();
+    unsafe {
+        let ptr = alloc.alloc(layout);
+        let (_, _, ticket) = live();
+        assert_eq!(live(), (1, 64, ticket));
+
+        let grown = alloc.realloc(ptr, layout, 1 << 20);
+        assert!(!grown.is_null());
+        assert_eq!(alloc.inner().live_during_realloc.load(Ordering::Relaxed), 0);
+        assert_eq!(live(), (1, 1 << 20, ticket));
+
+        // a failed realloc leaves the object where it was.
+        alloc.inner().fail.store(true, Ordering::Relaxed);
+        let layout = Layout::from_size_align(1 << 20, 8).unwrap();
+        assert!(alloc.realloc(grown, layout, 2 << 20).is_null());
+        assert_eq!(alloc.inner().live_during_realloc.load(Ordering::Relaxed), 0);
+        assert_eq!(live(), (1, 1 << 20, ticket));
+
+        alloc.dealloc(grown, layout);
+        assert_eq!(live().0, 0);
+    }
+}
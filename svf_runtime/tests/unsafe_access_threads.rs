@@ -0,0 +1,103 @@
+//! unsafe access sets and tables must not lose updates under contention: every
+//! access from many concurrent threads, exited or still running when the report
+//! is collected, has to show up in the totals.
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+use svf_runtime::heap::__svf_report_alloc;
+use svf_runtime::unsafe_heap_access::{
+    unsafe_heap_stats, AccessClass, UnsafeHeapStats, __svf_analyze_heap_obj, __svf_check_heap_access,
+};
+
+const OBJECTS: usize = 8;
+const OBJECT_SIZE: usize = 64;
+const ROUNDS: usize = 400;
+
+/// per thread: `ROUNDS` TP, FN and FP accesses spread over `OBJECTS` objects of
+/// the thread's own site. access ids are shared by all threads.
+fn run_accesses(t: u64) -> Vec<u8> {
+    let mut buf = vec![0u8; OBJECTS * OBJECT_SIZE];
+    let site = 1000 + t;
+    let not_heap = 0u64;
+    unsafe {
+        for i in 0..OBJECTS {
+            __svf_report_alloc(buf.as_mut_ptr().add(i * OBJECT_SIZE), OBJECT_SIZE, site);
+        }
+        for r in 0..ROUNDS {
+            let obj = buf.as_ptr().add((r % OBJECTS) * OBJECT_SIZE);
+            __svf_analyze_heap_obj(obj, site);
+            __svf_check_heap_access(obj, true, 1);
+            __svf_check_heap_access(obj, false, 2);
+            __svf_analyze_heap_obj(obj, site);
+            __svf_check_heap_access(&not_heap as *const u64 as *const u8, true, 3);
+        }
+    }
+    // keep the objects out of the allocator until the thread is done with them.
+    buf
+}
+
+#[test]
+fn totals_add_up_across_threads() {
+    const LIVE: u64 = 8;
+    const EXITED: u64 = 24;
+
+    let exited: Vec<_> = (0..EXITED).map(|t| thread::spawn(move || run_accesses(t))).collect();
+    for handle in exited {
+        handle.join().unwrap();
+    }
+
+    let done = Arc::new(Barrier::new(LIVE as usize + 1));
+    let release = Arc::new(Barrier::new(LIVE as usize + 1));
+    let live: Vec<_> = (0..LIVE)
+        .map(|t| {
+            let (done, release) = (Arc::clone(&done), Arc::clone(&release));
+            thread::spawn(move || {
+                let _objects = run_accesses(EXITED + t);
+                done.wait();
+                release.wait();
+            })
+        })
+        .collect();
+    done.wait();
+
+    let threads = (EXITED + LIVE) as usize;
+    let per_class = threads * ROUNDS;
+    let expect = |stats: &UnsafeHeapStats| {
+        assert_eq!(stats.dropped_events, 0);
+        assert_eq!((stats.tp, stats.fn_, stats.fp, stats.tn), (per_class, per_class, per_class, 0));
+        assert_eq!(stats.analyzed_sites, threads);
+        assert_eq!(stats.touched_objects, threads * OBJECTS);
+        assert_eq!(stats.matched_objects, threads * OBJECTS);
+        assert_eq!(stats.matched_site_ids.len(), threads);
+        assert_eq!(stats.missed_site_ids.len(), threads);
+        assert_eq!(stats.fp_site_ids.len(), threads);
+
+        assert_eq!(stats.sites.len(), threads);
+        for site in &stats.sites {
+            assert_eq!((site.tp, site.fn_, site.fp_predictions), (ROUNDS as u64, ROUNDS as u64, ROUNDS as u64));
+            assert_eq!((site.access_ids, site.tickets), (2, OBJECTS as u64));
+        }
+
+        let rows: Vec<_> = stats.accesses.iter().map(|a| (a.access_id, a.classes, a.site_ids.len())).collect();
+        let n = per_class as u64;
+        assert_eq!(rows, vec![
+            (1, [n, 0, 0, 0], threads),
+            (2, [0, 0, n, 0], threads),
+            (3, [0, n, 0, 0], 0),
+        ]);
+        let executions: u64 = stats.accesses.iter().map(|a| a.executions()).sum();
+        assert_eq!(executions, 3 * n);
+        assert_eq!(stats.accesses[0].classes[AccessClass::Tp as usize], n);
+    };
+
+    // live workers still hold unflushed events in their buffers.
+    expect(&unsafe_heap_stats());
+
+    release.wait();
+    for handle in live {
+        handle.join().unwrap();
+    }
+    // retiring the workers must not count their events twice.
+    expect(&unsafe_heap_stats());
+}
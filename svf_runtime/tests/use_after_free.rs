@@ -0,0 +1,28 @@
+//! accesses into recently freed objects are use-after-frees, even when smaller
+//! objects freed later start between the object's base and the address.
+
+use svf_runtime::heap::{__svf_report_alloc, __svf_report_dealloc};
+use svf_runtime::unsafe_heap_access::{unsafe_heap_stats, __svf_check_heap_access};
+
+#[test]
+fn freed_objects_behind_later_frees_are_found() {
+    let (big, small) = (0x7400_0000usize, 0x7400_0010usize);
+    unsafe {
+        __svf_report_alloc(big as *mut u8, 256, 31);
+        __svf_report_dealloc(big as *mut u8);
+        // reuses the start of the freed range, then is freed itself.
+        __svf_report_alloc(small as *mut u8, 16, 32);
+        __svf_report_dealloc(small as *mut u8);
+
+        __svf_check_heap_access(small as *const u8, true, 1);
+        // past the small object, still inside the big one.
+        __svf_check_heap_access((big + 0x80) as *const u8, false, 2);
+        // past both.
+        __svf_check_heap_access((big + 256) as *const u8, true, 3);
+    }
+
+    let stats = unsafe_heap_stats();
+    assert_eq!(stats.uaf_accesses, 2);
+    assert_eq!(stats.uaf_site_ids, [31, 32]);
+    assert_eq!(stats.uaf_checks_skipped, 0);
+}